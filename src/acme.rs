@@ -0,0 +1,590 @@
+use axum::{
+    extract::{Path as UrlPath, State},
+    http::StatusCode,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use chrono::Utc;
+use hyper::{
+    body::{self, Bytes},
+    client::HttpConnector,
+    header, Body, Client, Method, Request, Response, Uri,
+};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use rcgen::{Certificate, CertificateParams, DistinguishedName};
+use ring::{
+    digest,
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING},
+};
+use rustls::{ClientConfig, OwnedTrustAnchor, RootCertStore};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    error::AppError,
+    helpers::base64::encode_base64_url,
+    tls::{reload_certificate, CertificateInfo, CertificatePaths, TlsStatus},
+};
+
+pub const LETS_ENCRYPT_DIRECTORY_URL: &str = "https://acme-v02.api.letsencrypt.org/directory";
+pub const DEFAULT_RENEW_BEFORE_DAYS: i64 = 30;
+const RENEWAL_CHECK_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const POLL_ATTEMPTS: usize = 30;
+const ACCOUNT_KEY_FILE: &str = "acme-account.key";
+const BAD_NONCE: &str = "urn:ietf:params:acme:error:badNonce";
+
+/// Settings of the ACME client.
+#[derive(Clone, Debug)]
+pub struct AcmeConfig {
+    pub directory_url: String,
+    pub domains: Vec<String>,
+    pub email: Option<String>,
+    /// Extra root certificate trusted when talking to the directory, e.g. Pebble's test CA.
+    pub ca_cert: Option<PathBuf>,
+    pub renew_before_days: i64,
+}
+
+/// Key authorizations of the HTTP-01 challenges in progress, by token.
+#[derive(Clone, Default)]
+pub struct AcmeChallenges(Arc<RwLock<HashMap<String, String>>>);
+
+impl AcmeChallenges {
+    fn get(&self, token: &str) -> Option<String> {
+        self.0
+            .read()
+            .ok()
+            .and_then(|challenges| challenges.get(token).cloned())
+    }
+
+    fn insert(&self, token: String, key_authorization: String) {
+        if let Ok(mut challenges) = self.0.write() {
+            challenges.insert(token, key_authorization);
+        }
+    }
+
+    fn remove(&self, token: &str) {
+        if let Ok(mut challenges) = self.0.write() {
+            challenges.remove(token);
+        }
+    }
+}
+
+/// Answers the CA's HTTP-01 validation requests on `/.well-known/acme-challenge/:token`.
+pub async fn serve_challenge(
+    State(challenges): State<AcmeChallenges>,
+    UrlPath(token): UrlPath<String>,
+) -> Result<String, StatusCode> {
+    challenges.get(&token).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Whether the certificate on disk is missing, unreadable or about to expire.
+pub fn needs_renewal(paths: &CertificatePaths, renew_before_days: i64) -> bool {
+    match CertificateInfo::from_pem_file(&paths.cert) {
+        Ok(certificate) => {
+            let remaining_days = (certificate.not_after - Utc::now().timestamp()) / (24 * 60 * 60);
+            remaining_days < renew_before_days
+        }
+        Err(_) => true,
+    }
+}
+
+/// Orders a certificate for the configured domains and writes it, with its new private key, to
+/// `paths`. The account key is kept next to them so the same ACME account is reused.
+pub async fn issue_certificate(
+    config: &AcmeConfig,
+    challenges: &AcmeChallenges,
+    paths: &CertificatePaths,
+) -> Result<(), AppError> {
+    tracing::info!(
+        domains = ?config.domains,
+        directory = %config.directory_url,
+        "requesting TLS certificate over ACME"
+    );
+
+    if let Some(dir) = paths.cert.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let account_key = paths.cert.with_file_name(ACCOUNT_KEY_FILE);
+
+    let mut client = AcmeClient::connect(config, &account_key).await?;
+    client.register_account(config.email.as_deref()).await?;
+    let (chain, key) = client
+        .order_certificate(&config.domains, challenges)
+        .await?;
+
+    // both files are in place before either is renamed, and the renames happen under the lock
+    // `reload_certificate` takes, so the new chain is never loaded with the previous key
+    let partial_key = write_partial(&paths.key, key.as_bytes(), 0o600).await?;
+    let partial_cert = write_partial(&paths.cert, chain.as_bytes(), 0o644).await?;
+    {
+        let _swap = paths.lock().await;
+        tokio::fs::rename(&partial_key, &paths.key).await?;
+        tokio::fs::rename(&partial_cert, &paths.cert).await?;
+    }
+
+    tracing::info!(domains = ?config.domains, "obtained TLS certificate over ACME");
+    Ok(())
+}
+
+/// Periodically renews the certificate once it gets within `renew_before_days` of expiring, then
+/// loads it into `rustls`. Failed attempts are retried at the next check.
+pub async fn renew_certificates(
+    config: AcmeConfig,
+    challenges: AcmeChallenges,
+    rustls: RustlsConfig,
+    paths: CertificatePaths,
+    status: TlsStatus,
+) {
+    let mut ticker = tokio::time::interval(RENEWAL_CHECK_INTERVAL);
+
+    loop {
+        ticker.tick().await;
+
+        if !needs_renewal(&paths, config.renew_before_days) {
+            continue;
+        }
+
+        let renewed = match issue_certificate(&config, &challenges, &paths).await {
+            Ok(()) => reload_certificate(&rustls, &paths, &status).await,
+            Err(error) => Err(error),
+        };
+        if let Err(error) = renewed {
+            tracing::error!(
+                ?error,
+                "failed to renew TLS certificate over ACME, keeping the previous one"
+            );
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Directory {
+    new_nonce: String,
+    new_account: String,
+    new_order: String,
+}
+
+#[derive(Deserialize)]
+struct Order {
+    status: String,
+    authorizations: Vec<String>,
+    finalize: String,
+    certificate: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Authorization {
+    status: String,
+    identifier: Identifier,
+    challenges: Vec<Challenge>,
+}
+
+#[derive(Deserialize)]
+struct Identifier {
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct Challenge {
+    #[serde(rename = "type")]
+    kind: String,
+    url: String,
+    #[serde(default)]
+    token: String,
+    error: Option<Problem>,
+}
+
+/// RFC 7807 problem document returned by the CA on errors.
+#[derive(Deserialize)]
+struct Problem {
+    #[serde(rename = "type", default)]
+    kind: String,
+    detail: Option<String>,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.detail {
+            Some(detail) => write!(f, "{}: {detail}", self.kind),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+struct AcmeResponse {
+    location: Option<String>,
+    body: Bytes,
+}
+
+impl AcmeResponse {
+    fn json<T: DeserializeOwned>(&self) -> Result<T, AppError> {
+        serde_json::from_slice(&self.body).map_err(acme_error)
+    }
+
+    fn location(&self) -> Result<String, AppError> {
+        self.location
+            .clone()
+            .ok_or_else(|| acme_error("response is missing the Location header"))
+    }
+}
+
+/// Minimal RFC 8555 client, signing requests with an ES256 account key.
+struct AcmeClient {
+    http: Client<HttpsConnector<HttpConnector>>,
+    directory: Directory,
+    rng: SystemRandom,
+    key: EcdsaKeyPair,
+    jwk: Value,
+    nonce: Option<String>,
+    account_url: Option<String>,
+}
+
+impl AcmeClient {
+    async fn connect(config: &AcmeConfig, account_key: &Path) -> Result<AcmeClient, AppError> {
+        let http = Client::builder().build(https_connector(config.ca_cert.as_deref())?);
+
+        let url: Uri = config.directory_url.parse().map_err(acme_error)?;
+        let response = http.get(url).await.map_err(acme_error)?;
+        let directory = read_response(response).await?.json()?;
+
+        let rng = SystemRandom::new();
+        let key = load_account_key(account_key, &rng).await?;
+        let jwk = jwk(&key);
+
+        Ok(AcmeClient {
+            http,
+            directory,
+            rng,
+            key,
+            jwk,
+            nonce: None,
+            account_url: None,
+        })
+    }
+
+    /// Creates the account, or looks up the existing one bound to the account key.
+    async fn register_account(&mut self, email: Option<&str>) -> Result<(), AppError> {
+        let mut payload = json!({ "termsOfServiceAgreed": true });
+        if let Some(email) = email {
+            payload["contact"] = json!([format!("mailto:{email}")]);
+        }
+
+        let url = self.directory.new_account.clone();
+        let response = self.post(&url, Some(&payload)).await?;
+        self.account_url = Some(response.location()?);
+        Ok(())
+    }
+
+    /// Returns the PEM certificate chain and the PEM private key it was issued for.
+    async fn order_certificate(
+        &mut self,
+        domains: &[String],
+        challenges: &AcmeChallenges,
+    ) -> Result<(String, String), AppError> {
+        let identifiers: Vec<Value> = domains
+            .iter()
+            .map(|domain| json!({ "type": "dns", "value": domain }))
+            .collect();
+        let url = self.directory.new_order.clone();
+        let response = self
+            .post(&url, Some(&json!({ "identifiers": identifiers })))
+            .await?;
+        let order_url = response.location()?;
+        let order: Order = response.json()?;
+
+        for authorization in &order.authorizations {
+            self.authorize(authorization, challenges).await?;
+        }
+        self.wait_for_order(&order_url, "ready").await?;
+
+        let mut params = CertificateParams::new(domains.to_vec());
+        params.distinguished_name = DistinguishedName::new();
+        let certificate = Certificate::from_params(params).map_err(acme_error)?;
+        let csr = certificate.serialize_request_der().map_err(acme_error)?;
+        self.post(
+            &order.finalize,
+            Some(&json!({ "csr": encode_base64_url(&csr) })),
+        )
+        .await?;
+
+        let order = self.wait_for_order(&order_url, "valid").await?;
+        let certificate_url = order
+            .certificate
+            .ok_or_else(|| acme_error("valid order has no certificate URL"))?;
+        let chain = self.post(&certificate_url, None).await?.body;
+        let chain = String::from_utf8(chain.to_vec()).map_err(acme_error)?;
+
+        Ok((chain, certificate.serialize_private_key_pem()))
+    }
+
+    /// Publishes the HTTP-01 key authorization for `url` and waits for the CA to validate it.
+    async fn authorize(&mut self, url: &str, challenges: &AcmeChallenges) -> Result<(), AppError> {
+        let authorization: Authorization = self.post(url, None).await?.json()?;
+        if authorization.status == "valid" {
+            return Ok(());
+        }
+
+        let challenge = authorization
+            .challenges
+            .into_iter()
+            .find(|challenge| challenge.kind == "http-01")
+            .ok_or_else(|| {
+                acme_error(format!(
+                    "no http-01 challenge offered for {}",
+                    authorization.identifier.value
+                ))
+            })?;
+
+        let key_authorization = format!("{}.{}", challenge.token, thumbprint(&self.jwk));
+        challenges.insert(challenge.token.clone(), key_authorization);
+        let validated = self.validate(url, &challenge.url).await;
+        challenges.remove(&challenge.token);
+
+        validated
+    }
+
+    async fn validate(&mut self, url: &str, challenge_url: &str) -> Result<(), AppError> {
+        self.post(challenge_url, Some(&json!({}))).await?;
+
+        for _ in 0..POLL_ATTEMPTS {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            let authorization: Authorization = self.post(url, None).await?.json()?;
+            match authorization.status.as_str() {
+                "valid" => return Ok(()),
+                "pending" => continue,
+                status => {
+                    let problem = authorization
+                        .challenges
+                        .iter()
+                        .find_map(|challenge| challenge.error.as_ref())
+                        .map(|problem| problem.to_string())
+                        .unwrap_or_default();
+                    return Err(acme_error(format!(
+                        "authorization for {} is {status} {problem}",
+                        authorization.identifier.value
+                    )));
+                }
+            }
+        }
+
+        Err(acme_error("timed out waiting for challenge validation"))
+    }
+
+    async fn wait_for_order(&mut self, url: &str, status: &str) -> Result<Order, AppError> {
+        for _ in 0..POLL_ATTEMPTS {
+            let order: Order = self.post(url, None).await?.json()?;
+            if order.status == status {
+                return Ok(order);
+            }
+            if order.status == "invalid" {
+                return Err(acme_error("order became invalid"));
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+
+        Err(acme_error(format!(
+            "timed out waiting for order to be {status}"
+        )))
+    }
+
+    /// Sends a signed request, or a POST-as-GET when `payload` is `None`. Requests rejected
+    /// because of a stale nonce are retried once.
+    async fn post(&mut self, url: &str, payload: Option<&Value>) -> Result<AcmeResponse, AppError> {
+        let mut retried = false;
+
+        loop {
+            let nonce = self.nonce().await?;
+            let body = self.sign(url, &nonce, payload)?;
+            let request = Request::builder()
+                .method(Method::POST)
+                .uri(url)
+                .header(header::CONTENT_TYPE, "application/jose+json")
+                .body(Body::from(body))?;
+            let response = self.http.request(request).await.map_err(acme_error)?;
+
+            self.nonce = response
+                .headers()
+                .get("replay-nonce")
+                .and_then(|nonce| nonce.to_str().ok())
+                .map(|nonce| nonce.to_string());
+
+            match read_response(response).await {
+                Err(AppError::AcmeError(problem)) if problem.starts_with(BAD_NONCE) && !retried => {
+                    retried = true;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn nonce(&mut self) -> Result<String, AppError> {
+        if let Some(nonce) = self.nonce.take() {
+            return Ok(nonce);
+        }
+
+        let request = Request::builder()
+            .method(Method::HEAD)
+            .uri(&self.directory.new_nonce)
+            .body(Body::empty())?;
+        let response = self.http.request(request).await.map_err(acme_error)?;
+        response
+            .headers()
+            .get("replay-nonce")
+            .and_then(|nonce| nonce.to_str().ok())
+            .map(|nonce| nonce.to_string())
+            .ok_or_else(|| acme_error("directory returned no nonce"))
+    }
+
+    /// Wraps `payload` in a flattened JWS, identified by the account URL once registered.
+    fn sign(&self, url: &str, nonce: &str, payload: Option<&Value>) -> Result<Vec<u8>, AppError> {
+        let mut protected = json!({ "alg": "ES256", "nonce": nonce, "url": url });
+        match &self.account_url {
+            Some(account_url) => protected["kid"] = json!(account_url),
+            None => protected["jwk"] = self.jwk.clone(),
+        }
+
+        let protected = encode_base64_url(protected.to_string().as_bytes());
+        let payload = match payload {
+            Some(payload) => encode_base64_url(payload.to_string().as_bytes()),
+            None => String::new(),
+        };
+        let signature = self
+            .key
+            .sign(&self.rng, format!("{protected}.{payload}").as_bytes())?;
+
+        Ok(json!({
+            "protected": protected,
+            "payload": payload,
+            "signature": encode_base64_url(signature.as_ref()),
+        })
+        .to_string()
+        .into_bytes())
+    }
+}
+
+fn https_connector(ca_cert: Option<&Path>) -> Result<HttpsConnector<HttpConnector>, AppError> {
+    let mut roots = RootCertStore::empty();
+    roots.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            anchor.subject,
+            anchor.spki,
+            anchor.name_constraints,
+        )
+    }));
+
+    if let Some(path) = ca_cert {
+        let pem = std::fs::read(path)?;
+        let certificates = rustls_pemfile::certs(&mut pem.as_slice())?;
+        let (added, _) = roots.add_parsable_certificates(&certificates);
+        if added == 0 {
+            return Err(AppError::InvalidCertificate);
+        }
+    }
+
+    let tls = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    Ok(HttpsConnectorBuilder::new()
+        .with_tls_config(tls)
+        .https_or_http()
+        .enable_http1()
+        .build())
+}
+
+async fn read_response(response: Response<Body>) -> Result<AcmeResponse, AppError> {
+    let status = response.status();
+    let location = response
+        .headers()
+        .get(header::LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .map(|location| location.to_string());
+    let body = body::to_bytes(response.into_body())
+        .await
+        .map_err(acme_error)?;
+
+    if !status.is_success() {
+        let problem = serde_json::from_slice::<Problem>(&body)
+            .map(|problem| problem.to_string())
+            .unwrap_or_else(|_| format!("unexpected status {status}"));
+        return Err(AppError::AcmeError(problem));
+    }
+
+    Ok(AcmeResponse { location, body })
+}
+
+/// Loads the PKCS#8 account key, generating it on first use.
+async fn load_account_key(path: &Path, rng: &SystemRandom) -> Result<EcdsaKeyPair, AppError> {
+    let pkcs8 = match tokio::fs::read(path).await {
+        Ok(pkcs8) => pkcs8,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, rng)?;
+            write_file(path, pkcs8.as_ref(), 0o600).await?;
+            tracing::info!(path = %path.display(), "generated ACME account key");
+            pkcs8.as_ref().to_vec()
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &pkcs8)
+        .map_err(|_| acme_error("invalid account key"))
+}
+
+/// Public account key as a JWK. The key is an uncompressed P-256 point: `0x04 || x || y`.
+fn jwk(key: &EcdsaKeyPair) -> Value {
+    let point = key.public_key().as_ref();
+    json!({
+        "crv": "P-256",
+        "kty": "EC",
+        "x": encode_base64_url(&point[1..33]),
+        "y": encode_base64_url(&point[33..65]),
+    })
+}
+
+/// RFC 7638 thumbprint: the SHA-256 of the required JWK members, in lexicographic order.
+fn thumbprint(jwk: &Value) -> String {
+    let canonical = format!(
+        r#"{{"crv":{},"kty":{},"x":{},"y":{}}}"#,
+        jwk["crv"], jwk["kty"], jwk["x"], jwk["y"]
+    );
+    encode_base64_url(digest::digest(&digest::SHA256, canonical.as_bytes()).as_ref())
+}
+
+/// Writes through a temporary file so the certificate watcher never reads a partial file.
+async fn write_file(path: &Path, contents: &[u8], mode: u32) -> Result<(), AppError> {
+    let partial = write_partial(path, contents, mode).await?;
+    tokio::fs::rename(&partial, path).await?;
+    Ok(())
+}
+
+/// Writes `contents` to a temporary file next to `path` and returns its path. `mode` sets the
+/// file's permissions on unix and is ignored elsewhere.
+async fn write_partial(path: &Path, contents: &[u8], mode: u32) -> Result<PathBuf, AppError> {
+    let partial = path.with_extension("tmp");
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(mode);
+    #[cfg(not(unix))]
+    let _ = mode;
+    let mut file = options.open(&partial).await?;
+    file.write_all(contents).await?;
+    file.sync_all().await?;
+    Ok(partial)
+}
+
+fn acme_error(error: impl fmt::Display) -> AppError {
+    AppError::AcmeError(error.to_string())
+}
@@ -2,15 +2,17 @@ use axum::{
     extract::DefaultBodyLimit,
     http::{
         header::{self, AUTHORIZATION, CONTENT_TYPE},
-        HeaderValue, Method,
+        HeaderName, HeaderValue, Method,
     },
+    middleware,
+    response::Redirect,
     routing::{get, patch, post},
     Router,
 };
-use mongodb::Client;
 use std::time::Duration;
 use tower_http::{
     cors::{Any, CorsLayer},
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
     set_header::SetRequestHeaderLayer,
     timeout::TimeoutLayer,
     trace::TraceLayer,
@@ -18,17 +20,33 @@ use tower_http::{
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
-use crate::handlers::{
-    artifacts::{
-        create_artifact, download_artifact, get_artifacts, get_download_headers, get_ios_plist,
-        list_project_artifacts,
+use crate::{
+    handlers::{
+        artifacts::{
+            create_artifact, download_artifact, download_latest_artifact, get_artifact_qrcode,
+            get_artifacts, get_download_headers, get_install_page, get_ios_plist,
+            get_latest_install_page, get_latest_ios_plist, get_latest_qrcode,
+            list_project_artifacts, update_artifact,
+        },
+        channels::{
+            create_channel, get_channel_latest, list_channels, list_promotions, promote_artifact,
+        },
+        health::{get_liveness, get_readiness},
+        metrics::get_metrics,
+        projects::{
+            archive_project, create_project, delete_project, get_project, get_projects,
+            remove_project_image, unarchive_project, update_project, update_project_image,
+        },
+        search::search,
+        updates::check_for_update,
+        users::{create_user, edit_favorite_projects, get_user_data, get_users, login_user},
+        web::{login, login_page, logout, project_page, projects_page},
+        SecurityAddon,
     },
-    projects::{
-        create_project, get_project, get_projects, remove_project_image, update_project,
-        update_project_image,
-    },
-    users::{create_user, edit_favorite_projects, get_user_data, get_users, login_user},
-    SecurityAddon,
+    metrics::track_http_metrics,
+    proxy::resolve_origin,
+    state::AppState,
+    telemetry::{make_request_span, request_context},
 };
 
 #[derive(OpenApi)]
@@ -40,16 +58,35 @@ use crate::handlers::{
         paths(
             crate::handlers::artifacts::get_artifacts,
             crate::handlers::artifacts::create_artifact,
+            crate::handlers::artifacts::update_artifact,
             crate::handlers::artifacts::list_project_artifacts,
             crate::handlers::artifacts::download_artifact,
             crate::handlers::artifacts::get_download_headers,
             crate::handlers::artifacts::get_ios_plist,
+            crate::handlers::artifacts::get_install_page,
+            crate::handlers::artifacts::get_artifact_qrcode,
+            crate::handlers::artifacts::get_latest_install_page,
+            crate::handlers::artifacts::download_latest_artifact,
+            crate::handlers::artifacts::get_latest_ios_plist,
+            crate::handlers::artifacts::get_latest_qrcode,
+            crate::handlers::channels::list_channels,
+            crate::handlers::channels::create_channel,
+            crate::handlers::channels::promote_artifact,
+            crate::handlers::channels::list_promotions,
+            crate::handlers::channels::get_channel_latest,
             crate::handlers::projects::create_project,
             crate::handlers::projects::get_projects,
             crate::handlers::projects::get_project,
             crate::handlers::projects::update_project,
             crate::handlers::projects::update_project_image,
             crate::handlers::projects::remove_project_image,
+            crate::handlers::projects::archive_project,
+            crate::handlers::projects::unarchive_project,
+            crate::handlers::projects::delete_project,
+            crate::handlers::search::search,
+            crate::handlers::updates::check_for_update,
+            crate::handlers::health::get_liveness,
+            crate::handlers::health::get_readiness,
             crate::handlers::users::create_user,
             crate::handlers::users::get_users,
             crate::handlers::users::get_user_data,
@@ -58,7 +95,10 @@ use crate::handlers::{
         ),
         components(
             schemas(
+                crate::error::ErrorResponse,
+                crate::error::FieldError,
                 crate::models::project::BaseProjectInput,
+                crate::models::project::UpdateProjectInput,
                 crate::models::project::Project,
                 crate::models::project::Platforms,
                 crate::models::project::EditImageInput,
@@ -70,22 +110,50 @@ use crate::handlers::{
                 crate::models::user::UserOutput,
                 crate::models::user::UpdateFavoriteProjectsInput,
                 crate::models::artifact::Artifact,
+                crate::models::artifact::BranchArtifacts,
                 crate::models::artifact::ArtifactExtensions,
                 crate::models::artifact::CreateArtifactInput,
                 crate::models::artifact::IosMetadata,
+                crate::models::artifact::AndroidMetadata,
+                crate::models::artifact::BuildInfo,
+                crate::models::artifact::ArtifactDetailsInput,
                 crate::models::artifact::ArtifactBinary,
+                crate::models::channel::Channel,
+                crate::models::channel::Promotion,
+                crate::models::channel::ChannelOutput,
+                crate::models::channel::CreateChannelInput,
+                crate::models::channel::PromoteArtifactInput,
+                crate::models::pagination::PageInfo,
+                crate::models::pagination::ArtifactPage,
+                crate::models::pagination::ProjectPage,
+                crate::models::pagination::UserPage,
+                crate::models::update::UpdateCheck,
+                crate::models::update::AvailableUpdate,
+                crate::models::search::SearchResult,
+                crate::models::search::SearchResultKind,
+                crate::models::health::HealthStatus,
+                crate::models::health::LivenessReport,
+                crate::models::health::CheckResult,
+                crate::models::health::ReadinessReport,
             )
         ),
         modifiers(&SecurityAddon),
         tags(
             (name = "Projects", description = "Projects management API"),
             (name = "Artifacts", description = "Artifacts management API"),
+            (name = "Channels", description = "Release channels and promotion of builds"),
             (name = "Users", description = "Users management API"),
+            (name = "Search", description = "Projects and artifacts search API"),
+            (name = "Updates", description = "Update checks for distributed apps"),
+            (name = "Health", description = "Liveness and readiness probes"),
         )
     )]
 struct ApiDoc;
 
-pub(super) async fn router(db: Client) -> Router {
+const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+const TRACEPARENT: HeaderName = HeaderName::from_static("traceparent");
+
+pub async fn router(state: AppState) -> Router {
     let default_request_body_limit: usize = 2 * 1024 * 1024; // 2MB
     let image_request_body_limit: usize = 5 * 1024 * 1024; // 5MB
     let artifact_request_body_limit: usize = 300 * 1024 * 1024; // 300MB
@@ -98,11 +166,14 @@ pub(super) async fn router(db: Client) -> Router {
             Router::new().route("/", get(get_artifacts)).nest(
                 "/:artifact_id",
                 Router::new()
+                    .route("/", patch(update_artifact))
                     .route(
                         "/download",
                         get(download_artifact).head(get_download_headers),
                     )
-                    .route("/ios-plist", get(get_ios_plist)),
+                    .route("/ios-plist", get(get_ios_plist))
+                    .route("/file", get(get_install_page))
+                    .route("/qrcode", get(get_artifact_qrcode)),
             ),
         )
         .nest(
@@ -112,7 +183,13 @@ pub(super) async fn router(db: Client) -> Router {
                 .nest(
                     "/:project_id",
                     Router::new()
-                        .route("/", get(get_project).patch(update_project))
+                        .route(
+                            "/",
+                            get(get_project)
+                                .patch(update_project)
+                                .delete(delete_project),
+                        )
+                        .route("/archive", post(archive_project).delete(unarchive_project))
                         .route(
                             "/image",
                             patch(update_project_image)
@@ -124,9 +201,29 @@ pub(super) async fn router(db: Client) -> Router {
                             get(list_project_artifacts)
                                 .post(create_artifact)
                                 .route_layer(DefaultBodyLimit::max(artifact_request_body_limit)),
+                        )
+                        .nest(
+                            "/latest",
+                            Router::new()
+                                .route("/", get(get_latest_install_page))
+                                .route("/download", get(download_latest_artifact))
+                                .route("/ios-plist", get(get_latest_ios_plist))
+                                .route("/qrcode", get(get_latest_qrcode)),
+                        )
+                        .nest(
+                            "/channels",
+                            Router::new()
+                                .route("/", get(list_channels).post(create_channel))
+                                .route(
+                                    "/:channel/promotions",
+                                    get(list_promotions).post(promote_artifact),
+                                )
+                                .route("/:channel/latest", get(get_channel_latest)),
                         ),
                 ),
         )
+        .route("/search", get(search))
+        .route("/updates/check", get(check_for_update))
         .nest(
             "/users",
             Router::new()
@@ -135,6 +232,17 @@ pub(super) async fn router(db: Client) -> Router {
                 .route("/me", get(get_user_data))
                 .route("/favorite-projects", patch(edit_favorite_projects)),
         )
+        .route("/", get(|| async { Redirect::to("/web") }))
+        .nest(
+            "/web",
+            Router::new()
+                .route("/", get(projects_page))
+                .route("/projects/:project_id", get(project_page))
+                .route("/login", get(login_page).post(login))
+                .route("/logout", post(logout)),
+        )
+        .route_layer(middleware::from_fn(track_http_metrics))
+        .route("/metrics", get(get_metrics))
         .layer(
             CorsLayer::new()
                 .allow_methods([
@@ -145,15 +253,26 @@ pub(super) async fn router(db: Client) -> Router {
                     Method::DELETE,
                 ])
                 .allow_origin(Any)
-                .allow_headers([CONTENT_TYPE, AUTHORIZATION]),
+                .allow_headers([CONTENT_TYPE, AUTHORIZATION, X_REQUEST_ID, TRACEPARENT])
+                .expose_headers([X_REQUEST_ID, TRACEPARENT]),
         )
         .layer(TimeoutLayer::new(Duration::from_secs(30)))
         .layer(DefaultBodyLimit::max(default_request_body_limit))
-        .layer(TraceLayer::new_for_http())
+        .layer(middleware::from_fn(request_context))
+        .layer(TraceLayer::new_for_http().make_span_with(make_request_span))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            resolve_origin,
+        ))
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
         .layer(SetRequestHeaderLayer::if_not_present(
             header::SERVER,
             server_header,
-        ));
+        ))
+        // probes are merged after the layers above so they don't flood the traces and metrics
+        .route("/healthz", get(get_liveness))
+        .route("/readyz", get(get_readiness));
 
-    app.with_state(db)
+    app.with_state(state)
 }
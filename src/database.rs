@@ -1,28 +1,55 @@
-use bson::doc;
+use bson::Document;
 use mongodb::{
-    options::{ClientOptions, IndexOptions},
-    Client, IndexModel,
+    options::{ClientOptions, FindOptions},
+    Client, Collection,
 };
-use std::env;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
 
-use crate::models::user::User;
+use crate::{
+    error::AppError,
+    models::pagination::{and_filter, Page, PageQuery, SortSpec},
+    telemetry::MongoCommandTracer,
+};
+
+pub mod migrations;
 
-pub async fn connect() -> Result<Client, mongodb::error::Error> {
-    let mongo_uri = env::var("MONGO_URI").expect("Failed to load MONGO_URI");
+/// Builds the client. Run [`migrations::run`] before serving requests with it.
+pub async fn connect(mongo_uri: &str) -> Result<Client, mongodb::error::Error> {
     let mut client_options = ClientOptions::parse(mongo_uri).await?;
     client_options.app_name = Some("AppDist".to_string());
+    client_options.command_event_handler = Some(Arc::new(MongoCommandTracer::default()));
 
-    let client = Client::with_options(client_options)?;
-
-    let user_collection = client.database("appdist").collection::<User>("users");
+    Client::with_options(client_options)
+}
 
-    let options = IndexOptions::builder().unique(true).build();
-    let unique_email_index = IndexModel::builder()
-        .keys(doc! { "email": 1 })
-        .options(options)
+pub async fn find_page<T>(
+    coll: &Collection<T>,
+    filter: Document,
+    page: &PageQuery,
+    sort: SortSpec,
+) -> Result<Page<T>, AppError>
+where
+    T: DeserializeOwned,
+{
+    let limit = page.limit();
+    let filter = match page.cursor_filter(&sort)? {
+        Some(cursor_filter) => and_filter(vec![filter, cursor_filter]),
+        None => filter,
+    };
+    let options = FindOptions::builder()
+        .sort(sort.to_document())
+        .limit(limit + 1)
         .build();
-    user_collection
-        .create_index(unique_email_index, None)
+    let mut cursor = coll
+        .clone_with_type::<Document>()
+        .find(filter, options)
         .await?;
-    Ok(client)
+
+    let mut documents: Vec<Document> = Vec::new();
+    while cursor.advance().await? {
+        documents.push(cursor.deserialize_current()?);
+    }
+
+    Page::from_documents(documents, limit, &sort)
 }
@@ -0,0 +1,331 @@
+use bson::doc;
+use chrono::Utc;
+use mongodb::{
+    error::{ErrorKind, WriteError, WriteFailure},
+    options::IndexOptions,
+    Client, Database, IndexModel,
+};
+
+use crate::error::AppError;
+
+/// Changes to the MongoDB schema, applied once each in this order and recorded in the
+/// `migrations` collection. Applied migrations must never change; add a new one instead.
+#[derive(Clone, Copy)]
+enum Migration {
+    CreateIndexes,
+    ArtifactProjectIdToObjectId,
+    DropStoredQrCodes,
+    CreateChannelIndexes,
+    CreateBundleIdentifierIndexes,
+    CreateTagIndexes,
+    SearchPackageNames,
+}
+
+impl Migration {
+    const ALL: [Migration; 7] = [
+        Migration::CreateIndexes,
+        Migration::ArtifactProjectIdToObjectId,
+        Migration::DropStoredQrCodes,
+        Migration::CreateChannelIndexes,
+        Migration::CreateBundleIdentifierIndexes,
+        Migration::CreateTagIndexes,
+        Migration::SearchPackageNames,
+    ];
+
+    fn version(self) -> i64 {
+        match self {
+            Migration::CreateIndexes => 1,
+            Migration::ArtifactProjectIdToObjectId => 2,
+            Migration::DropStoredQrCodes => 3,
+            Migration::CreateChannelIndexes => 4,
+            Migration::CreateBundleIdentifierIndexes => 5,
+            Migration::CreateTagIndexes => 6,
+            Migration::SearchPackageNames => 7,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Migration::CreateIndexes => "create_indexes",
+            Migration::ArtifactProjectIdToObjectId => "artifact_project_id_to_object_id",
+            Migration::DropStoredQrCodes => "drop_stored_qrcodes",
+            Migration::CreateChannelIndexes => "create_channel_indexes",
+            Migration::CreateBundleIdentifierIndexes => "create_bundle_identifier_indexes",
+            Migration::CreateTagIndexes => "create_tag_indexes",
+            Migration::SearchPackageNames => "search_package_names",
+        }
+    }
+
+    /// A crash between applying a migration and recording it runs it again, and so does
+    /// another instance starting at the same time, so every migration must be idempotent.
+    async fn apply(self, db: &Database) -> Result<(), AppError> {
+        match self {
+            Migration::CreateIndexes => create_indexes(db).await,
+            Migration::ArtifactProjectIdToObjectId => artifact_project_id_to_object_id(db).await,
+            Migration::DropStoredQrCodes => drop_stored_qrcodes(db).await,
+            Migration::CreateChannelIndexes => create_channel_indexes(db).await,
+            Migration::CreateBundleIdentifierIndexes => create_bundle_identifier_indexes(db).await,
+            Migration::CreateTagIndexes => create_tag_indexes(db).await,
+            Migration::SearchPackageNames => search_package_names(db).await,
+        }
+    }
+}
+
+/// Applies the migrations that haven't run yet, oldest first, and returns their names.
+pub async fn run(client: &Client) -> Result<Vec<&'static str>, AppError> {
+    let db = client.database("appdist");
+    let records = db.collection::<bson::Document>("migrations");
+
+    let mut applied = Vec::new();
+    for migration in Migration::ALL {
+        let filter = doc! { "_id": migration.version() };
+        if records.count_documents(filter, None).await? > 0 {
+            continue;
+        }
+
+        tracing::info!(
+            version = migration.version(),
+            name = migration.name(),
+            "applying migration"
+        );
+        migration.apply(&db).await?;
+
+        let record = doc! {
+            "_id": migration.version(),
+            "name": migration.name(),
+            "appliedAt": Utc::now().timestamp_millis(),
+        };
+        match records.insert_one(record, None).await {
+            Ok(_) => (),
+            Err(e) => match *e.kind {
+                // another instance applied it concurrently
+                ErrorKind::Write(WriteFailure::WriteError(WriteError { code: 11000, .. })) => (),
+                _ => return Err(AppError::MongoError(e)),
+            },
+        }
+        applied.push(migration.name());
+    }
+
+    Ok(applied)
+}
+
+/// Indexes every query relies on. Creating an index that already exists does nothing.
+async fn create_indexes(db: &Database) -> Result<(), AppError> {
+    let users = db.collection::<bson::Document>("users");
+    users
+        .create_indexes(
+            [
+                IndexModel::builder()
+                    .keys(doc! { "email": 1 })
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+                IndexModel::builder()
+                    .keys(doc! { "favoriteProjects": 1 })
+                    .build(),
+            ],
+            None,
+        )
+        .await?;
+
+    let projects = db.collection::<bson::Document>("projects");
+    projects
+        .create_indexes(
+            [
+                IndexModel::builder()
+                    .keys(doc! { "name": "text", "description": "text" })
+                    .options(
+                        IndexOptions::builder()
+                            .name("project_text_search".to_string())
+                            .weights(doc! { "name": 10, "description": 2 })
+                            .build(),
+                    )
+                    .build(),
+                IndexModel::builder()
+                    .keys(doc! { "owner": 1, "name": 1 })
+                    .build(),
+            ],
+            None,
+        )
+        .await?;
+
+    let artifacts = db.collection::<bson::Document>("artifacts");
+    artifacts
+        .create_indexes(
+            [
+                IndexModel::builder()
+                    .keys(doc! {
+                        "branch": "text",
+                        "identifier": "text",
+                        "originalFilename": "text",
+                        "iosMetadata.bundleIdentifier": "text",
+                    })
+                    .options(
+                        IndexOptions::builder()
+                            .name("artifact_text_search".to_string())
+                            .weights(doc! {
+                                "branch": 5,
+                                "identifier": 5,
+                                "originalFilename": 2,
+                                "iosMetadata.bundleIdentifier": 2,
+                            })
+                            .build(),
+                    )
+                    .build(),
+                IndexModel::builder()
+                    .keys(doc! { "projectId": 1, "branch": 1, "createdAt": -1 })
+                    .build(),
+                IndexModel::builder()
+                    .keys(doc! { "createdAt": 1, "_id": 1 })
+                    .build(),
+            ],
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Artifacts used to reference their project by its id as a hex string. Strings that aren't
+/// valid ids are left as they are and reported, since `$toObjectId` would fail the whole update.
+async fn artifact_project_id_to_object_id(db: &Database) -> Result<(), AppError> {
+    let artifacts = db.collection::<bson::Document>("artifacts");
+    let result = artifacts
+        .update_many(
+            doc! { "projectId": { "$type": "string", "$regex": "^[0-9a-fA-F]{24}$" } },
+            vec![doc! { "$set": { "projectId": { "$toObjectId": "$projectId" } } }],
+            None,
+        )
+        .await?;
+    tracing::info!(
+        converted = result.modified_count,
+        "converted artifact project ids"
+    );
+
+    let invalid = artifacts
+        .count_documents(doc! { "projectId": { "$type": "string" } }, None)
+        .await?;
+    if invalid > 0 {
+        tracing::warn!(
+            invalid,
+            "left artifacts whose project id is not a valid object id unconverted"
+        );
+    }
+    Ok(())
+}
+
+/// QR codes used to be rendered at upload and stored in every artifact as a base64 SVG;
+/// they are now rendered on request.
+async fn drop_stored_qrcodes(db: &Database) -> Result<(), AppError> {
+    let artifacts = db.collection::<bson::Document>("artifacts");
+    let result = artifacts
+        .update_many(
+            doc! { "qrcode": { "$exists": true } },
+            doc! { "$unset": { "qrcode": "" } },
+            None,
+        )
+        .await?;
+    tracing::info!(dropped = result.modified_count, "dropped stored qr codes");
+    Ok(())
+}
+
+/// Channel names are unique within a project, and a channel's history is read newest first.
+async fn create_channel_indexes(db: &Database) -> Result<(), AppError> {
+    let channels = db.collection::<bson::Document>("channels");
+    channels
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "projectId": 1, "name": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            None,
+        )
+        .await?;
+
+    let promotions = db.collection::<bson::Document>("promotions");
+    promotions
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "projectId": 1, "channel": 1, "promotedAt": -1 })
+                .build(),
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Update checks look the newest builds of an app up by bundle identifier or package name.
+async fn create_bundle_identifier_indexes(db: &Database) -> Result<(), AppError> {
+    let artifacts = db.collection::<bson::Document>("artifacts");
+    artifacts
+        .create_indexes(
+            [
+                IndexModel::builder()
+                    .keys(doc! { "iosMetadata.bundleIdentifier": 1, "createdAt": -1 })
+                    .build(),
+                IndexModel::builder()
+                    .keys(doc! { "androidMetadata.packageName": 1, "createdAt": -1 })
+                    .build(),
+            ],
+            None,
+        )
+        .await?;
+    Ok(())
+}
+
+/// Artifacts are filtered by tag and by metadata entry. Metadata keys are free-form, so they
+/// get a wildcard index.
+async fn create_tag_indexes(db: &Database) -> Result<(), AppError> {
+    let artifacts = db.collection::<bson::Document>("artifacts");
+    artifacts
+        .create_indexes(
+            [
+                IndexModel::builder()
+                    .keys(doc! { "tags": 1, "createdAt": -1 })
+                    .build(),
+                IndexModel::builder()
+                    .keys(doc! { "metadata.$**": 1 })
+                    .build(),
+            ],
+            None,
+        )
+        .await?;
+    Ok(())
+}
+
+/// Android package names are searched like iOS bundle identifiers. A collection has a single
+/// text index, so the artifact one is replaced.
+async fn search_package_names(db: &Database) -> Result<(), AppError> {
+    let artifacts = db.collection::<bson::Document>("artifacts");
+    let names = artifacts.list_index_names().await?;
+    if names.iter().any(|name| name == "artifact_text_search") {
+        artifacts.drop_index("artifact_text_search", None).await?;
+    }
+    artifacts
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! {
+                    "branch": "text",
+                    "identifier": "text",
+                    "originalFilename": "text",
+                    "iosMetadata.bundleIdentifier": "text",
+                    "androidMetadata.packageName": "text",
+                })
+                .options(
+                    IndexOptions::builder()
+                        .name("artifact_text_search".to_string())
+                        .weights(doc! {
+                            "branch": 5,
+                            "identifier": 5,
+                            "originalFilename": 2,
+                            "iosMetadata.bundleIdentifier": 2,
+                            "androidMetadata.packageName": 2,
+                        })
+                        .build(),
+                )
+                .build(),
+            None,
+        )
+        .await?;
+    Ok(())
+}
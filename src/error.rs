@@ -1,15 +1,21 @@
+use serde::Serialize;
 use thiserror::Error;
+use utoipa::ToSchema;
 
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Mongo failed to complete operation {}", .0)]
     MongoError(#[from] mongodb::error::Error),
+    #[error("SQLite failed to complete operation {}", .0)]
+    SqliteError(#[from] rusqlite::Error),
     #[error("Failed to decode form-data field")]
     MultipartError(#[from] axum::extract::multipart::MultipartError),
     #[error("Image error")]
     ImageError(#[from] image::ImageError),
     #[error("QrCode error")]
     QrCodeError(#[from] qrcode_generator::QRCodeError),
+    #[error("Failed to render page: {}", .0)]
+    TemplateError(#[from] askama::Error),
     #[error("Invalid iOS metadata")]
     InvalidIosMetadata,
     #[error("Failed to insert data")]
@@ -18,6 +24,14 @@ pub enum AppError {
     FileMissing,
     #[error("Not found")]
     NotFound,
+    #[error("Validation failed")]
+    Validation(Vec<FieldError>),
+    #[error("Project is archived")]
+    ProjectArchived,
+    #[error("An artifact with this identifier already exists")]
+    DuplicateIdentifier,
+    #[error("Server is shutting down")]
+    ShuttingDown,
     #[error("User already registered")]
     UserAlreadyRegistered,
     #[error("Invalid credentials")]
@@ -30,8 +44,30 @@ pub enum AppError {
     SystemTimeError(#[from] std::time::SystemTimeError),
     #[error("IO Error")]
     IOError(#[from] std::io::Error),
+    #[error("Invalid pagination cursor")]
+    InvalidCursor,
+    #[error("Invalid sort field {}", .0)]
+    InvalidSortField(String),
+    #[error("Invalid filter {}", .0)]
+    InvalidFilter(String),
+    #[error("Missing search terms")]
+    MissingSearchTerms,
+    #[error("Failed to serialize document")]
+    BsonSerialization(#[from] bson::ser::Error),
+    #[error("Failed to deserialize document")]
+    BsonDeserialization(#[from] bson::de::Error),
     #[error("Invalid ObjectId")]
     ObjectIdParsingError(#[from] mongodb::bson::oid::Error),
+    #[error("Couldn't determine the public URL of the server")]
+    UnknownPublicUrl,
+    #[error("Invalid TLS certificate")]
+    InvalidCertificate,
+    #[error("ACME certificate provisioning failed: {}", .0)]
+    AcmeError(String),
+    #[error("Failed to set up tracing: {}", .0)]
+    TelemetryError(#[from] opentelemetry::trace::TraceError),
+    #[error("Failed to collect metrics: {}", .0)]
+    MetricsError(#[from] prometheus::Error),
     #[error("Unknown error")]
     AxumError(#[from] axum::http::Error),
     #[error("Unknown error")]
@@ -43,3 +79,77 @@ pub enum AppError {
     #[error("Unknown error")]
     Never, // kinda like Typescript never type
 }
+
+impl AppError {
+    /// Stable, machine-readable identifier of the error, safe for clients to match on.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::MongoError(_) | AppError::SqliteError(_) => "database_error",
+            AppError::MultipartError(_) => "invalid_multipart",
+            AppError::ImageError(_) => "invalid_image",
+            AppError::QrCodeError(_) => "qrcode_generation_failed",
+            AppError::TemplateError(_) => "template_error",
+            AppError::InvalidIosMetadata => "invalid_ios_metadata",
+            AppError::FailedInsertion => "insertion_failed",
+            AppError::FileMissing => "file_missing",
+            AppError::NotFound => "not_found",
+            AppError::Validation(_) => "validation_failed",
+            AppError::ProjectArchived => "project_archived",
+            AppError::DuplicateIdentifier => "duplicate_identifier",
+            AppError::ShuttingDown => "shutting_down",
+            AppError::UserAlreadyRegistered => "user_already_registered",
+            AppError::InvalidCredentials => "invalid_credentials",
+            AppError::Unauthorized => "unauthorized",
+            AppError::Forbidden => "forbidden",
+            AppError::SystemTimeError(_) => "system_time_error",
+            AppError::IOError(e) if e.kind() == std::io::ErrorKind::NotFound => "file_not_found",
+            AppError::IOError(_) => "io_error",
+            AppError::InvalidCursor => "invalid_cursor",
+            AppError::InvalidSortField(_) => "invalid_sort_field",
+            AppError::InvalidFilter(_) => "invalid_filter",
+            AppError::MissingSearchTerms => "missing_search_terms",
+            AppError::BsonSerialization(_) => "serialization_error",
+            AppError::BsonDeserialization(_) => "deserialization_error",
+            AppError::ObjectIdParsingError(_) => "invalid_object_id",
+            AppError::UnknownPublicUrl => "unknown_public_url",
+            AppError::InvalidCertificate => "invalid_certificate",
+            AppError::AcmeError(_) => "acme_error",
+            AppError::TelemetryError(_) => "telemetry_error",
+            AppError::MetricsError(_) => "metrics_error",
+            AppError::AxumError(_) => "http_error",
+            AppError::Unspecified(_) => "crypto_error",
+            AppError::Decode(_) => "decode_error",
+            AppError::Encode(_) => "token_encoding_error",
+            AppError::Never => "internal_error",
+        }
+    }
+}
+
+/// Body of every error response returned by the API.
+#[derive(Serialize, ToSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorResponse {
+    /// Stable, machine-readable error code, e.g. `not_found` or `validation_failed`.
+    pub code: String,
+    /// Human readable description of the error.
+    pub message: String,
+    /// Field-level details, present on validation errors.
+    pub details: Option<Vec<FieldError>>,
+    /// Identifier of the request that failed, to be quoted when reporting issues.
+    pub request_id: Option<String>,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: &str) -> FieldError {
+        FieldError {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
@@ -1,68 +1,84 @@
+use askama::Template;
 use axum::{
-    body::{self, boxed, Bytes, StreamBody},
-    extract::{Multipart, Path, State},
-    http::{Response, StatusCode},
-    response::IntoResponse,
-    Json,
-};
-use bson::oid::ObjectId;
-use mongodb::{
-    bson::doc,
-    options::{AggregateOptions, FindOneOptions, FindOptions, InsertOneOptions, UpdateOptions},
-    Client, Collection,
+    body::{self, boxed, BoxBody, Bytes, StreamBody},
+    extract::{Multipart, Path, Query, State},
+    headers::UserAgent,
+    http::{header, HeaderMap, Response, StatusCode},
+    response::{Html, IntoResponse, Redirect},
+    Json, TypedHeader,
 };
+use futures::StreamExt;
+use once_cell::sync::Lazy;
 use qrcode_generator::QrCodeEcc;
+use ring::digest;
+use std::{collections::BTreeMap, io, sync::Arc};
+use tokio::sync::Mutex;
 use tokio_util::io::ReaderStream;
 
 use crate::{
+    config::{Config, DuplicateIdentifierPolicy},
     error::AppError,
     helpers::{
         artifact::{
             create_file_url, create_itms_service_url, parse_plist_template, write_file_to_disk,
         },
-        base64::encode_base64,
+        base64::encode_base64_url,
+        install_page::{Device, InstallPage},
     },
-    models::artifact::{
-        Artifact, ArtifactExtensions, ArtifactToCreate, CreateArtifact, IosMetadata,
+    metrics::METRICS,
+    models::{
+        artifact::{
+            AndroidMetadata, Artifact, ArtifactDetailsInput, ArtifactExtensions, ArtifactToCreate,
+            CreateArtifact, IosMetadata, LatestArtifactQuery,
+        },
+        pagination::{ArtifactFilters, PageQuery, SortSpec},
+        project::{Platforms, Project},
+        qrcode::{QrCodeFormat, QrCodeQuery},
+        user::Claims,
     },
+    proxy::PublicUrl,
+    repositories::Repositories,
+    state::Draining,
 };
 
-const DB_NAME: &str = "appdist";
-const COLLECTION_NAME: &str = "artifacts";
+use super::{
+    projects::find_managed_project,
+    web::{signed_in_user, Session},
+};
+
+const SORT_FIELDS: [&str; 4] = ["createdAt", "branch", "identifier", "size"];
+
+/// Held from the duplicate check to the insert when duplicates are rejected, so concurrent
+/// uploads of the same identifier can't both pass the check.
+static DUPLICATE_CHECK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
 
 /// List all artifacts
 ///
-/// List all artifacts in the database.
+/// List artifacts in the database, one page at a time. Sortable by `createdAt`, `branch`, `identifier` and `size`; newest first by default.
 #[utoipa::path(
     get,
     path = "/artifacts",
     tag = "Artifacts",
+    params(PageQuery, ArtifactFilters),
     responses(
-        (status = 200, description = "Listed artifacts successfully", body = [Artifact])
+        (status = 200, description = "Listed artifacts successfully", body = ArtifactPage),
+        (status = 400, description = "Invalid cursor, sort field or filter", body = ErrorResponse)
     )
 )]
 pub(crate) async fn get_artifacts(
-    State(client): State<Client>,
+    State(repos): State<Repositories>,
+    Query(page): Query<PageQuery>,
+    Query(filters): Query<ArtifactFilters>,
 ) -> Result<impl IntoResponse, AppError> {
-    let coll: Collection<Artifact> = client
-        .database(DB_NAME)
-        .collection::<Artifact>(COLLECTION_NAME);
-
-    let options = FindOptions::default();
-    let mut cursor = coll.find(None, options).await?;
-
-    let mut rows: Vec<Artifact> = Vec::new();
-
-    while cursor.advance().await? {
-        rows.push(cursor.deserialize_current()?);
-    }
+    let sort = page.sort_spec(&SORT_FIELDS, "-createdAt")?;
+    let rows = repos.artifacts.find_page(&filters, &page, sort).await?;
 
     Ok((StatusCode::OK, Json(rows)).into_response())
 }
 
 /// Create new artifact
 ///
-/// Tries to store a new artifact in disk and save relevant data in database or fails with 400 if it can't be done.
+/// Tries to store a new artifact in disk and save relevant data in database or fails with 400 if it can't be done. An identifier already used on the branch is handled according to `DUPLICATE_IDENTIFIER_POLICY`: rejected with 409, replacing the older artifacts, or kept alongside them.
 #[utoipa::path(
     post,
     tag = "Projects",
@@ -73,16 +89,35 @@ pub(crate) async fn get_artifacts(
     ),
     responses(
         (status = 201, description = "Artifact created and stored successfully", body = Artifact),
-        (status = 400, description = "Bad Request")
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+        (status = 409, description = "Project is archived, or the identifier is taken and duplicates are rejected", body = ErrorResponse),
+        (status = 422, description = "Invalid release notes or build information", body = ErrorResponse),
+        (status = 503, description = "Server is shutting down", body = ErrorResponse)
     )
 )]
+#[allow(clippy::redundant_pattern_matching)]
 pub(crate) async fn create_artifact(
-    State(client): State<Client>,
+    State(repos): State<Repositories>,
+    State(config): State<Arc<Config>>,
+    State(draining): State<Draining>,
     Path(project_id): Path<String>,
     mut payload: Multipart,
 ) -> Result<impl IntoResponse, AppError> {
+    if draining.is_draining() {
+        return Err(AppError::ShuttingDown);
+    }
+
+    match repos.projects.find_by_id(&project_id).await? {
+        Some(Project { archived: true, .. }) => return Err(AppError::ProjectArchived),
+        Some(_) => (),
+        None => return Err(AppError::NotFound),
+    }
+
     let mut artifact_to_create = CreateArtifact::default();
     let mut ios_metadata = IosMetadata::default();
+    let mut android_metadata = AndroidMetadata::default();
+    let mut details = ArtifactDetailsInput::default();
     let mut file_content: Option<Bytes> = None;
     while let Some(field) = payload.next_field().await? {
         match field.name() {
@@ -90,6 +125,26 @@ pub(crate) async fn create_artifact(
             Some("identifier") => artifact_to_create.identifier = Some(field.text().await?),
             Some("bundle_identifier") => ios_metadata.bundle_identifier = field.text().await?,
             Some("bundle_version") => ios_metadata.bundle_version = field.text().await?,
+            Some("package_name") => android_metadata.package_name = field.text().await?,
+            Some("version_code") => android_metadata.version_code = field.text().await?,
+            Some("release_notes") => details.release_notes = Some(field.text().await?),
+            Some("commit_sha") => details.commit_sha = Some(field.text().await?),
+            Some("commit_message") => details.commit_message = Some(field.text().await?),
+            Some("build_url") => details.build_url = Some(field.text().await?),
+            Some("author") => details.author = Some(field.text().await?),
+            Some("tags") => {
+                let tags = field.text().await?;
+                let tags = tags.split(',').map(|tag| tag.trim().to_string());
+                details.tags.get_or_insert_with(Vec::new).extend(tags);
+            }
+            Some(name) if name.starts_with("metadata.") => {
+                let key = name["metadata.".len()..].to_string();
+                let value = field.text().await?;
+                details
+                    .metadata
+                    .get_or_insert_with(BTreeMap::new)
+                    .insert(key, value);
+            }
             Some("file") => {
                 let file_name = match field.file_name() {
                     Some(v) => v.to_string(),
@@ -127,6 +182,9 @@ pub(crate) async fn create_artifact(
             ArtifactExtensions::Ipa => {
                 artifact_to_create.metadata = Some(ios_metadata);
             }
+            _ if !android_metadata.package_name.is_empty() => {
+                artifact_to_create.android_metadata = Some(android_metadata);
+            }
             _ => (),
         }
     }
@@ -135,39 +193,109 @@ pub(crate) async fn create_artifact(
         return Err(AppError::FileMissing);
     }
 
-    let artifact_to_create = ArtifactToCreate::new(artifact_to_create, project_id)?;
-    let coll: Collection<Artifact> = client
-        .database(DB_NAME)
-        .collection::<Artifact>(COLLECTION_NAME);
-    let new_artifact = Artifact::new(artifact_to_create)?;
-
-    let options = InsertOneOptions::default();
-    let insert_result = coll.insert_one(&new_artifact, options).await?;
-
-    if let Some(oid) = insert_result.inserted_id.as_object_id() {
-        let inserted_id = oid.to_string();
-        let url = match new_artifact.get_extension() {
-            ArtifactExtensions::Ipa => create_itms_service_url(inserted_id),
-            _ => create_file_url(inserted_id),
-        };
-        let qrcode = qrcode_generator::to_svg_to_string(url, QrCodeEcc::Low, 240, None::<&str>)?;
-        let mut encoded_code = String::from("data:image/svg+xml;base64,");
-        encoded_code.push_str(encode_base64(qrcode.as_bytes())?.as_str());
-        let filter = doc! { "_id": oid };
-        let update = doc! { "$set": doc! { "qrcode": encoded_code } };
-        let options = UpdateOptions::default();
-        coll.update_one(filter, update, options).await?;
-        if let Some(file_content) = file_content {
-            let content_vec = file_content.to_vec();
-            write_file_to_disk(new_artifact.get_path(), content_vec.as_slice())?;
+    let errors = details.validate();
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+
+    let artifact_to_create =
+        ArtifactToCreate::new(artifact_to_create, project_id, &config.uploads_path)?;
+    let mut new_artifact = Artifact::new(artifact_to_create)?;
+    new_artifact.set_details(details);
+
+    let _duplicate_check = match config.duplicate_identifier_policy {
+        DuplicateIdentifierPolicy::Reject => Some(DUPLICATE_CHECK.lock().await),
+        _ => None,
+    };
+    let duplicates = repos.artifacts.find_duplicates(&new_artifact).await?;
+    if !duplicates.is_empty()
+        && config.duplicate_identifier_policy == DuplicateIdentifierPolicy::Reject
+    {
+        return Err(AppError::DuplicateIdentifier);
+    }
+
+    // the file goes first, so an interrupted upload never leaves a record without its file
+    if let Some(file_content) = file_content {
+        let content_vec = file_content.to_vec();
+        write_file_to_disk(new_artifact.get_path(), content_vec.as_slice())?;
+        METRICS.uploaded_bytes.inc_by(content_vec.len() as u64);
+    }
+    if let Err(error) = repos.artifacts.insert(&new_artifact).await {
+        let _ = tokio::fs::remove_file(new_artifact.get_path()).await;
+        return Err(error);
+    }
+
+    if config.duplicate_identifier_policy == DuplicateIdentifierPolicy::Replace {
+        for duplicate in duplicates {
+            replace_artifact(&repos, &duplicate, &new_artifact).await?;
         }
+    }
 
-        Ok((StatusCode::CREATED, Json(new_artifact)).into_response())
-    } else {
-        Err(AppError::FailedInsertion)
+    Ok((StatusCode::CREATED, Json(new_artifact)).into_response())
+}
+
+/// Removes `old` in favor of `new`, which takes its place in release channels.
+async fn replace_artifact(
+    repos: &Repositories,
+    old: &Artifact,
+    new: &Artifact,
+) -> Result<(), AppError> {
+    repos
+        .channels
+        .reassign_promotions(old.get_id(), new.get_id())
+        .await?;
+    repos.artifacts.delete(old.get_id()).await?;
+    match tokio::fs::remove_file(old.get_path()).await {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
     }
 }
 
+/// Update artifact details
+///
+/// Edits the release notes, build information, tags and metadata of an artifact: fields present in the body are changed, empty ones removed. Only the project owner or an admin can edit them.
+#[utoipa::path(
+    patch,
+    tag = "Artifacts",
+    path = "/artifacts/{artifact_id}",
+    request_body = ArtifactDetailsInput,
+    params(
+        ("artifact_id" = String, Path, description = "id of the artifact")
+    ),
+    responses(
+        (status = 204, description = "Artifact updated successfully"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Forbidden", body = ErrorResponse),
+        (status = 404, description = "Artifact not found", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+)]
+pub(crate) async fn update_artifact(
+    State(repos): State<Repositories>,
+    claims: Claims,
+    Path(artifact_id): Path<String>,
+    Json(payload): Json<ArtifactDetailsInput>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut artifact = match repos.artifacts.find_by_id(&artifact_id).await? {
+        Some(artifact) => artifact,
+        None => return Err(AppError::NotFound),
+    };
+    find_managed_project(&repos, &claims, artifact.get_project_id()).await?;
+
+    let errors = payload.validate();
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+
+    artifact.set_details(payload);
+    repos.artifacts.update_details(&artifact).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
 /// List artifacts by project
 ///
 /// List all artifacts that belongs to a project.
@@ -179,52 +307,14 @@ pub(crate) async fn create_artifact(
         ("project_id" = String, Path, description = "id of the project that the artifact belongs to")
     ),
     responses(
-        (status = 200, description = "Found artifacts", body = [Artifact]),
+        (status = 200, description = "Found artifacts, grouped by branch", body = [BranchArtifacts]),
     )
 )]
 pub(crate) async fn list_project_artifacts(
-    State(client): State<Client>,
+    State(repos): State<Repositories>,
     Path(project_id): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
-    let coll: Collection<Artifact> = client
-        .database(DB_NAME)
-        .collection::<Artifact>(COLLECTION_NAME);
-
-    let pipeline = vec![
-        doc! {
-            "$match": doc! {
-              "projectId": project_id,
-            },
-        },
-        doc! {
-          "$project": doc! {
-            "_id": 1,
-            "type": 1,
-            "createdAt": 1,
-            "branch": 1,
-            "originalFilename": 1,
-            "identifier": 1,
-            "iosMetadata": 1,
-            "qrcode": 1,
-          },
-        },
-        doc! {
-          "$group": doc! {
-            "_id": "$branch",
-            "artifacts": {
-              "$push": "$$ROOT",
-            },
-          },
-        },
-    ];
-    let options = AggregateOptions::default();
-    let mut cursor = coll.aggregate(pipeline, options).await?;
-
-    let mut rows = Vec::new();
-
-    while cursor.advance().await? {
-        rows.push(cursor.deserialize_current()?);
-    }
+    let rows = repos.artifacts.find_by_branch(&project_id).await?;
 
     Ok((StatusCode::OK, Json(rows)).into_response())
 }
@@ -240,24 +330,22 @@ pub(crate) async fn list_project_artifacts(
         ("artifact_id" = String, Path, description = "id of the artifact")
     ),
     responses(
-        (status = 200, description = "Downloaded successfully", body = ArtifactBinary, content_type = "application/octet-stream")
+        (status = 200, description = "Downloaded successfully", body = ArtifactBinary, content_type = "application/octet-stream"),
+        (status = 404, description = "Artifact not found", body = ErrorResponse)
     )
 )]
 pub(crate) async fn download_artifact(
-    State(client): State<Client>,
+    State(repos): State<Repositories>,
     Path(artifact_id): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
-    let coll: Collection<Artifact> = client
-        .database(DB_NAME)
-        .collection::<Artifact>(COLLECTION_NAME);
-
-    let options = FindOneOptions::default();
-    let oid = ObjectId::parse_str(artifact_id)?;
-    let filter = doc! { "_id": oid };
-    if let Some(artifact) = coll.find_one(filter, options).await? {
+    if let Some(artifact) = repos.artifacts.find_by_id(&artifact_id).await? {
         let path = artifact.get_path();
         let file = tokio::fs::File::open(path).await?;
-        let stream = ReaderStream::new(file);
+        let stream = ReaderStream::new(file).inspect(|chunk| {
+            if let Ok(bytes) = chunk {
+                METRICS.downloaded_bytes.inc_by(bytes.len() as u64);
+            }
+        });
         let body = StreamBody::new(stream);
         let response = Response::builder()
             .status(StatusCode::OK)
@@ -280,21 +368,15 @@ pub(crate) async fn download_artifact(
         ("artifact_id" = String, Path, description = "id of the artifact")
     ),
     responses(
-        (status = 200, description = "Fetched download data successfully")
+        (status = 200, description = "Fetched download data successfully"),
+        (status = 404, description = "Artifact not found", body = ErrorResponse)
     )
 )]
 pub(crate) async fn get_download_headers(
-    State(client): State<Client>,
+    State(repos): State<Repositories>,
     Path(artifact_id): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
-    let coll: Collection<Artifact> = client
-        .database(DB_NAME)
-        .collection::<Artifact>(COLLECTION_NAME);
-
-    let options = FindOneOptions::default();
-    let oid = ObjectId::parse_str(artifact_id)?;
-    let filter = doc! { "_id": oid };
-    if let Some(artifact) = coll.find_one(filter, options).await? {
+    if let Some(artifact) = repos.artifacts.find_by_id(&artifact_id).await? {
         let (original_filename, mime_type, size) = artifact.get_download_data();
         let response = Response::builder()
             .status(StatusCode::OK)
@@ -323,53 +405,44 @@ pub(crate) async fn get_download_headers(
         ("artifact_id" = String, Path, description = "id of the artifact")
     ),
     responses(
-        (status = 200, description = "Generated plist successfully", body = String)
+        (status = 200, description = "Generated plist successfully", body = String),
+        (status = 400, description = "Artifact is not an iOS build", body = ErrorResponse),
+        (status = 404, description = "Artifact not found", body = ErrorResponse)
     )
 )]
 pub(crate) async fn get_ios_plist(
-    State(client): State<Client>,
+    State(repos): State<Repositories>,
+    PublicUrl(public_url): PublicUrl,
     Path(artifact_id): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
-    let coll: Collection<Artifact> = client
-        .database(DB_NAME)
-        .collection::<Artifact>(COLLECTION_NAME);
-
-    let options = AggregateOptions::default();
-    let oid = ObjectId::parse_str(artifact_id)?;
-    let pipeline = vec![
-        doc! {
-            "$match": doc! { "_id": oid }
-        },
-        doc! {
-            "$addFields": doc! {
-                "convertedProjectId": doc! { "$toObjectId": "$projectId" }
-            }
-        },
-        doc! {
-            "$lookup": doc! {
-                "from": "projects",
-                "localField": "convertedProjectId",
-                "foreignField": "_id",
-                "as": "project"
-            }
-        },
-        doc! {
-            "$unwind": "$project"
-        },
-        doc! {
-            "$limit": 1,
-        },
-    ];
-
-    let artifact = coll
-        .aggregate(pipeline, options)
-        .await?
-        .with_type::<Artifact>()
-        .deserialize_current()?;
-
-    let (artifact_id, bundle_identifier, bundle_version, app_name) = artifact.get_plist_data()?;
+    let artifact = match repos.artifacts.find_by_id(&artifact_id).await? {
+        Some(artifact) => artifact,
+        None => return Err(AppError::NotFound),
+    };
+    ios_plist_response(&repos, &public_url, &artifact).await
+}
 
-    let plist = parse_plist_template(&artifact_id, &bundle_identifier, &bundle_version, &app_name);
+/// The manifest `itms-services` links point to, for an iOS artifact.
+async fn ios_plist_response(
+    repos: &Repositories,
+    public_url: &str,
+    artifact: &Artifact,
+) -> Result<Response<BoxBody>, AppError> {
+    let project = match repos.projects.find_by_id(artifact.get_project_id()).await? {
+        Some(project) => project,
+        None => return Err(AppError::NotFound),
+    };
+
+    let (artifact_id, bundle_identifier, bundle_version, app_name) =
+        artifact.get_plist_data(&project)?;
+
+    let plist = parse_plist_template(
+        public_url,
+        &artifact_id,
+        &bundle_identifier,
+        &bundle_version,
+        &app_name,
+    );
 
     let response = Response::builder()
         .status(StatusCode::OK)
@@ -379,3 +452,326 @@ pub(crate) async fn get_ios_plist(
 
     Ok(response.into_response())
 }
+
+/// Artifact install page
+///
+/// Mobile-friendly page that artifact QR codes point to, offering the install action that fits the device.
+#[utoipa::path(
+    get,
+    path = "/artifacts/{artifact_id}/file",
+    tag = "Artifacts",
+    params(
+        ("artifact_id" = String, Path, description = "id of the artifact")
+    ),
+    responses(
+        (status = 200, description = "Rendered install page", body = String, content_type = "text/html"),
+        (status = 404, description = "Artifact not found", body = ErrorResponse)
+    )
+)]
+pub(crate) async fn get_install_page(
+    State(repos): State<Repositories>,
+    Session(claims): Session,
+    PublicUrl(public_url): PublicUrl,
+    Path(artifact_id): Path<String>,
+    user_agent: Option<TypedHeader<UserAgent>>,
+) -> Result<impl IntoResponse, AppError> {
+    let artifact = match repos.artifacts.find_by_id(&artifact_id).await? {
+        Some(artifact) => artifact,
+        None => return Err(AppError::NotFound),
+    };
+    let project = match repos.projects.find_by_id(artifact.get_project_id()).await? {
+        Some(project) => project,
+        None => return Err(AppError::NotFound),
+    };
+
+    let (platform, install_url) = match artifact.get_extension() {
+        ArtifactExtensions::Ipa => (
+            Device::Ios,
+            create_itms_service_url(&public_url, artifact_id),
+        ),
+        _ => (
+            Device::Android,
+            format!("{public_url}/artifacts/{artifact_id}/download"),
+        ),
+    };
+    let device = match &user_agent {
+        Some(TypedHeader(user_agent)) => Device::from_user_agent(user_agent.as_str()),
+        None => Device::Other,
+    };
+    let (file_name, _, _) = artifact.get_download_data();
+    let user = signed_in_user(&repos, &claims).await?;
+
+    let page = InstallPage {
+        user: user.map(|user| user.name),
+        project_name: &project.name,
+        project_image: project.image.as_deref(),
+        identifier: artifact.get_identifier(),
+        branch: artifact.get_branch(),
+        bundle_version: artifact
+            .get_ios_metadata()
+            .map(|metadata| metadata.bundle_version.as_str()),
+        created_at: artifact.get_created_at(),
+        file_name,
+        release_notes: artifact.get_release_notes().map(String::as_str),
+        build_info: artifact.get_build_info(),
+        tags: artifact.get_tags(),
+        install_url,
+        platform,
+        device,
+    };
+
+    Ok(Html(page.render()?).into_response())
+}
+
+/// Artifact QR code
+///
+/// QR code pointing to the artifact's install link, rendered on request as SVG or PNG. Responses carry an `ETag` and may be cached for an hour.
+#[utoipa::path(
+    get,
+    path = "/artifacts/{artifact_id}/qrcode",
+    tag = "Artifacts",
+    params(
+        ("artifact_id" = String, Path, description = "id of the artifact"),
+        QrCodeQuery
+    ),
+    responses(
+        (status = 200, description = "Rendered QR code", content_type = "image/svg+xml"),
+        (status = 304, description = "QR code unchanged since the `If-None-Match` ETag"),
+        (status = 404, description = "Artifact not found", body = ErrorResponse),
+        (status = 422, description = "Invalid size", body = ErrorResponse)
+    )
+)]
+pub(crate) async fn get_artifact_qrcode(
+    State(repos): State<Repositories>,
+    PublicUrl(public_url): PublicUrl,
+    Path(artifact_id): Path<String>,
+    Query(query): Query<QrCodeQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let artifact = match repos.artifacts.find_by_id(&artifact_id).await? {
+        Some(artifact) => artifact,
+        None => return Err(AppError::NotFound),
+    };
+
+    // the link depends on the public URL of this request, so it is never stored
+    let url = match artifact.get_extension() {
+        ArtifactExtensions::Ipa => create_itms_service_url(&public_url, artifact_id),
+        _ => create_file_url(&public_url, artifact_id),
+    };
+    qrcode_response(&url, &query, &headers)
+}
+
+/// Renders a QR code for `url`, or answers 304 if the client already has it.
+fn qrcode_response(
+    url: &str,
+    query: &QrCodeQuery,
+    headers: &HeaderMap,
+) -> Result<Response<BoxBody>, AppError> {
+    let errors = query.validate();
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+    let (format, size, ecc) = (query.format(), query.size(), query.ecc());
+
+    let fingerprint = format!("{url}|{format:?}|{size}|{ecc:?}");
+    let hash = digest::digest(&digest::SHA256, fingerprint.as_bytes());
+    let etag = format!("\"{}\"", encode_base64_url(&hash.as_ref()[..16]));
+    let cache_headers = [
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, "public, max-age=3600".to_string()),
+    ];
+
+    let unchanged = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|tag| tag.trim() == etag));
+    if unchanged {
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
+    }
+
+    let ecc = QrCodeEcc::from(ecc);
+    let (content_type, image) = match format {
+        QrCodeFormat::Svg => (
+            "image/svg+xml",
+            qrcode_generator::to_svg_to_string(url, ecc, size, None::<&str>)?.into_bytes(),
+        ),
+        QrCodeFormat::Png => (
+            "image/png",
+            qrcode_generator::to_png_to_vec(url, ecc, size)?,
+        ),
+    };
+
+    Ok((
+        StatusCode::OK,
+        cache_headers,
+        [(header::CONTENT_TYPE, content_type)],
+        image,
+    )
+        .into_response())
+}
+
+/// Latest artifact install page
+///
+/// Redirects to the install page of the newest artifact of the project, optionally only among those built from a branch or for a platform. The link stays the same as new builds are uploaded.
+#[utoipa::path(
+    get,
+    path = "/projects/{project_id}/latest",
+    tag = "Artifacts",
+    params(
+        ("project_id" = String, Path, description = "id of the project"),
+        LatestArtifactQuery
+    ),
+    responses(
+        (status = 307, description = "Redirect to the install page of the newest matching artifact"),
+        (status = 404, description = "Project or matching artifact not found", body = ErrorResponse)
+    )
+)]
+pub(crate) async fn get_latest_install_page(
+    State(repos): State<Repositories>,
+    PublicUrl(public_url): PublicUrl,
+    Path(project_id): Path<String>,
+    Query(query): Query<LatestArtifactQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let artifact = find_latest(&repos, &project_id, &query).await?;
+    let url = create_file_url(&public_url, artifact.get_id().clone());
+
+    Ok(Redirect::temporary(&url))
+}
+
+/// Download latest artifact
+///
+/// Redirects to the download of the newest artifact of the project, optionally only among those built from a branch or for a platform.
+#[utoipa::path(
+    get,
+    path = "/projects/{project_id}/latest/download",
+    tag = "Artifacts",
+    params(
+        ("project_id" = String, Path, description = "id of the project"),
+        LatestArtifactQuery
+    ),
+    responses(
+        (status = 307, description = "Redirect to the download of the newest matching artifact"),
+        (status = 404, description = "Project or matching artifact not found", body = ErrorResponse)
+    )
+)]
+pub(crate) async fn download_latest_artifact(
+    State(repos): State<Repositories>,
+    PublicUrl(public_url): PublicUrl,
+    Path(project_id): Path<String>,
+    Query(query): Query<LatestArtifactQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let artifact = find_latest(&repos, &project_id, &query).await?;
+    let url = format!("{public_url}/artifacts/{}/download", artifact.get_id());
+
+    Ok(Redirect::temporary(&url))
+}
+
+/// Latest iOS artifact plist
+///
+/// Manifest of the newest iOS artifact of the project, optionally only among those built from a branch, for `itms-services` links that keep installing the newest build.
+#[utoipa::path(
+    get,
+    path = "/projects/{project_id}/latest/ios-plist",
+    tag = "Artifacts",
+    params(
+        ("project_id" = String, Path, description = "id of the project"),
+        LatestArtifactQuery
+    ),
+    responses(
+        (status = 200, description = "Generated plist successfully", body = String),
+        (status = 404, description = "Project or matching artifact not found", body = ErrorResponse)
+    )
+)]
+pub(crate) async fn get_latest_ios_plist(
+    State(repos): State<Repositories>,
+    PublicUrl(public_url): PublicUrl,
+    Path(project_id): Path<String>,
+    Query(mut query): Query<LatestArtifactQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    query.platform = Some(Platforms::Ios);
+    let artifact = find_latest(&repos, &project_id, &query).await?;
+
+    ios_plist_response(&repos, &public_url, &artifact).await
+}
+
+/// Latest artifact QR code
+///
+/// QR code of the stable link to the newest artifact of the project, so a printed code keeps installing the newest build. For `platform=ios` it installs directly through `itms-services`.
+#[utoipa::path(
+    get,
+    path = "/projects/{project_id}/latest/qrcode",
+    tag = "Artifacts",
+    params(
+        ("project_id" = String, Path, description = "id of the project"),
+        LatestArtifactQuery,
+        QrCodeQuery
+    ),
+    responses(
+        (status = 200, description = "Rendered QR code", content_type = "image/svg+xml"),
+        (status = 304, description = "QR code unchanged since the `If-None-Match` ETag"),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+        (status = 422, description = "Invalid size", body = ErrorResponse)
+    )
+)]
+pub(crate) async fn get_latest_qrcode(
+    State(repos): State<Repositories>,
+    PublicUrl(public_url): PublicUrl,
+    Path(project_id): Path<String>,
+    Query(query): Query<LatestArtifactQuery>,
+    Query(qrcode): Query<QrCodeQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    if repos.projects.find_by_id(&project_id).await?.is_none() {
+        return Err(AppError::NotFound);
+    }
+
+    let latest = format!("{public_url}/projects/{project_id}/latest");
+    let url = match query.platform {
+        Some(Platforms::Ios) => {
+            let plist_url = format!("{latest}/ios-plist{}", query.to_query_string()?);
+            let params = [("action", "download-manifest"), ("url", &plist_url)];
+            let params = serde_urlencoded::to_string(params).map_err(|_| AppError::Never)?;
+            format!("itms-services://?{params}")
+        }
+        _ => format!("{latest}{}", query.to_query_string()?),
+    };
+    qrcode_response(&url, &qrcode, &headers)
+}
+
+/// The newest artifact of a project matching the query.
+async fn find_latest(
+    repos: &Repositories,
+    project_id: &str,
+    query: &LatestArtifactQuery,
+) -> Result<Artifact, AppError> {
+    if repos.projects.find_by_id(project_id).await?.is_none() {
+        return Err(AppError::NotFound);
+    }
+
+    let filters = ArtifactFilters {
+        project_id: Some(project_id.to_string()),
+        branch: query.branch.clone(),
+        extension: None,
+        created_after: None,
+        created_before: None,
+        platform: query.platform,
+        bundle_identifier: None,
+        tag: None,
+        metadata: None,
+    };
+    let page = PageQuery {
+        limit: Some(1),
+        cursor: None,
+        sort: None,
+    };
+    let sort = SortSpec {
+        field: "createdAt".to_string(),
+        descending: true,
+    };
+    let rows = repos.artifacts.find_page(&filters, &page, sort).await?;
+
+    match rows.data.into_iter().next() {
+        Some(artifact) => Ok(artifact),
+        None => Err(AppError::NotFound),
+    }
+}
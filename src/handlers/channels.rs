@@ -0,0 +1,237 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+use crate::{
+    error::{AppError, FieldError},
+    models::{
+        channel::{Channel, ChannelOutput, CreateChannelInput, PromoteArtifactInput, Promotion},
+        project::Project,
+        user::Claims,
+    },
+    repositories::Repositories,
+};
+
+use super::projects::find_managed_project;
+
+/// List project channels
+///
+/// List the release channels of a project by name, each with the build it currently offers.
+#[utoipa::path(
+    get,
+    tag = "Channels",
+    path = "/projects/{project_id}/channels",
+    params(
+        ("project_id" = String, Path, description = "id of the project")
+    ),
+    responses(
+        (status = 200, description = "Listed channels successfully", body = [ChannelOutput]),
+        (status = 404, description = "Project not found", body = ErrorResponse)
+    )
+)]
+pub(crate) async fn list_channels(
+    State(repos): State<Repositories>,
+    Path(project_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let project = find_project(&repos, &project_id).await?;
+
+    let mut rows = Vec::new();
+    for channel in repos.channels.find_by_project(&project.id).await? {
+        let latest = repos
+            .channels
+            .latest_promotion(&project.id, &channel.name)
+            .await?;
+        rows.push(ChannelOutput { channel, latest });
+    }
+
+    Ok((StatusCode::OK, Json(rows)).into_response())
+}
+
+/// Create channel
+///
+/// Adds a release channel to a project. Only the project owner or an admin can create channels; fails with 422 if the name is invalid or taken.
+#[utoipa::path(
+    post,
+    tag = "Channels",
+    path = "/projects/{project_id}/channels",
+    request_body = CreateChannelInput,
+    params(
+        ("project_id" = String, Path, description = "id of the project")
+    ),
+    responses(
+        (status = 201, description = "Channel created successfully", body = Channel),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Forbidden", body = ErrorResponse),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+)]
+pub(crate) async fn create_channel(
+    State(repos): State<Repositories>,
+    claims: Claims,
+    Path(project_id): Path<String>,
+    Json(payload): Json<CreateChannelInput>,
+) -> Result<impl IntoResponse, AppError> {
+    let project = find_managed_project(&repos, &claims, &project_id).await?;
+
+    let errors = payload.validate();
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+
+    let channel = Channel::new(payload, project.id);
+    if !repos.channels.insert(&channel).await? {
+        return Err(AppError::Validation(vec![FieldError::new(
+            "name",
+            "A channel with this name already exists",
+        )]));
+    }
+
+    Ok((StatusCode::CREATED, Json(channel)).into_response())
+}
+
+/// Promote artifact
+///
+/// Promotes an artifact of the project into a channel, making it the build the channel offers. The user and time are kept in the channel's history.
+#[utoipa::path(
+    post,
+    tag = "Channels",
+    path = "/projects/{project_id}/channels/{channel}/promotions",
+    request_body = PromoteArtifactInput,
+    params(
+        ("project_id" = String, Path, description = "id of the project"),
+        ("channel" = String, Path, description = "name of the channel")
+    ),
+    responses(
+        (status = 201, description = "Artifact promoted successfully", body = Promotion),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Project or channel not found", body = ErrorResponse),
+        (status = 409, description = "Project is archived", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+)]
+pub(crate) async fn promote_artifact(
+    State(repos): State<Repositories>,
+    claims: Claims,
+    Path((project_id, channel)): Path<(String, String)>,
+    Json(payload): Json<PromoteArtifactInput>,
+) -> Result<impl IntoResponse, AppError> {
+    let project = find_project(&repos, &project_id).await?;
+    if project.archived {
+        return Err(AppError::ProjectArchived);
+    }
+    let channel = find_channel(&repos, &project.id, &channel).await?;
+
+    let mut errors = payload.validate();
+    if errors.is_empty() {
+        match repos.artifacts.find_by_id(&payload.artifact_id).await? {
+            Some(artifact) if artifact.get_project_id() == &project.id => (),
+            _ => errors.push(FieldError::new(
+                "artifactId",
+                "No artifact with this id in the project",
+            )),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+
+    let promotion = Promotion::new(payload, &channel, claims.user_id);
+    repos.channels.insert_promotion(&promotion).await?;
+
+    Ok((StatusCode::CREATED, Json(promotion)).into_response())
+}
+
+/// List channel history
+///
+/// List every promotion into a channel, newest first.
+#[utoipa::path(
+    get,
+    tag = "Channels",
+    path = "/projects/{project_id}/channels/{channel}/promotions",
+    params(
+        ("project_id" = String, Path, description = "id of the project"),
+        ("channel" = String, Path, description = "name of the channel")
+    ),
+    responses(
+        (status = 200, description = "Listed promotions successfully", body = [Promotion]),
+        (status = 404, description = "Project or channel not found", body = ErrorResponse)
+    )
+)]
+pub(crate) async fn list_promotions(
+    State(repos): State<Repositories>,
+    Path((project_id, channel)): Path<(String, String)>,
+) -> Result<impl IntoResponse, AppError> {
+    let project = find_project(&repos, &project_id).await?;
+    let channel = find_channel(&repos, &project.id, &channel).await?;
+    let rows = repos
+        .channels
+        .promotions(&project.id, &channel.name)
+        .await?;
+
+    Ok((StatusCode::OK, Json(rows)).into_response())
+}
+
+/// Get latest in channel
+///
+/// Get the artifact most recently promoted into a channel. Fails with 404 if nothing was promoted yet.
+#[utoipa::path(
+    get,
+    tag = "Channels",
+    path = "/projects/{project_id}/channels/{channel}/latest",
+    params(
+        ("project_id" = String, Path, description = "id of the project"),
+        ("channel" = String, Path, description = "name of the channel")
+    ),
+    responses(
+        (status = 200, description = "Found the latest artifact", body = Artifact),
+        (status = 404, description = "Project or channel not found, or nothing promoted yet", body = ErrorResponse)
+    )
+)]
+pub(crate) async fn get_channel_latest(
+    State(repos): State<Repositories>,
+    Path((project_id, channel)): Path<(String, String)>,
+) -> Result<impl IntoResponse, AppError> {
+    let project = find_project(&repos, &project_id).await?;
+    let channel = find_channel(&repos, &project.id, &channel).await?;
+    let promotion = match repos
+        .channels
+        .latest_promotion(&project.id, &channel.name)
+        .await?
+    {
+        Some(promotion) => promotion,
+        None => return Err(AppError::NotFound),
+    };
+
+    match repos.artifacts.find_by_id(&promotion.artifact_id).await? {
+        Some(artifact) => Ok((StatusCode::OK, Json(artifact)).into_response()),
+        None => Err(AppError::NotFound),
+    }
+}
+
+async fn find_project(repos: &Repositories, project_id: &str) -> Result<Project, AppError> {
+    match repos.projects.find_by_id(project_id).await? {
+        Some(project) => Ok(project),
+        None => Err(AppError::NotFound),
+    }
+}
+
+async fn find_channel(
+    repos: &Repositories,
+    project_id: &str,
+    name: &str,
+) -> Result<Channel, AppError> {
+    match repos.channels.find(project_id, name).await? {
+        Some(channel) => Ok(channel),
+        None => Err(AppError::NotFound),
+    }
+}
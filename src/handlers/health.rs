@@ -0,0 +1,127 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use chrono::Utc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::{
+    models::health::{CheckResult, HealthStatus, LivenessReport, ReadinessReport},
+    state::AppState,
+};
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Liveness probe
+///
+/// Reports that the process is up and serving requests.
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "Health",
+    responses(
+        (status = 200, description = "Process is alive", body = LivenessReport)
+    )
+)]
+pub(crate) async fn get_liveness() -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        Json(LivenessReport {
+            status: HealthStatus::Ok,
+        }),
+    )
+}
+
+/// Readiness probe
+///
+/// Checks that the server isn't shutting down, that the database answers pings, that the uploads directory is writable and that the TLS certificate is loaded and not about to expire.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "Health",
+    responses(
+        (status = 200, description = "Ready to serve traffic", body = ReadinessReport),
+        (status = 503, description = "At least one check failed", body = ReadinessReport)
+    )
+)]
+pub(crate) async fn get_readiness(State(state): State<AppState>) -> impl IntoResponse {
+    let report = check_readiness(&state).await;
+    let status = if report.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report))
+}
+
+pub(crate) async fn check_readiness(state: &AppState) -> ReadinessReport {
+    ReadinessReport::new(vec![
+        check_shutdown(state),
+        check_backend(state).await,
+        check_uploads(state).await,
+        check_certificate(state),
+    ])
+}
+
+fn check_shutdown(state: &AppState) -> CheckResult {
+    if state.draining.is_draining() {
+        CheckResult::fail("shutdown", "Server is shutting down".to_string())
+    } else {
+        CheckResult::pass("shutdown", "Server is accepting requests".to_string())
+    }
+}
+
+async fn check_backend(state: &AppState) -> CheckResult {
+    let backend = &state.repos.backend;
+    match tokio::time::timeout(CHECK_TIMEOUT, backend.ping()).await {
+        Ok(Ok(_)) => CheckResult::pass(backend.name(), "Ping succeeded".to_string()),
+        Ok(Err(e)) => CheckResult::fail(backend.name(), format!("Ping failed: {e}")),
+        Err(_) => CheckResult::fail(backend.name(), "Ping timed out".to_string()),
+    }
+}
+
+async fn check_uploads(state: &AppState) -> CheckResult {
+    let base_path = &state.config.uploads_path;
+    let probe = base_path.join(format!(".readyz-{}", Uuid::new_v4()));
+    let result = match tokio::fs::write(&probe, b"ok").await {
+        Ok(_) => tokio::fs::remove_file(&probe).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(_) => CheckResult::pass("uploads", format!("{} is writable", base_path.display())),
+        Err(e) => CheckResult::fail(
+            "uploads",
+            format!("{} is not writable: {e}", base_path.display()),
+        ),
+    }
+}
+
+fn check_certificate(state: &AppState) -> CheckResult {
+    let (tls, tls_config) = match (&state.tls, &state.config.tls) {
+        (Some(tls), Some(tls_config)) => (tls, tls_config),
+        _ => return CheckResult::pass("tls", "TLS is terminated by a proxy".to_string()),
+    };
+    let certificate = match tls.get() {
+        Some(certificate) => certificate,
+        None => return CheckResult::fail("tls", "No certificate loaded".to_string()),
+    };
+
+    let threshold_days = tls_config.expiry_threshold_days;
+    let remaining_days = (certificate.not_after - Utc::now().timestamp()) / (24 * 60 * 60);
+
+    if remaining_days < threshold_days {
+        CheckResult::fail(
+            "tls",
+            format!(
+                "Certificate for {} expires in {remaining_days} days",
+                certificate.subject
+            ),
+        )
+    } else {
+        CheckResult::pass(
+            "tls",
+            format!(
+                "Certificate for {} expires in {remaining_days} days",
+                certificate.subject
+            ),
+        )
+    }
+}
@@ -0,0 +1,68 @@
+use axum::{
+    extract::State,
+    headers::{authorization::Bearer, Authorization},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    TypedHeader,
+};
+use ring::constant_time::verify_slices_are_equal;
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{
+    config::Config, error::AppError, helpers::storage::project_disk_usage, metrics::METRICS,
+    repositories::Repositories,
+};
+
+/// Exposes metrics in the Prometheus text format. When a metrics token is configured, scrapers must
+/// send it as a bearer token.
+pub(crate) async fn get_metrics(
+    State(repos): State<Repositories>,
+    State(config): State<Arc<Config>>,
+    authorization: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Result<impl IntoResponse, AppError> {
+    if let Some(token) = &config.metrics_token {
+        let authorized = match authorization {
+            Some(TypedHeader(Authorization(bearer))) => {
+                verify_slices_are_equal(bearer.token().as_bytes(), token.as_bytes()).is_ok()
+            }
+            None => false,
+        };
+        if !authorized {
+            return Err(AppError::Unauthorized);
+        }
+    }
+
+    refresh_storage_metrics(&repos, config.uploads_path.clone()).await?;
+
+    let (content_type, body) = METRICS.render()?;
+    Ok((StatusCode::OK, [(header::CONTENT_TYPE, content_type)], body).into_response())
+}
+
+/// Artifact counts come from the database; storage is measured on disk, so files without a
+/// record, such as overwritten uploads, are counted too.
+async fn refresh_storage_metrics(
+    repos: &Repositories,
+    uploads_path: PathBuf,
+) -> Result<(), AppError> {
+    let rows = repos.artifacts.storage_by_project().await?;
+    let usage = tokio::task::spawn_blocking(move || project_disk_usage(&uploads_path))
+        .await
+        .map_err(|_| AppError::Never)??;
+
+    METRICS.project_artifacts.reset();
+    METRICS.project_storage_bytes.reset();
+    for row in rows {
+        METRICS
+            .project_artifacts
+            .with_label_values(&[&row.project_id])
+            .set(row.artifacts);
+    }
+    for (project_id, bytes) in usage {
+        METRICS
+            .project_storage_bytes
+            .with_label_values(&[&project_id])
+            .set(bytes as i64);
+    }
+
+    Ok(())
+}
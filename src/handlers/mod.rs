@@ -1,29 +1,39 @@
 use axum::{
     async_trait,
-    body::{self, BoxBody, Full},
-    extract::{FromRequestParts, TypedHeader},
+    body::BoxBody,
+    extract::{FromRef, FromRequestParts, TypedHeader},
     headers::{authorization::Bearer, Authorization},
     http::{request::Parts, StatusCode},
     response::{IntoResponse, Response},
-    RequestPartsExt,
+    Json, RequestPartsExt,
 };
 use jsonwebtoken::{decode, DecodingKey, Validation};
-use std::env;
+use std::{io, sync::Arc};
 use utoipa::{
     openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
     Modify,
 };
 
-use crate::{error::AppError, models::user::Claims};
+use crate::{
+    config::Config,
+    error::{AppError, ErrorResponse},
+    models::user::Claims,
+    telemetry::current_request_id,
+};
 
 pub(super) mod artifacts;
+pub(super) mod channels;
+pub(crate) mod health;
+pub(super) mod metrics;
 pub(super) mod projects;
+pub(super) mod search;
+pub(super) mod updates;
 pub(super) mod users;
+pub(super) mod web;
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response<BoxBody> {
-        let (status, message) = match self {
-            AppError::MongoError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        let (status, message) = match &self {
             AppError::MultipartError(e) => (StatusCode::BAD_REQUEST, e.to_string()),
             AppError::InvalidCredentials => {
                 (StatusCode::BAD_REQUEST, "Invalid credentials".to_string())
@@ -37,8 +47,43 @@ impl IntoResponse for AppError {
             }
             AppError::FileMissing => (StatusCode::BAD_REQUEST, "File is missing".to_string()),
             AppError::ObjectIdParsingError(e) => (StatusCode::BAD_REQUEST, e.to_string()),
+            AppError::InvalidCursor => (
+                StatusCode::BAD_REQUEST,
+                "Invalid pagination cursor".to_string(),
+            ),
+            AppError::MissingSearchTerms => {
+                (StatusCode::BAD_REQUEST, "Missing search terms".to_string())
+            }
+            AppError::InvalidSortField(field) => {
+                (StatusCode::BAD_REQUEST, format!("Cannot sort by {field}"))
+            }
+            AppError::InvalidFilter(filter) => {
+                (StatusCode::BAD_REQUEST, format!("Invalid {filter} filter"))
+            }
+            AppError::UnknownPublicUrl => (
+                StatusCode::BAD_REQUEST,
+                "Couldn't determine the public URL, set PUBLIC_URL or send a Host header"
+                    .to_string(),
+            ),
             AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized".to_string()),
             AppError::Forbidden => (StatusCode::FORBIDDEN, "Forbidden".to_string()),
+            AppError::NotFound => (StatusCode::NOT_FOUND, "Not found".to_string()),
+            AppError::IOError(e) if e.kind() == io::ErrorKind::NotFound => {
+                (StatusCode::NOT_FOUND, "File not found".to_string())
+            }
+            AppError::ProjectArchived => (StatusCode::CONFLICT, "Project is archived".to_string()),
+            AppError::DuplicateIdentifier => (
+                StatusCode::CONFLICT,
+                "An artifact with this identifier already exists on this branch".to_string(),
+            ),
+            AppError::ShuttingDown => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Server is shutting down, retry shortly".to_string(),
+            ),
+            AppError::Validation(_) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Validation failed".to_string(),
+            ),
             AppError::ImageError(_) => (
                 StatusCode::UNPROCESSABLE_ENTITY,
                 "Couldn't parse image".to_string(),
@@ -49,39 +94,60 @@ impl IntoResponse for AppError {
             ),
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "Unkown error".to_string(),
+                "Internal server error".to_string(),
             ),
         };
 
-        Response::builder()
-            .status(status)
-            .body(body::boxed(Full::from(message)))
-            .expect("Couldn't create error response")
+        if status.is_server_error() {
+            tracing::error!(code = self.code(), error = ?self, "request failed: {self}");
+        } else {
+            tracing::debug!(code = self.code(), error = ?self, "request rejected: {self}");
+        }
+
+        let code = self.code().to_string();
+        let details = match self {
+            AppError::Validation(errors) => Some(errors),
+            _ => None,
+        };
+        let body = ErrorResponse {
+            code,
+            message,
+            details,
+            request_id: current_request_id(),
+        };
+
+        (status, Json(body)).into_response()
     }
 }
 
 #[async_trait]
 impl<S> FromRequestParts<S> for Claims
 where
+    Arc<Config>: FromRef<S>,
     S: Send + Sync,
 {
     type Rejection = AppError;
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         let TypedHeader(Authorization(bearer)) = parts
             .extract::<TypedHeader<Authorization<Bearer>>>()
             .await
             .map_err(|_| AppError::Unauthorized)?;
 
-        let secret = env::var("JWT_SECRET").expect("JWT_SECRET is not set!");
-        let key = DecodingKey::from_secret(secret.as_ref());
-        let token_data = decode::<Claims>(bearer.token(), &key, &Validation::default())
-            .map_err(|_| AppError::Unauthorized)?;
-
-        Ok(token_data.claims)
+        let config = Arc::<Config>::from_ref(state);
+        decode_token(bearer.token(), &config)
     }
 }
 
+/// Verifies a token issued by [`AuthOutput::new`](crate::models::user::AuthOutput::new).
+pub(super) fn decode_token(token: &str, config: &Config) -> Result<Claims, AppError> {
+    let key = DecodingKey::from_secret(config.jwt_secret.as_ref());
+    let token_data = decode::<Claims>(token, &key, &Validation::default())
+        .map_err(|_| AppError::Unauthorized)?;
+
+    Ok(token_data.claims)
+}
+
 pub(super) struct SecurityAddon;
 
 impl Modify for SecurityAddon {
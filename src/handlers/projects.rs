@@ -1,58 +1,46 @@
 use axum::{
-    extract::{Multipart, Path, State},
+    extract::{Multipart, Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
 };
-use bson::{doc, oid::ObjectId};
 use image::{imageops::FilterType, io::Reader as ImageReader, ImageOutputFormat};
-use mongodb::{
-    options::{
-        FindOneAndUpdateOptions, FindOneOptions, FindOptions, InsertOneOptions, UpdateOptions,
-    },
-    results::UpdateResult,
-    Client, Collection,
-};
-use std::io::Cursor;
+use std::{io::Cursor, sync::Arc};
 
 use crate::{
-    error::AppError,
-    helpers::base64::encode_base64,
+    config::Config,
+    error::{AppError, FieldError},
+    helpers::{artifact::remove_project_files, base64::encode_base64},
     models::{
-        project::{BaseProjectInput, Project},
-        user::Claims,
+        pagination::{PageQuery, ProjectFilters},
+        project::{BaseProjectInput, Project, UpdateProjectInput},
+        user::{Claims, User, UserRole},
     },
+    repositories::{ProjectRepo, Repositories},
 };
 
-const DB_NAME: &str = "appdist";
-const COLLECTION_NAME: &str = "projects";
+const SORT_FIELDS: [&str; 1] = ["name"];
 
 /// List all projects
 ///
-/// List all projects in the database.
+/// List projects in the database, one page at a time. Sorted by `name` by default.
 #[utoipa::path(
     get,
     path = "/projects",
     tag = "Projects",
+    params(PageQuery, ProjectFilters),
     responses(
-        (status = 200, description = "Listed projects successfully", body = [Project])
+        (status = 200, description = "Listed projects successfully", body = ProjectPage),
+        (status = 400, description = "Invalid cursor, sort field or filter", body = ErrorResponse)
     )
 )]
 pub(crate) async fn get_projects(
-    State(client): State<Client>,
+    State(repos): State<Repositories>,
+    Query(page): Query<PageQuery>,
+    Query(filters): Query<ProjectFilters>,
 ) -> Result<impl IntoResponse, AppError> {
-    let coll: Collection<Project> = client
-        .database(DB_NAME)
-        .collection::<Project>(COLLECTION_NAME);
-
-    let options = FindOptions::default();
-    let mut cursor = coll.find(None, options).await?;
-
-    let mut rows: Vec<Project> = Vec::new();
-
-    while cursor.advance().await? {
-        rows.push(cursor.deserialize_current()?);
-    }
+    let sort = page.sort_spec(&SORT_FIELDS, "name")?;
+    let rows = repos.projects.find_page(&filters, &page, sort).await?;
 
     Ok((StatusCode::OK, Json(rows)).into_response())
 }
@@ -69,21 +57,14 @@ pub(crate) async fn get_projects(
     ),
     responses(
         (status = 200, description = "Found project successfully", body = Project),
-        (status = 404, description = "Project not found")
+        (status = 404, description = "Project not found", body = ErrorResponse)
     )
 )]
 pub(crate) async fn get_project(
-    State(client): State<Client>,
+    State(repos): State<Repositories>,
     Path(project_id): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
-    let coll: Collection<Project> = client
-        .database(DB_NAME)
-        .collection::<Project>(COLLECTION_NAME);
-
-    let options = FindOneOptions::default();
-    let oid = ObjectId::parse_str(project_id)?;
-    let filter = doc! { "_id": oid };
-    match coll.find_one(filter, options).await? {
+    match repos.projects.find_by_id(&project_id).await? {
         Some(project) => Ok((StatusCode::OK, Json(project)).into_response()),
         None => Err(AppError::NotFound),
     }
@@ -91,7 +72,7 @@ pub(crate) async fn get_project(
 
 /// Create new project
 ///
-/// Tries to create a new project database or fails with 400 if it can't be done.
+/// Tries to create a new project database or fails with 400 if it can't be done, or with 422 if the input is invalid.
 #[utoipa::path(
     post,
     path = "/projects",
@@ -99,73 +80,105 @@ pub(crate) async fn get_project(
     tag = "Projects",
     responses(
         (status = 201, description = "Project created successfully", body = Project),
-        (status = 400, description = "Bad Request")
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse)
     ),
     security(
         ("jwt_auth" = [])
     ),
 )]
 pub(crate) async fn create_project(
-    State(client): State<Client>,
+    State(repos): State<Repositories>,
     claims: Claims,
     Json(payload): Json<BaseProjectInput>,
 ) -> Result<impl IntoResponse, AppError> {
-    let coll: Collection<Project> = client
-        .database(DB_NAME)
-        .collection::<Project>(COLLECTION_NAME);
+    let mut errors = payload.validate();
+    if errors.is_empty() {
+        check_unique_name(
+            repos.projects.as_ref(),
+            &claims.user_id,
+            &payload.name,
+            None,
+            &mut errors,
+        )
+        .await?;
+    }
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+
     let new_project = Project::new(payload, claims.user_id);
-    let options = InsertOneOptions::default();
-    coll.insert_one(&new_project, options).await?;
+    repos.projects.insert(&new_project).await?;
 
     Ok((StatusCode::CREATED, Json(new_project)).into_response())
 }
 
 /// Update project
 ///
-/// Tries to update a project in database. Fails with 400 if it can't be done or with 404 if not found.
+/// Partially updates a project: only the fields present in the body are changed. Fails with 404 if not found, or with 422 if the input is invalid.
 #[utoipa::path(
     patch,
     path = "/projects/{project_id}",
-    request_body = BaseProjectInput,
+    request_body = UpdateProjectInput,
     tag = "Projects",
     params(
         ("project_id" = String, Path, description = "id of the requested project to be update")
     ),
     responses(
         (status = 204, description = "Project updated successfully"),
-        (status = 400, description = "Bad Request"),
-        (status = 404, description = "Not Found")
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 404, description = "Not Found", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse)
     )
 )]
 pub(crate) async fn update_project(
-    State(client): State<Client>,
+    State(repos): State<Repositories>,
     Path(project_id): Path<String>,
-    Json(payload): Json<BaseProjectInput>,
+    Json(payload): Json<UpdateProjectInput>,
 ) -> Result<impl IntoResponse, AppError> {
-    let coll: Collection<Project> = client
-        .database(DB_NAME)
-        .collection::<Project>(COLLECTION_NAME);
-
-    let oid = ObjectId::parse_str(project_id)?;
-    let filter = doc! { "_id": oid };
-
-    let updated_platforms = bson::to_bson(&payload.platforms).unwrap();
-    let update = doc! {
-        "$set": doc! {
-            "name": payload.name,
-            "description": payload.description,
-            "platforms": updated_platforms
-        }
+    let project = match repos.projects.find_by_id(&project_id).await? {
+        Some(project) => project,
+        None => return Err(AppError::NotFound),
     };
 
-    let options = FindOneAndUpdateOptions::default();
+    let mut errors = payload.validate();
+    if let (true, Some(name)) = (errors.is_empty(), &payload.name) {
+        check_unique_name(
+            repos.projects.as_ref(),
+            &project.owner,
+            name,
+            Some(&project.id),
+            &mut errors,
+        )
+        .await?;
+    }
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
 
-    match coll.find_one_and_update(filter, update, options).await? {
-        Some(_) => Ok(StatusCode::NO_CONTENT.into_response()),
-        None => Err(AppError::NotFound),
+    match repos.projects.update(&project.id, &payload).await? {
+        true => Ok(StatusCode::NO_CONTENT.into_response()),
+        false => Err(AppError::NotFound),
     }
 }
 
+/// Adds a validation error if the owner already has another project with the same name.
+async fn check_unique_name(
+    projects: &dyn ProjectRepo,
+    owner: &str,
+    name: &str,
+    exclude: Option<&str>,
+    errors: &mut Vec<FieldError>,
+) -> Result<(), AppError> {
+    if projects.name_taken(owner, name, exclude).await? {
+        errors.push(FieldError::new(
+            "name",
+            "A project with this name already exists",
+        ));
+    }
+    Ok(())
+}
+
 /// Update project image
 ///
 /// Updates a project image, resizeing it to 160x160 and then saving it as a base64 encoded string.
@@ -179,12 +192,13 @@ pub(crate) async fn update_project(
     ),
     responses(
         (status = 204, description = "Image resized and stored successfully"),
-        (status = 400, description = "Bad Request"),
-        (status = 404, description = "Not Found")
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 404, description = "Not Found", body = ErrorResponse)
     )
 )]
+#[allow(clippy::single_match)]
 pub(crate) async fn update_project_image(
-    State(client): State<Client>,
+    State(repos): State<Repositories>,
     Path(project_id): Path<String>,
     mut payload: Multipart,
 ) -> Result<impl IntoResponse, AppError> {
@@ -207,21 +221,13 @@ pub(crate) async fn update_project_image(
         };
     }
 
-    let coll: Collection<Project> = client
-        .database(DB_NAME)
-        .collection::<Project>(COLLECTION_NAME);
-
-    let oid = ObjectId::parse_str(project_id)?;
-    let filter = doc! { "_id": oid };
-    let update = doc! { "$set": doc! { "image": encoded_image } };
-    let options = UpdateOptions::default();
-
-    match coll.update_one(filter, update, options).await {
-        Ok(UpdateResult {
-            modified_count: 1, ..
-        }) => Ok(StatusCode::NO_CONTENT.into_response()),
-        Ok(_) => Err(AppError::NotFound),
-        Err(e) => Err(AppError::MongoError(e)),
+    match repos
+        .projects
+        .set_image(&project_id, &encoded_image)
+        .await?
+    {
+        true => Ok(StatusCode::NO_CONTENT.into_response()),
+        false => Err(AppError::NotFound),
     }
 }
 
@@ -237,27 +243,145 @@ pub(crate) async fn update_project_image(
     ),
     responses(
         (status = 204, description = "Image removed successfully"),
-        (status = 404, description = "Not Found")
+        (status = 404, description = "Not Found", body = ErrorResponse)
     )
 )]
 pub(crate) async fn remove_project_image(
-    State(client): State<Client>,
+    State(repos): State<Repositories>,
+    Path(project_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    match repos.projects.set_image(&project_id, "").await? {
+        true => Ok(StatusCode::NO_CONTENT.into_response()),
+        false => Err(AppError::NotFound),
+    }
+}
+
+/// Archive project
+///
+/// Hides a project from the projects list and rejects new uploads to it. Existing artifacts can still be downloaded. Only the project owner or an admin can archive a project.
+#[utoipa::path(
+    post,
+    tag = "Projects",
+    path = "/projects/{project_id}/archive",
+    params(
+        ("project_id" = String, Path, description = "id of the project to be archived")
+    ),
+    responses(
+        (status = 204, description = "Project archived successfully"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Forbidden", body = ErrorResponse),
+        (status = 404, description = "Not Found", body = ErrorResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+)]
+pub(crate) async fn archive_project(
+    State(repos): State<Repositories>,
+    claims: Claims,
+    Path(project_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    set_project_archived(repos, claims, project_id, true).await
+}
+
+/// Unarchive project
+///
+/// Restores an archived project, making it listed and accepting uploads again. Only the project owner or an admin can unarchive a project.
+#[utoipa::path(
+    delete,
+    tag = "Projects",
+    path = "/projects/{project_id}/archive",
+    params(
+        ("project_id" = String, Path, description = "id of the project to be unarchived")
+    ),
+    responses(
+        (status = 204, description = "Project unarchived successfully"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Forbidden", body = ErrorResponse),
+        (status = 404, description = "Not Found", body = ErrorResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+)]
+pub(crate) async fn unarchive_project(
+    State(repos): State<Repositories>,
+    claims: Claims,
     Path(project_id): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
-    let coll: Collection<Project> = client
-        .database(DB_NAME)
-        .collection::<Project>(COLLECTION_NAME);
+    set_project_archived(repos, claims, project_id, false).await
+}
 
-    let oid = ObjectId::parse_str(project_id)?;
-    let filter = doc! { "_id": oid };
-    let update = doc! { "$set": doc! { "image": "" } };
-    let options = UpdateOptions::default();
+async fn set_project_archived(
+    repos: Repositories,
+    claims: Claims,
+    project_id: String,
+    archived: bool,
+) -> Result<impl IntoResponse, AppError> {
+    let project = find_managed_project(&repos, &claims, &project_id).await?;
+    repos.projects.set_archived(&project.id, archived).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Delete project
+///
+/// Permanently deletes a project, all of its artifacts and their files, its release channels, and removes it from every user's favorite projects. Only the project owner or an admin can delete a project.
+#[utoipa::path(
+    delete,
+    tag = "Projects",
+    path = "/projects/{project_id}",
+    params(
+        ("project_id" = String, Path, description = "id of the project to be deleted")
+    ),
+    responses(
+        (status = 204, description = "Project deleted successfully"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Forbidden", body = ErrorResponse),
+        (status = 404, description = "Not Found", body = ErrorResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+)]
+pub(crate) async fn delete_project(
+    State(repos): State<Repositories>,
+    State(config): State<Arc<Config>>,
+    claims: Claims,
+    Path(project_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let project = find_managed_project(&repos, &claims, &project_id).await?;
+
+    repos.channels.delete_by_project(&project.id).await?;
+    repos.artifacts.delete_by_project(&project.id).await?;
+    remove_project_files(&config.uploads_path, &project.id).await?;
+    repos.users.remove_favorite_project(&project.id).await?;
+    repos.projects.delete(&project.id).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Finds a project that the requesting user is allowed to manage, i.e. owns or is an admin.
+pub(super) async fn find_managed_project(
+    repos: &Repositories,
+    claims: &Claims,
+    project_id: &str,
+) -> Result<Project, AppError> {
+    let project = match repos.projects.find_by_id(project_id).await? {
+        Some(project) => project,
+        None => return Err(AppError::NotFound),
+    };
+
+    if project.owner == claims.user_id {
+        return Ok(project);
+    }
 
-    match coll.update_one(filter, update, options).await {
-        Ok(UpdateResult {
-            modified_count: 1, ..
-        }) => Ok(StatusCode::NO_CONTENT.into_response()),
-        Ok(_) => Err(AppError::NotFound),
-        Err(e) => Err(AppError::MongoError(e)),
+    match repos.users.find_by_id(&claims.user_id).await? {
+        Some(User {
+            role: UserRole::Admin,
+            ..
+        }) => Ok(project),
+        Some(_) => Err(AppError::Forbidden),
+        None => Err(AppError::Unauthorized),
     }
 }
@@ -0,0 +1,61 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+use crate::{
+    error::AppError,
+    models::search::{SearchQuery, SearchResult},
+    repositories::Repositories,
+};
+
+/// Search projects and artifacts
+///
+/// Full-text search across project names and descriptions, and artifact branches, identifiers, original filenames, iOS bundle identifiers and Android package names. Each kind's scores are scaled against its best match before both are merged and ranked, since text scores of different collections aren't comparable.
+#[utoipa::path(
+    get,
+    path = "/search",
+    tag = "Search",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Searched successfully", body = [SearchResult]),
+        (status = 400, description = "Missing search terms", body = ErrorResponse)
+    )
+)]
+pub(crate) async fn search(
+    State(repos): State<Repositories>,
+    Query(query): Query<SearchQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let terms = query.q.trim();
+    if terms.is_empty() {
+        return Err(AppError::MissingSearchTerms);
+    }
+    let limit = query.limit();
+
+    let mut rows: Vec<SearchResult> = Vec::new();
+    for (project, score) in normalize(repos.projects.search(terms, limit).await?) {
+        rows.push(SearchResult::from_project(project, score));
+    }
+    for (artifact, score) in normalize(repos.artifacts.search(terms, limit).await?) {
+        rows.push(SearchResult::from_artifact(artifact, score));
+    }
+
+    rows.sort_by(|a, b| b.score.total_cmp(&a.score));
+    rows.truncate(limit as usize);
+
+    Ok((StatusCode::OK, Json(rows)).into_response())
+}
+
+/// Scales the scores of one kind of result so its best match scores 1.
+fn normalize<T>(results: Vec<(T, f64)>) -> Vec<(T, f64)> {
+    let top = results.iter().map(|(_, score)| *score).fold(0.0, f64::max);
+    if top <= 0.0 {
+        return results;
+    }
+    results
+        .into_iter()
+        .map(|(result, score)| (result, score / top))
+        .collect()
+}
@@ -0,0 +1,141 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use std::cmp::Ordering;
+
+use crate::{
+    error::AppError,
+    helpers::{artifact::create_itms_service_url, version::compare_versions},
+    models::{
+        artifact::{Artifact, ArtifactExtensions},
+        pagination::{ArtifactFilters, PageQuery, SortSpec},
+        update::{AvailableUpdate, UpdateCheck, UpdateCheckQuery},
+    },
+    proxy::PublicUrl,
+    repositories::Repositories,
+};
+
+/// Check for updates
+///
+/// Lets a distributed app ask whether a newer build of itself exists: the newest upload with the same bundle identifier or package name, or the build promoted into `channel`. Newer means a higher version, or the same version with a higher build number.
+#[utoipa::path(
+    get,
+    path = "/updates/check",
+    tag = "Updates",
+    params(UpdateCheckQuery),
+    responses(
+        (status = 200, description = "Checked for updates successfully", body = UpdateCheck),
+        (status = 404, description = "Channel not found", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse)
+    )
+)]
+pub(crate) async fn check_for_update(
+    State(repos): State<Repositories>,
+    PublicUrl(public_url): PublicUrl,
+    Query(query): Query<UpdateCheckQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let errors = query.validate();
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+    let bundle_identifier = query.bundle_identifier.clone().unwrap_or_default();
+
+    let filters = ArtifactFilters {
+        project_id: None,
+        branch: None,
+        extension: None,
+        created_after: None,
+        created_before: None,
+        platform: query.platform,
+        bundle_identifier: Some(bundle_identifier.clone()),
+        tag: None,
+        metadata: None,
+    };
+    let page = PageQuery {
+        limit: Some(1),
+        cursor: None,
+        sort: None,
+    };
+    let sort = SortSpec {
+        field: "createdAt".to_string(),
+        descending: true,
+    };
+    let newest = repos
+        .artifacts
+        .find_page(&filters, &page, sort)
+        .await?
+        .data
+        .into_iter()
+        .next();
+
+    let candidate = match (newest, &query.channel) {
+        (Some(newest), Some(channel)) => {
+            let project_id = newest.get_project_id();
+            if repos.channels.find(project_id, channel).await?.is_none() {
+                return Err(AppError::NotFound);
+            }
+            match repos.channels.latest_promotion(project_id, channel).await? {
+                Some(promotion) => repos
+                    .artifacts
+                    .find_by_id(&promotion.artifact_id)
+                    .await?
+                    .filter(|artifact| same_app(artifact, &newest, &bundle_identifier)),
+                None => None,
+            }
+        }
+        (newest, _) => newest,
+    };
+
+    let update = candidate
+        .filter(|artifact| is_newer(artifact, &query))
+        .map(|artifact| {
+            let artifact_id = artifact.get_id().clone();
+            let install_url = match artifact.get_extension() {
+                ArtifactExtensions::Ipa => {
+                    create_itms_service_url(&public_url, artifact_id.clone())
+                }
+                _ => format!("{public_url}/artifacts/{artifact_id}/download"),
+            };
+            AvailableUpdate {
+                artifact_id,
+                version: artifact.get_identifier().clone(),
+                build: artifact.get_bundle_data().map(|(_, build)| build.clone()),
+                install_url,
+                release_notes: artifact.get_release_notes().cloned(),
+                created_at: artifact.get_created_at() as i64,
+            }
+        });
+
+    let body = UpdateCheck {
+        update_available: update.is_some(),
+        update,
+    };
+    Ok((StatusCode::OK, Json(body)).into_response())
+}
+
+/// Whether a promoted artifact is a build of the same app, for the same platform, as the newest
+/// upload the check found.
+fn same_app(artifact: &Artifact, newest: &Artifact, bundle_identifier: &str) -> bool {
+    let is_ios = |artifact: &Artifact| matches!(artifact.get_extension(), ArtifactExtensions::Ipa);
+    is_ios(artifact) == is_ios(newest)
+        && artifact
+            .get_bundle_data()
+            .is_some_and(|(identifier, _)| identifier == bundle_identifier)
+}
+
+fn is_newer(artifact: &Artifact, query: &UpdateCheckQuery) -> bool {
+    let version = query.version.as_deref().unwrap_or_default();
+    match compare_versions(artifact.get_identifier(), version) {
+        Ordering::Greater => true,
+        Ordering::Less => false,
+        Ordering::Equal => match (artifact.get_bundle_data(), &query.build) {
+            (Some((_, build)), Some(current)) => {
+                compare_versions(build, current) == Ordering::Greater
+            }
+            _ => false,
+        },
+    }
+}
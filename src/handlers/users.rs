@@ -1,45 +1,46 @@
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
-use bson::doc;
-use mongodb::{
-    bson::oid::ObjectId,
-    error::{ErrorKind, WriteError, WriteFailure},
-    options::{FindOneOptions, FindOptions, InsertOneOptions, UpdateOptions},
-    Client, Collection,
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
 };
+use std::sync::Arc;
 
 use crate::{
+    config::Config,
     error::AppError,
-    models::user::{
-        AuthOutput, Claims, CreateUserInput, LoginInput, UpdateFavoriteProjectsInput, User,
-        UserOutput,
+    metrics::METRICS,
+    models::{
+        pagination::PageQuery,
+        user::{
+            AuthOutput, Claims, CreateUserInput, LoginInput, UpdateFavoriteProjectsInput, User,
+            UserOutput,
+        },
     },
+    repositories::Repositories,
 };
 
-const DB_NAME: &str = "appdist";
-const COLLECTION_NAME: &str = "users";
+const SORT_FIELDS: [&str; 2] = ["name", "email"];
 
 /// List all users
 ///
-/// List all users in the database.
+/// List users in the database, one page at a time. Sortable by `name` and `email`; sorted by `name` by default.
 #[utoipa::path(
     get,
     path = "/users",
     tag = "Users",
+    params(PageQuery),
     responses(
-        (status = 200, description = "Listed users successfully", body = [User])
+        (status = 200, description = "Listed users successfully", body = UserPage),
+        (status = 400, description = "Invalid cursor or sort field", body = ErrorResponse)
     )
 )]
-pub(crate) async fn get_users(State(client): State<Client>) -> Result<impl IntoResponse, AppError> {
-    let coll: Collection<User> = client.database(DB_NAME).collection::<User>(COLLECTION_NAME);
-
-    let options = FindOptions::default();
-    let mut cursor = coll.find(None, options).await?;
-
-    let mut rows: Vec<User> = Vec::new();
-
-    while cursor.advance().await? {
-        rows.push(cursor.deserialize_current()?)
-    }
+pub(crate) async fn get_users(
+    State(repos): State<Repositories>,
+    Query(page): Query<PageQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let sort = page.sort_spec(&SORT_FIELDS, "name")?;
+    let rows = repos.users.find_page(&page, sort).await?;
 
     Ok((StatusCode::OK, Json(rows)).into_response())
 }
@@ -53,22 +54,17 @@ pub(crate) async fn get_users(State(client): State<Client>) -> Result<impl IntoR
     tag = "Users",
     responses(
         (status = 200, description = "Returned user successfully", body = UserOutput),
-        (status = 401, description = "Unauthorized")
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
     ),
     security(
         ("jwt_auth" = [])
     ),
 )]
 pub(crate) async fn get_user_data(
-    State(client): State<Client>,
+    State(repos): State<Repositories>,
     claims: Claims,
 ) -> Result<impl IntoResponse, AppError> {
-    let coll: Collection<User> = client.database(DB_NAME).collection::<User>(COLLECTION_NAME);
-
-    let options = FindOneOptions::default();
-    let oid = ObjectId::parse_str(claims.user_id)?;
-    let filter = doc! { "_id": oid };
-    match coll.find_one(filter, options).await? {
+    match repos.users.find_by_id(&claims.user_id).await? {
         Some(user) => Ok((StatusCode::OK, Json(UserOutput::new(user))).into_response()),
         None => Err(AppError::Unauthorized),
     }
@@ -84,30 +80,19 @@ pub(crate) async fn get_user_data(
     request_body = CreateUserInput,
     responses(
         (status = 201, description = "User created successfully", body = AuthOutput),
-        (status = 400, description = "Bad Request")
+        (status = 400, description = "Bad Request", body = ErrorResponse)
     )
 )]
 pub(crate) async fn create_user(
-    State(client): State<Client>,
+    State(repos): State<Repositories>,
+    State(config): State<Arc<Config>>,
     Json(payload): Json<CreateUserInput>,
 ) -> Result<impl IntoResponse, AppError> {
-    let coll: Collection<User> = client.database(DB_NAME).collection::<User>(COLLECTION_NAME);
-
     let new_user = User::new(payload)?;
+    repos.users.insert(&new_user).await?;
 
-    let options = InsertOneOptions::default();
-    match coll.insert_one(&new_user, options).await {
-        Ok(_) => {
-            let response = AuthOutput::new(new_user.email, new_user.id)?;
-            Ok((StatusCode::CREATED, Json(response)).into_response())
-        }
-        Err(e) => match *e.kind.to_owned() {
-            ErrorKind::Write(WriteFailure::WriteError(WriteError { code: 11000, .. })) => {
-                Err(AppError::UserAlreadyRegistered)
-            }
-            _ => Err(AppError::MongoError(e)),
-        },
-    }
+    let response = AuthOutput::new(new_user.email, new_user.id, &config.jwt_secret)?;
+    Ok((StatusCode::CREATED, Json(response)).into_response())
 }
 
 /// Log in
@@ -120,28 +105,40 @@ pub(crate) async fn create_user(
     request_body = LoginInput,
     responses(
         (status = 201, description = "User logged in successfully", body = AuthOutput),
-        (status = 400, description = "Bad Request")
+        (status = 400, description = "Bad Request", body = ErrorResponse)
     )
 )]
 pub(crate) async fn login_user(
-    State(client): State<Client>,
+    State(repos): State<Repositories>,
+    State(config): State<Arc<Config>>,
     Json(payload): Json<LoginInput>,
 ) -> Result<impl IntoResponse, AppError> {
-    let coll: Collection<User> = client.database(DB_NAME).collection::<User>(COLLECTION_NAME);
+    let response = authenticate(&repos, &config, payload).await?;
 
-    let filter = doc! { "email": payload.email };
-    let options = FindOneOptions::default();
-    let user = coll.find_one(filter, options).await?;
+    Ok((StatusCode::OK, Json(response)).into_response())
+}
+
+/// Checks the credentials and issues a token, counting failed attempts.
+pub(super) async fn authenticate(
+    repos: &Repositories,
+    config: &Config,
+    payload: LoginInput,
+) -> Result<AuthOutput, AppError> {
+    let user = repos.users.find_by_email(&payload.email).await?;
 
     let user = match user {
         Some(u) => u,
-        None => return Err(AppError::InvalidCredentials),
+        None => {
+            METRICS.login_failures.inc();
+            return Err(AppError::InvalidCredentials);
+        }
     };
 
-    user.validate_password(payload.password)?;
-    let response = AuthOutput::new(user.email, user.id)?;
-
-    Ok((StatusCode::OK, Json(response)).into_response())
+    if let Err(e) = user.validate_password(payload.password) {
+        METRICS.login_failures.inc();
+        return Err(e);
+    }
+    AuthOutput::new(user.email, user.id, &config.jwt_secret)
 }
 
 /// Edit favorite projects
@@ -154,50 +151,31 @@ pub(crate) async fn login_user(
     request_body = LoginInput,
     responses(
         (status = 204, description = "User logged in successfully"),
-        (status = 400, description = "Bad Request"),
-        (status = 403, description = "Forbidden")
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 403, description = "Forbidden", body = ErrorResponse)
     ),
     security(
         ("jwt_auth" = [])
     ),
 )]
 pub(crate) async fn edit_favorite_projects(
-    State(client): State<Client>,
+    State(repos): State<Repositories>,
     claims: Claims,
     Json(payload): Json<UpdateFavoriteProjectsInput>,
 ) -> Result<impl IntoResponse, AppError> {
-    let coll: Collection<User> = client.database(DB_NAME).collection::<User>(COLLECTION_NAME);
-
-    let options = FindOneOptions::default();
-
-    let oid = ObjectId::parse_str(claims.user_id)?;
-    let filter = doc! { "_id": oid };
-
-    let user = match coll.find_one(filter.clone(), options).await? {
+    let user = match repos.users.find_by_id(&claims.user_id).await? {
         Some(u) => u,
         None => return Err(AppError::Forbidden),
     };
 
-    let options = UpdateOptions::default();
-    let update;
-    if user
+    let favorite = !user
         .favorite_projects
         .iter()
-        .any(|id| id.eq(&payload.project_id))
-    {
-        update = doc! {
-            "$pull": doc! {
-                "favoriteProjects": payload.project_id
-            }
-        };
-    } else {
-        update = doc! {
-            "$addToSet": doc! {
-                "favoriteProjects": payload.project_id
-            }
-        };
-    }
-    coll.update_one(filter, update, options).await?;
+        .any(|id| id.eq(&payload.project_id));
+    repos
+        .users
+        .set_favorite_project(&user.id, &payload.project_id, favorite)
+        .await?;
 
     Ok(StatusCode::NO_CONTENT.into_response())
 }
@@ -0,0 +1,314 @@
+use askama::Template;
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts, Path, Query, State},
+    headers::Cookie,
+    http::{header, request::Parts, StatusCode},
+    response::{AppendHeaders, Html, IntoResponse, Redirect, Response},
+    Form, RequestPartsExt, TypedHeader,
+};
+use chrono::{TimeZone, Utc};
+use std::{convert::Infallible, sync::Arc};
+
+use crate::{
+    config::Config,
+    error::AppError,
+    helpers::artifact::create_itms_service_url,
+    models::{
+        artifact::{Artifact, ArtifactExtensions},
+        pagination::{PageQuery, ProjectFilters},
+        project::{Platforms, Project},
+        user::{Claims, LoginInput, User, TOKEN_LIFETIME_DAYS},
+    },
+    proxy::PublicUrl,
+    repositories::Repositories,
+};
+
+use super::{decode_token, users::authenticate};
+
+const SESSION_COOKIE: &str = "appdist_session";
+
+/// The user signed in through the login form, if any. Missing, invalid and expired session
+/// cookies all mean an anonymous visitor.
+pub(crate) struct Session(pub(super) Option<Claims>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Session
+where
+    Arc<Config>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let config = Arc::<Config>::from_ref(state);
+        let claims = match parts.extract::<TypedHeader<Cookie>>().await {
+            Ok(TypedHeader(cookie)) => cookie
+                .get(SESSION_COOKIE)
+                .and_then(|token| decode_token(token, &config).ok()),
+            Err(_) => None,
+        };
+        Ok(Session(claims))
+    }
+}
+
+struct ProjectItem {
+    id: String,
+    name: String,
+    initial: String,
+    image: Option<String>,
+    platforms: String,
+}
+
+impl ProjectItem {
+    fn new(project: &Project) -> ProjectItem {
+        let platforms: Vec<&str> = project
+            .platforms
+            .iter()
+            .map(|platform| match platform {
+                Platforms::Android => "Android",
+                Platforms::Ios => "iOS",
+            })
+            .collect();
+        ProjectItem {
+            id: project.id.clone(),
+            name: project.name.clone(),
+            initial: project.name.chars().take(1).collect(),
+            image: project.image.clone().filter(|image| !image.is_empty()),
+            platforms: platforms.join(" · "),
+        }
+    }
+}
+
+struct BranchItem {
+    name: String,
+    builds: Vec<BuildItem>,
+}
+
+struct BuildItem {
+    id: String,
+    identifier: String,
+    platform: &'static str,
+    size: String,
+    uploaded: String,
+    install_url: String,
+    action: &'static str,
+}
+
+impl BuildItem {
+    fn new(artifact: &Artifact, public_url: &str) -> BuildItem {
+        let id = artifact.get_id().clone();
+        let download_url = format!("{public_url}/artifacts/{id}/download");
+        let (platform, install_url, action) = match artifact.get_extension() {
+            ArtifactExtensions::Ipa => (
+                "iOS",
+                create_itms_service_url(public_url, id.clone()),
+                "Install",
+            ),
+            ArtifactExtensions::Apk => ("Android", download_url, "Install"),
+            ArtifactExtensions::Aab => ("Android App Bundle", download_url, "Download"),
+        };
+        let (_, _, size) = artifact.get_download_data();
+        let uploaded = Utc
+            .timestamp_millis_opt(artifact.get_created_at() as i64)
+            .single()
+            .map(|date| date.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_default();
+
+        BuildItem {
+            id,
+            identifier: artifact.get_identifier().clone(),
+            platform,
+            size: format_size(*size),
+            uploaded,
+            install_url,
+            action,
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "web/projects.html")]
+struct ProjectsTemplate {
+    user: Option<String>,
+    favorites: Vec<ProjectItem>,
+    projects: Vec<ProjectItem>,
+    next_cursor: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "web/project.html")]
+struct ProjectTemplate {
+    user: Option<String>,
+    project: ProjectItem,
+    description: String,
+    branches: Vec<BranchItem>,
+}
+
+#[derive(Template)]
+#[template(path = "web/login.html")]
+struct LoginTemplate {
+    user: Option<String>,
+    email: String,
+    error: Option<&'static str>,
+}
+
+pub(crate) async fn projects_page(
+    State(repos): State<Repositories>,
+    Session(claims): Session,
+    Query(page): Query<PageQuery>,
+) -> Result<Response, AppError> {
+    let user = signed_in_user(&repos, &claims).await?;
+
+    // favorites are listed above the first page only
+    let mut favorites = Vec::new();
+    if let (Some(user), None) = (&user, &page.cursor) {
+        for project_id in &user.favorite_projects {
+            match repos.projects.find_by_id(project_id).await? {
+                Some(project) if !project.archived => favorites.push(ProjectItem::new(&project)),
+                _ => (),
+            }
+        }
+    }
+
+    let filters = ProjectFilters {
+        platform: None,
+        include_archived: None,
+    };
+    let sort = page.sort_spec(&["name"], "name")?;
+    let projects = repos.projects.find_page(&filters, &page, sort).await?;
+
+    render(ProjectsTemplate {
+        user: user.map(|user| user.name),
+        favorites,
+        projects: projects.data.iter().map(ProjectItem::new).collect(),
+        next_cursor: projects.pagination.next_cursor,
+    })
+}
+
+pub(crate) async fn project_page(
+    State(repos): State<Repositories>,
+    Session(claims): Session,
+    PublicUrl(public_url): PublicUrl,
+    Path(project_id): Path<String>,
+) -> Result<Response, AppError> {
+    let user = signed_in_user(&repos, &claims).await?;
+    let project = match repos.projects.find_by_id(&project_id).await? {
+        Some(project) => project,
+        None => return Err(AppError::NotFound),
+    };
+
+    // branches with the most recent builds come first, newest build first within each
+    let mut groups = repos.artifacts.find_by_branch(&project_id).await?;
+    for group in &mut groups {
+        group
+            .artifacts
+            .sort_by_key(|artifact| std::cmp::Reverse(artifact.get_created_at()));
+    }
+    groups.sort_by_key(|group| {
+        std::cmp::Reverse(group.artifacts.first().map(Artifact::get_created_at))
+    });
+    let branches = groups
+        .iter()
+        .map(|group| BranchItem {
+            name: group.branch.clone(),
+            builds: group
+                .artifacts
+                .iter()
+                .map(|artifact| BuildItem::new(artifact, &public_url))
+                .collect(),
+        })
+        .collect();
+
+    render(ProjectTemplate {
+        user: user.map(|user| user.name),
+        project: ProjectItem::new(&project),
+        description: project.description,
+        branches,
+    })
+}
+
+pub(crate) async fn login_page(Session(claims): Session) -> Result<Response, AppError> {
+    if claims.is_some() {
+        return Ok(Redirect::to("/web").into_response());
+    }
+    render(LoginTemplate {
+        user: None,
+        email: String::new(),
+        error: None,
+    })
+}
+
+/// Logs in with the same credentials as `POST /users/login`, keeping the token in a cookie.
+pub(crate) async fn login(
+    State(repos): State<Repositories>,
+    State(config): State<Arc<Config>>,
+    PublicUrl(public_url): PublicUrl,
+    Form(payload): Form<LoginInput>,
+) -> Result<Response, AppError> {
+    let email = payload.email.clone();
+    match authenticate(&repos, &config, payload).await {
+        Ok(auth) => {
+            let secure = if public_url.starts_with("https://") {
+                "; Secure"
+            } else {
+                ""
+            };
+            let cookie = format!(
+                "{SESSION_COOKIE}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax{secure}",
+                auth.get_token(),
+                TOKEN_LIFETIME_DAYS * 24 * 60 * 60,
+            );
+            Ok((
+                AppendHeaders([(header::SET_COOKIE, cookie)]),
+                Redirect::to("/web"),
+            )
+                .into_response())
+        }
+        Err(AppError::InvalidCredentials) => {
+            let page = LoginTemplate {
+                user: None,
+                email,
+                error: Some("Wrong email or password."),
+            };
+            Ok((StatusCode::UNAUTHORIZED, Html(page.render()?)).into_response())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+pub(crate) async fn logout() -> impl IntoResponse {
+    let cookie = format!("{SESSION_COOKIE}=; Path=/; Max-Age=0; HttpOnly; SameSite=Lax");
+    (
+        AppendHeaders([(header::SET_COOKIE, cookie)]),
+        Redirect::to("/web"),
+    )
+}
+
+pub(super) async fn signed_in_user(
+    repos: &Repositories,
+    claims: &Option<Claims>,
+) -> Result<Option<User>, AppError> {
+    match claims {
+        Some(claims) => repos.users.find_by_id(&claims.user_id).await,
+        None => Ok(None),
+    }
+}
+
+fn render(template: impl Template) -> Result<Response, AppError> {
+    Ok(Html(template.render()?).into_response())
+}
+
+fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{bytes} B"),
+        _ => format!("{size:.1} {}", UNITS[unit]),
+    }
+}
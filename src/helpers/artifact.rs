@@ -1,30 +1,76 @@
-use std::{env, fs, io};
+use std::{fs, io, path::Path};
 
+/// Files are named after the artifact id, so no two artifacts ever share one, whatever their
+/// branch and identifier.
 pub fn create_file_path(
-    project: &String,
-    branch: &String,
-    identifier: &String,
-    extension: &String,
+    uploads_path: &Path,
+    project: &str,
+    artifact_id: &str,
+    extension: &str,
 ) -> Result<String, std::io::Error> {
-    let base_path = env::var("UPLOADS_PATH").expect("Failed to load UPLOADS_PATH");
-    let path = format!("{base_path}/{project}/{branch}",);
+    let path = project_uploads_path(uploads_path, project);
     fs::create_dir_all(&path)?;
-    let full_path = format!("{path}/{identifier}.{extension}");
-    Ok(full_path)
+    Ok(artifact_file_path(&path, artifact_id, extension))
+}
+
+pub fn artifact_file_path(project_path: &str, artifact_id: &str, extension: &str) -> String {
+    format!("{project_path}/{artifact_id}.{extension}")
+}
+
+pub fn project_uploads_path(uploads_path: &Path, project: &str) -> String {
+    format!("{}/{project}", uploads_path.display())
+}
+
+pub async fn remove_project_files(uploads_path: &Path, project: &str) -> io::Result<()> {
+    match tokio::fs::remove_dir_all(project_uploads_path(uploads_path, project)).await {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
 }
 
+const INCOMPLETE_SUFFIX: &str = ".part";
+
+/// Writes the file next to its final path first and renames it once complete, so an
+/// interrupted upload never leaves a truncated artifact behind.
 pub fn write_file_to_disk(path: &String, file_buffer: &[u8]) -> io::Result<()> {
-    fs::write(path, file_buffer)
+    let incomplete_path = format!("{path}{INCOMPLETE_SUFFIX}");
+    fs::write(&incomplete_path, file_buffer)?;
+    fs::rename(&incomplete_path, path)
+}
+
+/// Removes files left behind by uploads that were interrupted while being written.
+pub fn remove_incomplete_uploads(uploads_path: &Path) -> io::Result<usize> {
+    remove_incomplete_files(uploads_path)
+}
+
+fn remove_incomplete_files(dir: &Path) -> io::Result<usize> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let mut removed = 0;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            removed += remove_incomplete_files(&path)?;
+        } else if path.to_string_lossy().ends_with(INCOMPLETE_SUFFIX) {
+            fs::remove_file(&path)?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
 }
 
 pub fn parse_plist_template(
+    public_url: &str,
     artifact_id: &str,
     bundle_identifier: &str,
     bundle_version: &str,
     app_name: &str,
 ) -> String {
-    let server_public_url = env::var("PUBLIC_URL").expect("PUBLIC_URL is not set");
-    let url = format!("{server_public_url}/artifacts/{artifact_id}/download");
+    let url = format!("{public_url}/artifacts/{artifact_id}/download");
     format!("
 <?xml version=\"1.0\" encoding=\"UTF-8\"?>
 <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">
@@ -60,13 +106,11 @@ pub fn parse_plist_template(
 ")
 }
 
-pub fn create_itms_service_url(artifact_id: String) -> String {
-    let server_public_url = env::var("PUBLIC_URL").expect("PUBLIC_URL is not set");
-    let plist_url = format!("{server_public_url}/artifacts/{artifact_id}/ios-plist");
-    format!("itms-services://?action=download-manifest&amp;url={plist_url}")
+pub fn create_itms_service_url(public_url: &str, artifact_id: String) -> String {
+    let plist_url = format!("{public_url}/artifacts/{artifact_id}/ios-plist");
+    format!("itms-services://?action=download-manifest&url={plist_url}")
 }
 
-pub fn create_file_url(artifact_id: String) -> String {
-    let server_public_url = env::var("PUBLIC_URL").expect("PUBLIC_URL is not set");
-    format!("{server_public_url}/artifacts/{artifact_id}/file")
+pub fn create_file_url(public_url: &str, artifact_id: String) -> String {
+    format!("{public_url}/artifacts/{artifact_id}/file")
 }
@@ -1,4 +1,4 @@
-use base64::{alphabet, engine, write};
+use base64::{alphabet, engine, write, Engine};
 use std::io::Write;
 
 pub fn encode_base64(buffer: &[u8]) -> Result<String, std::io::Error> {
@@ -8,3 +8,11 @@ pub fn encode_base64(buffer: &[u8]) -> Result<String, std::io::Error> {
     encoder.write_all(buffer)?;
     Ok(encoder.into_inner())
 }
+
+pub fn encode_base64_url(buffer: &[u8]) -> String {
+    engine::general_purpose::URL_SAFE_NO_PAD.encode(buffer)
+}
+
+pub fn decode_base64_url(input: &str) -> Result<Vec<u8>, base64::DecodeError> {
+    engine::general_purpose::URL_SAFE_NO_PAD.decode(input)
+}
@@ -0,0 +1,126 @@
+use askama::Template;
+use chrono::{TimeZone, Utc};
+
+use crate::models::artifact::BuildInfo;
+
+use super::markdown::render_markdown;
+
+/// The kind of device a request comes from, as far as installing builds is concerned.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum Device {
+    Ios,
+    Android,
+    Other,
+}
+
+impl Device {
+    pub fn from_user_agent(user_agent: &str) -> Device {
+        if ["iPhone", "iPad", "iPod"]
+            .iter()
+            .any(|device| user_agent.contains(device))
+        {
+            Device::Ios
+        } else if user_agent.contains("Android") {
+            Device::Android
+        } else {
+            Device::Other
+        }
+    }
+}
+
+/// Everything shown on an artifact's install page. Values are escaped when rendered.
+#[derive(Template)]
+#[template(path = "web/install.html")]
+pub struct InstallPage<'a> {
+    /// Name of the signed-in user, if any.
+    pub user: Option<String>,
+    pub project_name: &'a str,
+    pub project_image: Option<&'a str>,
+    pub identifier: &'a str,
+    pub branch: &'a str,
+    pub bundle_version: Option<&'a str>,
+    pub created_at: u64,
+    pub file_name: &'a str,
+    /// Markdown, rendered without raw HTML.
+    pub release_notes: Option<&'a str>,
+    pub build_info: &'a BuildInfo,
+    pub tags: &'a [String],
+    /// `itms-services` link for iOS builds, download link otherwise.
+    pub install_url: String,
+    /// The device the build runs on.
+    pub platform: Device,
+    /// The device the page is viewed on.
+    pub device: Device,
+}
+
+impl InstallPage<'_> {
+    fn image(&self) -> Option<&str> {
+        self.project_image.filter(|image| !image.is_empty())
+    }
+
+    fn initial(&self) -> String {
+        self.project_name.chars().take(1).collect()
+    }
+
+    fn details(&self) -> Vec<(&'static str, String)> {
+        let mut details = vec![
+            ("Version", self.identifier.to_string()),
+            ("Branch", self.branch.to_string()),
+        ];
+        if let Some(bundle_version) = self.bundle_version {
+            details.push(("Build", bundle_version.to_string()));
+        }
+        if let Some(created_at) = Utc.timestamp_millis_opt(self.created_at as i64).single() {
+            details.push((
+                "Uploaded",
+                created_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+            ));
+        }
+        if let Some(commit_sha) = &self.build_info.commit_sha {
+            details.push(("Commit", commit_sha.chars().take(7).collect()));
+        }
+        if let Some(author) = &self.build_info.author {
+            details.push(("Author", author.to_string()));
+        }
+        if !self.tags.is_empty() {
+            details.push(("Tags", self.tags.join(", ")));
+        }
+        details
+    }
+
+    /// The release notes as HTML, safe to embed unescaped.
+    fn notes(&self) -> Option<String> {
+        self.release_notes.map(render_markdown)
+    }
+
+    fn action(&self) -> &'static str {
+        match self.platform {
+            Device::Ios => "Install",
+            _ if self.file_name.ends_with(".aab") => "Download",
+            _ => "Download and install",
+        }
+    }
+
+    fn hint(&self) -> Option<&'static str> {
+        let hint = match self.platform {
+            Device::Ios => "This build installs on iPhone and iPad.",
+            _ if self.file_name.ends_with(".aab") => {
+                "Android App Bundles can't be installed directly; use bundletool or Play."
+            }
+            _ => "This build installs on Android.",
+        };
+        match self.device {
+            Device::Other => Some("Open this page on your phone to install the build."),
+            device if device != self.platform => Some(hint),
+            _ => None,
+        }
+    }
+
+    /// File name the link downloads to; iOS builds are installed rather than downloaded.
+    fn download_name(&self) -> Option<&str> {
+        match self.platform {
+            Device::Ios => None,
+            _ => Some(self.file_name),
+        }
+    }
+}
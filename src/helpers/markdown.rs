@@ -0,0 +1,34 @@
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+
+/// Renders user-written markdown, such as release notes, to HTML that is safe to embed: raw
+/// HTML is shown as text and only web and mail links are kept.
+pub fn render_markdown(text: &str) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let events = Parser::new_ext(text, options).map(|event| match event {
+        Event::Html(html) => Event::Text(html),
+        Event::Start(Tag::Link(kind, url, title)) => {
+            Event::Start(Tag::Link(kind, safe_url(url), title))
+        }
+        Event::Start(Tag::Image(kind, url, title)) => {
+            Event::Start(Tag::Image(kind, safe_url(url), title))
+        }
+        event => event,
+    });
+
+    let mut output = String::with_capacity(text.len() * 3 / 2);
+    html::push_html(&mut output, events);
+    output
+}
+
+fn safe_url(url: CowStr) -> CowStr {
+    let lowercase = url.trim_start().to_lowercase();
+    let scheme = lowercase.split_once(':').map(|(scheme, _)| scheme);
+    match scheme {
+        // relative links have no scheme, or a colon only after a path separator
+        None => url,
+        Some(scheme) if scheme.contains(['/', '?', '#']) => url,
+        Some("http" | "https" | "mailto") => url,
+        Some(_) => CowStr::Borrowed("#"),
+    }
+}
@@ -1,2 +1,7 @@
 pub mod artifact;
 pub mod base64;
+pub mod install_page;
+pub mod markdown;
+pub mod storage;
+pub mod systemd;
+pub mod version;
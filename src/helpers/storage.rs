@@ -0,0 +1,148 @@
+use std::{cmp::Reverse, collections::HashMap, fs, io, path::Path};
+
+use crate::{
+    error::AppError,
+    models::{
+        artifact::Artifact,
+        pagination::{ArtifactFilters, PageQuery, SortSpec},
+    },
+    repositories::Repositories,
+};
+
+use super::artifact::{artifact_file_path, project_uploads_path};
+
+/// What [`repair_storage`] changed, or would change on a dry run.
+#[derive(Default, Debug)]
+pub struct StorageRepair {
+    /// Artifacts whose file was moved to the name of their id.
+    pub moved: Vec<String>,
+    /// Artifacts whose file was overwritten by a later upload, with the id of that upload.
+    pub overwritten: Vec<(String, String)>,
+    /// Artifacts whose file doesn't exist, including the ones overwritten on a previous run.
+    pub missing: Vec<String>,
+    /// Overwritten and missing artifacts whose record was deleted.
+    pub deleted: Vec<String>,
+}
+
+/// Moves artifact files stored under their branch and identifier, which uploads with the same
+/// ones used to share, to names of their own. Of the artifacts sharing a file, only the newest
+/// upload's bytes survived, so it keeps the file; the others are reported as overwritten and
+/// fail to download until they are deleted or uploaded again. Every run reports the artifacts
+/// left without a file, and `delete_missing` deletes their records.
+pub async fn repair_storage(
+    repos: &Repositories,
+    uploads_path: &Path,
+    dry_run: bool,
+    delete_missing: bool,
+) -> Result<StorageRepair, AppError> {
+    let mut by_path: HashMap<String, Vec<Artifact>> = HashMap::new();
+    for artifact in all_artifacts(repos).await? {
+        by_path
+            .entry(artifact.get_path().clone())
+            .or_default()
+            .push(artifact);
+    }
+
+    let mut repair = StorageRepair::default();
+    for (path, mut artifacts) in by_path {
+        artifacts.sort_by_key(|artifact| {
+            Reverse((artifact.get_created_at(), artifact.get_id().clone()))
+        });
+        let newest = artifacts[0].get_id().clone();
+
+        for (index, artifact) in artifacts.iter().enumerate() {
+            let project_path = project_uploads_path(uploads_path, artifact.get_project_id());
+            let extension = artifact.get_extension().to_string().to_lowercase();
+            let key = artifact_file_path(&project_path, artifact.get_id(), &extension);
+
+            let has_file = if path == key {
+                let exists = Path::new(&key).exists();
+                if !exists {
+                    repair.missing.push(artifact.get_id().clone());
+                }
+                exists
+            } else if index > 0 {
+                repair
+                    .overwritten
+                    .push((artifact.get_id().clone(), newest.clone()));
+                false
+            } else if Path::new(&path).exists() {
+                if !dry_run {
+                    fs::create_dir_all(&project_path)?;
+                    fs::rename(&path, &key)?;
+                }
+                repair.moved.push(artifact.get_id().clone());
+                true
+            } else {
+                repair.missing.push(artifact.get_id().clone());
+                false
+            };
+
+            if !has_file && delete_missing {
+                if !dry_run {
+                    repos.artifacts.delete(artifact.get_id()).await?;
+                }
+                repair.deleted.push(artifact.get_id().clone());
+            } else if !dry_run && path != key {
+                repos.artifacts.set_path(artifact.get_id(), &key).await?;
+            }
+        }
+    }
+    Ok(repair)
+}
+
+/// Bytes stored in each project's uploads directory, by project id, as found on disk.
+pub fn project_disk_usage(uploads_path: &Path) -> io::Result<Vec<(String, u64)>> {
+    let mut usage = Vec::new();
+    let entries = match fs::read_dir(uploads_path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(usage),
+        Err(e) => return Err(e),
+    };
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            let project_id = entry.file_name().to_string_lossy().into_owned();
+            usage.push((project_id, directory_size(&entry.path())?));
+        }
+    }
+    Ok(usage)
+}
+
+/// Total size of the files in `path`, including those of legacy branch subdirectories.
+fn directory_size(path: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            size += directory_size(&entry.path())?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+async fn all_artifacts(repos: &Repositories) -> Result<Vec<Artifact>, AppError> {
+    let filters = ArtifactFilters::default();
+    let mut artifacts = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = PageQuery {
+            limit: Some(200),
+            cursor,
+            sort: None,
+        };
+        let sort = SortSpec {
+            field: "createdAt".to_string(),
+            descending: false,
+        };
+        let mut rows = repos.artifacts.find_page(&filters, &page, sort).await?;
+        artifacts.append(&mut rows.data);
+        match rows.pagination.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => return Ok(artifacts),
+        }
+    }
+}
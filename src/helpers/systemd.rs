@@ -0,0 +1,63 @@
+use std::{env, io, time::Duration};
+
+/// Sends a state update (e.g. `READY=1`) to systemd when running as a `Type=notify` service.
+/// Does nothing when `NOTIFY_SOCKET` isn't set.
+#[cfg(unix)]
+pub fn notify(state: &str) -> io::Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    let socket_path = match env::var("NOTIFY_SOCKET") {
+        Ok(path) => path,
+        Err(_) => return Ok(()),
+    };
+
+    let socket = UnixDatagram::unbound()?;
+    match socket_path.strip_prefix('@') {
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+            let addr = SocketAddr::from_abstract_name(name)?;
+            socket.send_to_addr(state.as_bytes(), &addr)?;
+        }
+        _ => {
+            socket.send_to(state.as_bytes(), &socket_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// systemd only runs on Linux, so there is never anyone to notify.
+#[cfg(not(unix))]
+pub fn notify(_state: &str) -> io::Result<()> {
+    Ok(())
+}
+
+/// Pings the systemd watchdog at half the configured `WatchdogSec` for as long as the runtime
+/// keeps scheduling tasks, so systemd restarts the server when it gets wedged. Readiness also
+/// depends on the database and the certificate, which a restart doesn't fix, so it is left to
+/// `/readyz`.
+pub async fn run_watchdog() {
+    let interval = match env::var("WATCHDOG_USEC")
+        .ok()
+        .and_then(|usec| usec.parse::<u64>().ok())
+    {
+        Some(usec) => Duration::from_micros(usec / 2),
+        None => return,
+    };
+
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        // a task that isn't scheduled within the interval means every worker is blocked
+        if tokio::time::timeout(interval, tokio::spawn(async {}))
+            .await
+            .is_err()
+        {
+            tracing::warn!("skipping watchdog ping, runtime is not scheduling tasks");
+            continue;
+        }
+        if let Err(error) = notify("WATCHDOG=1") {
+            tracing::warn!(%error, "failed to ping systemd watchdog");
+        }
+    }
+}
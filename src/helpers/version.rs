@@ -0,0 +1,31 @@
+use std::cmp::Ordering;
+
+/// Compares version strings such as `1.10.2`, `2.0` or `1.3.0-beta.2` the way people read
+/// them: numeric parts numerically, missing parts as zero, anything else alphabetically.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |version: &str| -> Vec<String> {
+        version
+            .trim()
+            .trim_start_matches(['v', 'V'])
+            .split(['.', '-', '+', '_'])
+            .map(str::to_string)
+            .collect()
+    };
+    let (a, b) = (parts(a), parts(b));
+
+    for index in 0..a.len().max(b.len()) {
+        let a = a.get(index).map(String::as_str).unwrap_or("0");
+        let b = b.get(index).map(String::as_str).unwrap_or("0");
+        let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            // a release is newer than its pre-releases, e.g. `1.0.0` > `1.0.0-beta`
+            (Ok(_), Err(_)) => Ordering::Greater,
+            (Err(_), Ok(_)) => Ordering::Less,
+            (Err(_), Err(_)) => a.cmp(b),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
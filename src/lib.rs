@@ -0,0 +1,16 @@
+pub mod acme;
+mod app_router;
+pub mod config;
+pub mod database;
+pub mod error;
+mod handlers;
+pub mod helpers;
+pub mod metrics;
+pub mod models;
+pub mod proxy;
+pub mod repositories;
+pub mod state;
+pub mod telemetry;
+pub mod tls;
+
+pub use app_router::router;
@@ -1,24 +1,86 @@
-mod app_router;
-mod database;
-mod error;
-mod handlers;
-mod helpers;
-mod models;
-
 use axum::{
     extract::Host,
-    handler::HandlerWithoutStateExt,
     http::{StatusCode, Uri},
     response::Redirect,
-    BoxError,
+    routing::get,
+    BoxError, Router,
 };
-use axum_server::tls_rustls::RustlsConfig;
+use axum_server::{tls_rustls::RustlsConfig, Handle};
 use dotenv::dotenv;
-use std::{env, net::SocketAddr, path::PathBuf};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use std::{env, net::SocketAddr, path::PathBuf, process, sync::Arc, time::Duration};
+use tokio::signal;
+
+use app_repository_server::{
+    acme::{self, AcmeChallenges},
+    config::{Config, DatabaseConfig, TlsConfig},
+    database::{self, migrations},
+    error::AppError,
+    helpers::{artifact::remove_incomplete_uploads, storage::repair_storage, systemd},
+    repositories::Repositories,
+    router,
+    state::{AppState, Draining},
+    telemetry,
+    tls::{watch_certificates, CertificateInfo, CertificatePaths, TlsStatus},
+};
+
+const USAGE: &str = "Usage: app_repository_server [--config <file>] [--print-config] [migrate | repair-storage [--dry-run] [--delete-missing]]
+
+Commands:
+    migrate           Apply pending database migrations and exit, instead of serving
+    repair-storage    Give every artifact file a name of its own, report artifacts whose file
+                      was overwritten by a later upload or is missing, and exit
+
+Options:
+    --config <file>   Read settings from a TOML file; environment variables take precedence
+    --print-config    Print the effective settings, with secrets redacted, and exit
+    --dry-run         Only report what repair-storage would change
+    --delete-missing  Make repair-storage delete the artifacts left without a file
+    -h, --help        Print this message and exit";
+
+/// Command line arguments. Everything else is configured through [`Config`].
+#[derive(Default)]
+struct Args {
+    config_file: Option<PathBuf>,
+    print_config: bool,
+    migrate: bool,
+    repair_storage: bool,
+    dry_run: bool,
+    delete_missing: bool,
+}
+
+impl Args {
+    fn parse() -> Result<Args, String> {
+        let mut args = Args::default();
+        let mut raw = env::args().skip(1);
 
-use app_router::router;
-use error::AppError;
+        while let Some(arg) = raw.next() {
+            match arg.as_str() {
+                "--config" => match raw.next() {
+                    Some(path) => args.config_file = Some(PathBuf::from(path)),
+                    None => return Err("--config requires a file".to_string()),
+                },
+                "--print-config" => args.print_config = true,
+                "migrate" => args.migrate = true,
+                "repair-storage" => args.repair_storage = true,
+                "--dry-run" => args.dry_run = true,
+                "--delete-missing" => args.delete_missing = true,
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    process::exit(0);
+                }
+                arg => return Err(format!("Unknown argument {arg}")),
+            }
+        }
+
+        if args.dry_run && !args.repair_storage {
+            return Err("--dry-run only applies to repair-storage".to_string());
+        }
+        if args.delete_missing && !args.repair_storage {
+            return Err("--delete-missing only applies to repair-storage".to_string());
+        }
+        Ok(args)
+    }
+}
 
 #[derive(Clone, Copy)]
 struct Ports {
@@ -28,52 +90,237 @@ struct Ports {
 
 #[tokio::main]
 async fn main() -> Result<(), AppError> {
+    let args = Args::parse().unwrap_or_else(|error| {
+        eprintln!("{error}\n\n{USAGE}");
+        process::exit(2);
+    });
+
     dotenv().ok();
 
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(
-            env::var("RUST_LOG").unwrap_or_else(|_| {
-                "rust_axum=debug,axum=debug,tower_http=debug,mongodb=debug".into()
-            }),
-        ))
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    let config = Config::load(args.config_file.as_deref()).unwrap_or_else(|errors| {
+        eprintln!("Invalid configuration:");
+        for error in errors {
+            eprintln!("  - {error}");
+        }
+        process::exit(1);
+    });
+    if args.print_config {
+        print!("{}", config.to_redacted_toml());
+        return Ok(());
+    }
+    let config = Arc::new(config);
+
+    telemetry::init()?;
+
+    if args.migrate {
+        open_repositories(&config).await?;
+        println!("Database is up to date");
+        return Ok(());
+    }
+
+    if args.repair_storage {
+        let repos = open_repositories(&config).await?;
+        let repair = repair_storage(
+            &repos,
+            &config.uploads_path,
+            args.dry_run,
+            args.delete_missing,
+        )
+        .await?;
+        let verb = if args.dry_run { "Would move" } else { "Moved" };
+        println!("{verb} {} artifact files", repair.moved.len());
+        for (artifact, newer) in &repair.overwritten {
+            println!("Artifact {artifact} was overwritten by the upload of {newer}");
+        }
+        for artifact in &repair.missing {
+            println!("Artifact {artifact} has no file");
+        }
+        let verb = if args.dry_run {
+            "Would delete"
+        } else {
+            "Deleted"
+        };
+        for artifact in &repair.deleted {
+            println!("{verb} artifact {artifact}");
+        }
+        return Ok(());
+    }
+
+    match remove_incomplete_uploads(&config.uploads_path) {
+        Ok(0) => (),
+        Ok(removed) => tracing::info!(removed, "removed incomplete uploads"),
+        Err(error) => tracing::warn!(%error, "failed to remove incomplete uploads"),
+    }
+
+    let tls = match &config.tls {
+        Some(tls_config) => Some(setup_tls(&config, tls_config).await?),
+        None => None,
+    };
+
+    let repos = open_repositories(&config).await?;
+
+    let draining = Draining::default();
+    let state = AppState {
+        config: config.clone(),
+        repos,
+        tls: tls.as_ref().map(|(_, status)| status.clone()),
+        draining: draining.clone(),
+    };
+    tokio::spawn(systemd::run_watchdog());
+
+    let app = router(state)
+        .await
+        .into_make_service_with_connect_info::<SocketAddr>();
 
+    let handle = Handle::new();
+    tokio::spawn(graceful_shutdown(
+        handle.clone(),
+        draining,
+        config.shutdown_timeout,
+    ));
+    tokio::spawn(notify_ready(handle.clone()));
+
+    let addr = config.listen_addr;
+    let served = match tls {
+        Some((rustls, _)) => {
+            let server = axum_server::bind_rustls(addr, rustls)
+                .handle(handle)
+                .serve(app);
+            server.await
+        }
+        None => {
+            let server = axum_server::bind(addr).handle(handle).serve(app);
+            server.await
+        }
+    };
+    served.expect("Couldn't initialize server!");
+
+    telemetry::shutdown();
+
+    Ok(())
+}
+
+/// Connects to the configured database and applies its pending migrations.
+async fn open_repositories(config: &Config) -> Result<Repositories, AppError> {
+    match &config.database {
+        DatabaseConfig::Mongo { uri } => {
+            let client = database::connect(uri).await?;
+            migrations::run(&client).await?;
+            Ok(Repositories::mongo(client))
+        }
+        DatabaseConfig::Sqlite { path } => Repositories::sqlite(path),
+    }
+}
+
+/// Starts the HTTP to HTTPS redirect, obtains a certificate over ACME when enabled, and loads
+/// the certificate served over HTTPS, reloading it when it changes.
+async fn setup_tls(
+    config: &Config,
+    tls_config: &TlsConfig,
+) -> Result<(RustlsConfig, TlsStatus), AppError> {
     let ports = Ports {
-        http: env::var("HTTP_PORT")
-            .unwrap_or_else(|_| "3001".to_string())
-            .parse()
-            .expect("Failed to parse HTTP_PORT"),
-        https: env::var("HTTPS_PORT")
-            .unwrap_or_else(|_| "3002".to_string())
-            .parse()
-            .expect("Failed to parse HTTP_PORT"),
+        http: config.http_port,
+        https: config.https_port,
     };
+    let acme = tls_config.acme.clone();
+    let challenges = AcmeChallenges::default();
 
     // spawn a second server to redirect http requests to this server
-    tokio::spawn(redirect_http_to_https(ports));
+    tokio::spawn(redirect_http_to_https(
+        ports,
+        acme.as_ref().map(|_| challenges.clone()),
+    ));
 
     // configure certificate and private key used by https
-    let config = RustlsConfig::from_pem_file(
-        PathBuf::from(env::var("CERTS_PATH").expect("CERTS_PATH not set")).join("cert.pem"),
-        PathBuf::from(env::var("CERTS_PATH").expect("CERTS_PATH not set")).join("key.pem"),
-    )
-    .await?;
+    let cert_paths = CertificatePaths::new(&tls_config.certs_path);
+    if let Some(acme) = &acme {
+        if acme::needs_renewal(&cert_paths, acme.renew_before_days) {
+            if let Err(error) = acme::issue_certificate(acme, &challenges, &cert_paths).await {
+                if !cert_paths.cert.exists() {
+                    return Err(error);
+                }
+                tracing::error!(
+                    ?error,
+                    "failed to renew TLS certificate over ACME, serving the current one"
+                );
+            }
+        }
+    }
+    let rustls = RustlsConfig::from_pem_file(&cert_paths.cert, &cert_paths.key).await?;
 
-    let db = database::connect().await?;
+    let tls = TlsStatus::default();
+    tls.set(CertificateInfo::from_pem_file(&cert_paths.cert)?);
+    tokio::spawn(watch_certificates(
+        rustls.clone(),
+        cert_paths.clone(),
+        tls.clone(),
+        tls_config.reload_interval,
+    ));
+    if let Some(acme) = acme {
+        tokio::spawn(acme::renew_certificates(
+            acme,
+            challenges,
+            rustls.clone(),
+            cert_paths,
+            tls.clone(),
+        ));
+    }
 
-    let app = router(db).await;
+    Ok((rustls, tls))
+}
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], ports.https));
-    axum_server::bind_rustls(addr, config)
-        .serve(app.into_make_service())
-        .await
-        .expect("Couldn't initialize server!");
+/// Tells systemd the server is ready once the listener is bound.
+async fn notify_ready(handle: Handle) {
+    let addr = match handle.listening().await {
+        Some(addr) => addr,
+        None => return,
+    };
+    tracing::info!("listening on {}", addr);
+    if let Err(error) = systemd::notify("READY=1") {
+        tracing::warn!(%error, "failed to notify systemd");
+    }
+}
 
-    Ok(())
+/// Waits for SIGINT or SIGTERM, then stops accepting connections and gives in-flight requests,
+/// such as uploads, `drain_timeout` to finish before closing them.
+async fn graceful_shutdown(handle: Handle, draining: Draining, drain_timeout: Duration) {
+    let ctrl_c = async {
+        signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    tracing::info!(
+        connections = handle.connection_count(),
+        timeout_secs = drain_timeout.as_secs(),
+        "shutting down, draining in-flight requests"
+    );
+    if let Err(error) = systemd::notify("STOPPING=1") {
+        tracing::warn!(%error, "failed to notify systemd");
+    }
+    draining.start();
+    handle.graceful_shutdown(Some(drain_timeout));
 }
 
-async fn redirect_http_to_https(ports: Ports) {
+/// Redirects plain HTTP requests to HTTPS. When ACME is enabled it also answers HTTP-01
+/// challenges, and listens on every interface so the CA can reach it.
+async fn redirect_http_to_https(ports: Ports, challenges: Option<AcmeChallenges>) {
     fn make_https(host: String, uri: Uri, ports: Ports) -> Result<Uri, BoxError> {
         let mut parts = uri.into_parts();
 
@@ -99,11 +346,25 @@ async fn redirect_http_to_https(ports: Ports) {
         }
     };
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], ports.http));
+    let (ip, app) = match challenges {
+        Some(challenges) => (
+            [0, 0, 0, 0],
+            Router::new()
+                .route(
+                    "/.well-known/acme-challenge/:token",
+                    get(acme::serve_challenge),
+                )
+                .fallback(redirect)
+                .with_state(challenges),
+        ),
+        None => ([127, 0, 0, 1], Router::new().fallback(redirect)),
+    };
+
+    let addr = SocketAddr::from((ip, ports.http));
     tracing::debug!("http redirect listening on {}", addr);
 
     axum::Server::bind(&addr)
-        .serve(redirect.into_service().into_make_service())
+        .serve(app.into_make_service())
         .await
         .unwrap();
 }
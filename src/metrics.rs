@@ -0,0 +1,153 @@
+use axum::{
+    extract::MatchedPath,
+    http::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::Lazy;
+use prometheus::{
+    exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::time::Instant;
+
+const NAMESPACE: &str = "appdist";
+
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub uploaded_bytes: IntCounter,
+    pub downloaded_bytes: IntCounter,
+    pub project_artifacts: IntGaugeVec,
+    pub project_storage_bytes: IntGaugeVec,
+    pub mongo_command_duration: HistogramVec,
+    pub login_failures: IntCounter,
+    pub tls_certificate_expiry: IntGauge,
+}
+
+pub static METRICS: Lazy<Metrics> =
+    Lazy::new(|| Metrics::new().expect("Couldn't register metrics"));
+
+impl Metrics {
+    fn new() -> Result<Metrics, prometheus::Error> {
+        let registry = Registry::new();
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled, by route")
+                .namespace(NAMESPACE),
+            &["method", "route", "status"],
+        )?;
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time taken to handle HTTP requests, by route",
+            )
+            .namespace(NAMESPACE)
+            .buckets(exponential_buckets(0.005, 2.0, 14)?),
+            &["method", "route"],
+        )?;
+        let uploaded_bytes = IntCounter::with_opts(
+            Opts::new(
+                "artifact_uploaded_bytes_total",
+                "Bytes of artifacts uploaded",
+            )
+            .namespace(NAMESPACE),
+        )?;
+        let downloaded_bytes = IntCounter::with_opts(
+            Opts::new(
+                "artifact_downloaded_bytes_total",
+                "Bytes of artifacts downloaded",
+            )
+            .namespace(NAMESPACE),
+        )?;
+        let project_artifacts = IntGaugeVec::new(
+            Opts::new("project_artifacts", "Artifacts stored, by project").namespace(NAMESPACE),
+            &["project_id"],
+        )?;
+        let project_storage_bytes = IntGaugeVec::new(
+            Opts::new(
+                "project_storage_bytes",
+                "Bytes in each project's uploads directory, as measured on disk",
+            )
+            .namespace(NAMESPACE),
+            &["project_id"],
+        )?;
+        let mongo_command_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "mongodb_command_duration_seconds",
+                "Time taken by Mongo commands, by command name",
+            )
+            .namespace(NAMESPACE)
+            .buckets(exponential_buckets(0.001, 2.0, 12)?),
+            &["command"],
+        )?;
+        let login_failures = IntCounter::with_opts(
+            Opts::new("login_failures_total", "Failed login attempts").namespace(NAMESPACE),
+        )?;
+        let tls_certificate_expiry = IntGauge::with_opts(
+            Opts::new(
+                "tls_certificate_expiry_timestamp_seconds",
+                "Expiry of the TLS certificate being served, as a Unix timestamp",
+            )
+            .namespace(NAMESPACE),
+        )?;
+
+        registry.register(Box::new(http_requests.clone()))?;
+        registry.register(Box::new(http_request_duration.clone()))?;
+        registry.register(Box::new(uploaded_bytes.clone()))?;
+        registry.register(Box::new(downloaded_bytes.clone()))?;
+        registry.register(Box::new(project_artifacts.clone()))?;
+        registry.register(Box::new(project_storage_bytes.clone()))?;
+        registry.register(Box::new(mongo_command_duration.clone()))?;
+        registry.register(Box::new(login_failures.clone()))?;
+        registry.register(Box::new(tls_certificate_expiry.clone()))?;
+
+        Ok(Metrics {
+            registry,
+            http_requests,
+            http_request_duration,
+            uploaded_bytes,
+            downloaded_bytes,
+            project_artifacts,
+            project_storage_bytes,
+            mongo_command_duration,
+            login_failures,
+            tls_certificate_expiry,
+        })
+    }
+
+    /// Renders every registered metric in the Prometheus text exposition format.
+    pub fn render(&self) -> Result<(String, Vec<u8>), prometheus::Error> {
+        let encoder = TextEncoder::new();
+        let mut buffer = Vec::new();
+        encoder.encode(&self.registry.gather(), &mut buffer)?;
+        Ok((encoder.format_type().to_string(), buffer))
+    }
+}
+
+/// Counts requests and measures their latency, labelled by the route template that matched
+/// them rather than the raw path, so ids don't blow up the number of series.
+pub async fn track_http_metrics<B>(request: Request<B>, next: Next<B>) -> Response {
+    let method = request.method().to_string();
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(path) => path.as_str().to_string(),
+        None => "unmatched".to_string(),
+    };
+
+    let start = Instant::now();
+    let response = next.run(request).await.into_response();
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let status = response.status().as_u16().to_string();
+    METRICS
+        .http_requests
+        .with_label_values(&[&method, &route, &status])
+        .inc();
+    METRICS
+        .http_request_duration
+        .with_label_values(&[&method, &route])
+        .observe(elapsed);
+
+    response
+}
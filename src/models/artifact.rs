@@ -1,16 +1,32 @@
 use core::fmt;
-use std::time::{SystemTime, SystemTimeError};
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    time::{SystemTime, SystemTimeError},
+};
 
 use bson::serde_helpers::{
     deserialize_hex_string_from_object_id, serialize_hex_string_as_object_id, serialize_u64_as_i64,
 };
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    error::{AppError, FieldError},
+    helpers::artifact::create_file_path,
+};
 
-use crate::{error::AppError, helpers::artifact::create_file_path};
+use super::project::{Platforms, Project};
 
-use super::project::Project;
+const RELEASE_NOTES_MAX_LENGTH: usize = 20000;
+const COMMIT_MESSAGE_MAX_LENGTH: usize = 2000;
+const AUTHOR_MAX_LENGTH: usize = 200;
+const TAGS_MAX_COUNT: usize = 20;
+const TAG_MAX_LENGTH: usize = 50;
+const METADATA_MAX_COUNT: usize = 20;
+const METADATA_KEY_MAX_LENGTH: usize = 50;
+const METADATA_VALUE_MAX_LENGTH: usize = 200;
 
 #[derive(Serialize, Deserialize, ToSchema, Debug)]
 #[serde(rename_all = "lowercase")]
@@ -33,6 +49,24 @@ pub struct IosMetadata {
     pub bundle_version: String,
 }
 
+#[derive(Serialize, Deserialize, Default, ToSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AndroidMetadata {
+    pub package_name: String,
+    pub version_code: String,
+}
+
+/// Where a build came from, as reported by whoever uploaded it.
+#[derive(Serialize, Deserialize, Default, ToSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildInfo {
+    pub commit_sha: Option<String>,
+    pub commit_message: Option<String>,
+    /// Link to the CI job that produced the build.
+    pub build_url: Option<String>,
+    pub author: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, ToSchema, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Artifact {
@@ -46,6 +80,10 @@ pub struct Artifact {
     branch: String,
     extension: ArtifactExtensions,
     path: String,
+    #[serde(
+        serialize_with = "serialize_hex_string_as_object_id",
+        deserialize_with = "deserialize_hex_string_from_object_id"
+    )]
     project_id: String,
     project: Option<Project>,
     mime_type: String,
@@ -53,8 +91,18 @@ pub struct Artifact {
     identifier: String,
     #[serde(serialize_with = "serialize_u64_as_i64")]
     created_at: u64,
-    qrcode: Option<String>,
     ios_metadata: Option<IosMetadata>,
+    android_metadata: Option<AndroidMetadata>,
+    /// Markdown describing what changed in this build.
+    release_notes: Option<String>,
+    #[serde(default)]
+    build_info: BuildInfo,
+    /// Free-form labels, e.g. `staging` or `JIRA-1234`.
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Free-form annotations, e.g. `flavor: free` or `buildType: release`.
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
 }
 
 impl Artifact {
@@ -62,7 +110,7 @@ impl Artifact {
         let duration = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
 
         Ok(Artifact {
-            id: ObjectId::new().to_string(),
+            id: data.id,
             branch: data.branch,
             extension: data.extension,
             identifier: data.identifier,
@@ -72,12 +120,99 @@ impl Artifact {
             project_id: data.project_id,
             size: data.size,
             ios_metadata: data.ios_metdata,
+            android_metadata: data.android_metadata,
+            release_notes: None,
+            build_info: BuildInfo::default(),
+            tags: Vec::new(),
+            metadata: BTreeMap::new(),
             created_at: duration.as_secs() * 1000,
-            qrcode: None,
             project: None,
         })
     }
 
+    pub fn get_id(&self) -> &String {
+        &self.id
+    }
+
+    pub fn get_project_id(&self) -> &String {
+        &self.project_id
+    }
+
+    pub fn get_branch(&self) -> &String {
+        &self.branch
+    }
+
+    pub fn get_identifier(&self) -> &String {
+        &self.identifier
+    }
+
+    pub fn get_created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    pub fn get_ios_metadata(&self) -> Option<&IosMetadata> {
+        self.ios_metadata.as_ref()
+    }
+
+    pub fn get_android_metadata(&self) -> Option<&AndroidMetadata> {
+        self.android_metadata.as_ref()
+    }
+
+    pub fn get_release_notes(&self) -> Option<&String> {
+        self.release_notes.as_ref()
+    }
+
+    pub fn get_build_info(&self) -> &BuildInfo {
+        &self.build_info
+    }
+
+    pub fn get_tags(&self) -> &Vec<String> {
+        &self.tags
+    }
+
+    pub fn get_metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+
+    /// Applies the details present in `input`; empty values remove them. Tags and metadata are
+    /// replaced as a whole.
+    pub fn set_details(&mut self, input: ArtifactDetailsInput) {
+        fn apply(field: &mut Option<String>, value: Option<String>) {
+            if let Some(value) = value {
+                *field = Some(value.trim().to_string()).filter(|value| !value.is_empty());
+            }
+        }
+        apply(&mut self.release_notes, input.release_notes);
+        apply(&mut self.build_info.commit_sha, input.commit_sha);
+        apply(&mut self.build_info.commit_message, input.commit_message);
+        apply(&mut self.build_info.build_url, input.build_url);
+        apply(&mut self.build_info.author, input.author);
+        if let Some(tags) = input.tags {
+            self.tags.clear();
+            for tag in tags.iter().map(|tag| tag.trim()) {
+                if !tag.is_empty() && !self.tags.iter().any(|existing| existing == tag) {
+                    self.tags.push(tag.to_string());
+                }
+            }
+        }
+        if let Some(metadata) = input.metadata {
+            self.metadata = metadata
+                .into_iter()
+                .map(|(key, value)| (key, value.trim().to_string()))
+                .filter(|(_, value)| !value.is_empty())
+                .collect();
+        }
+    }
+
+    /// The iOS bundle identifier or Android package name, with the build number.
+    pub fn get_bundle_data(&self) -> Option<(&String, &String)> {
+        match (&self.ios_metadata, &self.android_metadata) {
+            (Some(ios), _) => Some((&ios.bundle_identifier, &ios.bundle_version)),
+            (None, Some(android)) => Some((&android.package_name, &android.version_code)),
+            (None, None) => None,
+        }
+    }
+
     pub fn get_extension(&self) -> &ArtifactExtensions {
         &self.extension
     }
@@ -90,8 +225,11 @@ impl Artifact {
         (&self.original_filename, &self.mime_type, &self.size)
     }
 
-    pub fn get_plist_data(&self) -> Result<(String, String, String, String), AppError> {
-        if let (Some(ios_metadata), Some(project)) = (&self.ios_metadata, &self.project) {
+    pub fn get_plist_data(
+        &self,
+        project: &Project,
+    ) -> Result<(String, String, String, String), AppError> {
+        if let Some(ios_metadata) = &self.ios_metadata {
             Ok((
                 self.id.clone(),
                 ios_metadata.bundle_identifier.clone(),
@@ -104,6 +242,14 @@ impl Artifact {
     }
 }
 
+/// A project's artifacts built from the same branch.
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct BranchArtifacts {
+    #[serde(rename = "_id")]
+    pub branch: String,
+    pub artifacts: Vec<Artifact>,
+}
+
 #[derive(Deserialize, Default)]
 pub struct CreateArtifact {
     pub branch: Option<String>,
@@ -113,6 +259,7 @@ pub struct CreateArtifact {
     pub extension: Option<ArtifactExtensions>,
     pub size: Option<usize>,
     pub metadata: Option<IosMetadata>,
+    pub android_metadata: Option<AndroidMetadata>,
 }
 
 #[allow(dead_code)]
@@ -122,11 +269,27 @@ pub struct CreateArtifactInput {
     identifier: Option<String>,
     bundle_identifier: Option<String>,
     bundle_version: Option<String>,
+    /// Markdown describing what changed in this build.
+    release_notes: Option<String>,
+    commit_sha: Option<String>,
+    commit_message: Option<String>,
+    /// Link to the CI job that produced the build.
+    build_url: Option<String>,
+    author: Option<String>,
+    /// Comma-separated tags; the field may be repeated.
+    tags: Option<String>,
+    /// Metadata entries are sent as one `metadata.<key>` field each, e.g. `metadata.flavor`.
+    metadata: Option<String>,
+    /// Package name of Android builds, for update checks.
+    package_name: Option<String>,
+    /// Version code of Android builds, for update checks.
+    version_code: Option<String>,
     #[schema(value_type = String, format = Binary)]
     file: String,
 }
 
 pub struct ArtifactToCreate {
+    id: String,
     extension: ArtifactExtensions,
     path: String,
     original_filename: String,
@@ -136,25 +299,32 @@ pub struct ArtifactToCreate {
     branch: String,
     identifier: String,
     ios_metdata: Option<IosMetadata>,
+    android_metadata: Option<AndroidMetadata>,
 }
 
 impl ArtifactToCreate {
-    pub fn new(data: CreateArtifact, project_id: String) -> Result<ArtifactToCreate, AppError> {
+    pub fn new(
+        data: CreateArtifact,
+        project_id: String,
+        uploads_path: &Path,
+    ) -> Result<ArtifactToCreate, AppError> {
         let branch = data.branch.unwrap_or_else(|| "develop".to_string());
         let identifier = data
             .identifier
             .unwrap_or_else(|| "unidentified".to_string());
         let extension = data.extension.unwrap_or(ArtifactExtensions::Apk);
+        let id = ObjectId::new().to_string();
         let path = create_file_path(
+            uploads_path,
             &project_id,
-            &branch,
-            &identifier,
+            &id,
             &extension.to_string().to_lowercase(),
         )?;
         if let (Some(original_filename), Some(mime_type), Some(size)) =
             (data.original_filename, data.mime_type, data.size)
         {
             Ok(ArtifactToCreate {
+                id,
                 original_filename,
                 mime_type,
                 size,
@@ -164,6 +334,7 @@ impl ArtifactToCreate {
                 path,
                 project_id,
                 ios_metdata: data.metadata,
+                android_metadata: data.android_metadata,
             })
         } else {
             Err(AppError::Never)
@@ -173,4 +344,149 @@ impl ArtifactToCreate {
 
 #[derive(ToSchema)]
 #[schema(value_type = String, format = Binary)]
-pub struct ArtifactBinary(String);
+pub struct ArtifactBinary(pub String);
+
+/// Release notes, build information, tags and metadata, set on upload or edited afterwards.
+/// Fields left out are unchanged and empty ones are removed.
+#[derive(Deserialize, Default, ToSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactDetailsInput {
+    /// Markdown describing what changed in this build.
+    pub release_notes: Option<String>,
+    pub commit_sha: Option<String>,
+    pub commit_message: Option<String>,
+    /// Link to the CI job that produced the build.
+    pub build_url: Option<String>,
+    pub author: Option<String>,
+    /// Free-form labels, e.g. `staging` or `JIRA-1234`. Replaces the current tags.
+    pub tags: Option<Vec<String>>,
+    /// Free-form annotations, e.g. `flavor: free`. Replaces the current metadata.
+    pub metadata: Option<BTreeMap<String, String>>,
+}
+
+impl ArtifactDetailsInput {
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        let too_long = |value: &Option<String>, max: usize| {
+            value
+                .as_ref()
+                .is_some_and(|value| value.trim().chars().count() > max)
+        };
+        if too_long(&self.release_notes, RELEASE_NOTES_MAX_LENGTH) {
+            errors.push(FieldError::new(
+                "releaseNotes",
+                &format!(
+                    "Release notes must be at most {RELEASE_NOTES_MAX_LENGTH} characters long"
+                ),
+            ));
+        }
+        if let Some(sha) = self.commit_sha.as_deref().map(str::trim) {
+            let valid = (7..=64).contains(&sha.len()) && sha.chars().all(|c| c.is_ascii_hexdigit());
+            if !sha.is_empty() && !valid {
+                errors.push(FieldError::new(
+                    "commitSha",
+                    "Commit SHA must be 7 to 64 hexadecimal characters",
+                ));
+            }
+        }
+        if too_long(&self.commit_message, COMMIT_MESSAGE_MAX_LENGTH) {
+            errors.push(FieldError::new(
+                "commitMessage",
+                &format!(
+                    "Commit message must be at most {COMMIT_MESSAGE_MAX_LENGTH} characters long"
+                ),
+            ));
+        }
+        if let Some(url) = self.build_url.as_deref().map(str::trim) {
+            if !url.is_empty() && !url.starts_with("https://") && !url.starts_with("http://") {
+                errors.push(FieldError::new(
+                    "buildUrl",
+                    "Build URL must be an http or https link",
+                ));
+            }
+        }
+        if too_long(&self.author, AUTHOR_MAX_LENGTH) {
+            errors.push(FieldError::new(
+                "author",
+                &format!("Author must be at most {AUTHOR_MAX_LENGTH} characters long"),
+            ));
+        }
+        if let Some(tags) = &self.tags {
+            if tags.len() > TAGS_MAX_COUNT {
+                errors.push(FieldError::new(
+                    "tags",
+                    &format!("At most {TAGS_MAX_COUNT} tags are allowed"),
+                ));
+            } else if tags.iter().any(|tag| {
+                tag.trim().chars().count() > TAG_MAX_LENGTH
+                    || tag.trim().contains(|c: char| c == ',' || c.is_whitespace())
+            }) {
+                errors.push(FieldError::new(
+                    "tags",
+                    &format!(
+                        "Tags must be at most {TAG_MAX_LENGTH} characters long, without commas or spaces"
+                    ),
+                ));
+            }
+        }
+        if let Some(metadata) = &self.metadata {
+            if metadata.len() > METADATA_MAX_COUNT {
+                errors.push(FieldError::new(
+                    "metadata",
+                    &format!("At most {METADATA_MAX_COUNT} metadata entries are allowed"),
+                ));
+            } else if metadata.keys().any(|key| !is_metadata_key(key)) {
+                errors.push(FieldError::new(
+                    "metadata",
+                    &format!(
+                        "Metadata keys must be 1 to {METADATA_KEY_MAX_LENGTH} letters, digits, '-' or '_'"
+                    ),
+                ));
+            } else if metadata
+                .values()
+                .any(|value| value.trim().chars().count() > METADATA_VALUE_MAX_LENGTH)
+            {
+                errors.push(FieldError::new(
+                    "metadata",
+                    &format!(
+                        "Metadata values must be at most {METADATA_VALUE_MAX_LENGTH} characters long"
+                    ),
+                ));
+            }
+        }
+        errors
+    }
+}
+
+/// Metadata keys end up in document paths and query strings, so they are kept simple.
+pub fn is_metadata_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.chars().count() <= METADATA_KEY_MAX_LENGTH
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Which of a project's artifacts the stable "latest" links resolve to.
+#[derive(Serialize, Deserialize, IntoParams, Debug)]
+#[into_params(parameter_in = Query)]
+pub struct LatestArtifactQuery {
+    /// Only consider artifacts built from this branch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Only consider artifacts installable on this platform.
+    #[param(inline)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform: Option<Platforms>,
+}
+
+impl LatestArtifactQuery {
+    /// The query string selecting the same artifacts, with its leading `?`.
+    pub fn to_query_string(&self) -> Result<String, AppError> {
+        match serde_urlencoded::to_string(self) {
+            Ok(query) if query.is_empty() => Ok(query),
+            Ok(query) => Ok(format!("?{query}")),
+            Err(_) => Err(AppError::Never),
+        }
+    }
+}
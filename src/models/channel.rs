@@ -0,0 +1,153 @@
+use bson::serde_helpers::{
+    deserialize_hex_string_from_object_id, serialize_hex_string_as_object_id,
+};
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::error::FieldError;
+
+const NAME_MAX_LENGTH: usize = 32;
+const NOTE_MAX_LENGTH: usize = 500;
+
+/// A named release channel of a project, e.g. `internal`, `beta` or `production-candidate`.
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Channel {
+    #[serde(
+        rename = "_id",
+        serialize_with = "serialize_hex_string_as_object_id",
+        deserialize_with = "deserialize_hex_string_from_object_id"
+    )]
+    pub id: String,
+    #[serde(
+        serialize_with = "serialize_hex_string_as_object_id",
+        deserialize_with = "deserialize_hex_string_from_object_id"
+    )]
+    pub project_id: String,
+    pub name: String,
+    pub created_at: i64,
+}
+
+impl Channel {
+    pub fn new(input: CreateChannelInput, project_id: String) -> Channel {
+        Channel {
+            id: ObjectId::new().to_string(),
+            project_id,
+            name: input.name,
+            created_at: Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+/// A record of an artifact being promoted into a channel. The newest promotion of a channel
+/// is the build it currently offers.
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Promotion {
+    #[serde(
+        rename = "_id",
+        serialize_with = "serialize_hex_string_as_object_id",
+        deserialize_with = "deserialize_hex_string_from_object_id"
+    )]
+    pub id: String,
+    #[serde(
+        serialize_with = "serialize_hex_string_as_object_id",
+        deserialize_with = "deserialize_hex_string_from_object_id"
+    )]
+    pub project_id: String,
+    pub channel: String,
+    #[serde(
+        serialize_with = "serialize_hex_string_as_object_id",
+        deserialize_with = "deserialize_hex_string_from_object_id"
+    )]
+    pub artifact_id: String,
+    /// Id of the user who promoted the artifact.
+    #[serde(
+        serialize_with = "serialize_hex_string_as_object_id",
+        deserialize_with = "deserialize_hex_string_from_object_id"
+    )]
+    pub promoted_by: String,
+    pub promoted_at: i64,
+    pub note: Option<String>,
+}
+
+impl Promotion {
+    pub fn new(input: PromoteArtifactInput, channel: &Channel, user_id: String) -> Promotion {
+        Promotion {
+            id: ObjectId::new().to_string(),
+            project_id: channel.project_id.clone(),
+            channel: channel.name.clone(),
+            artifact_id: input.artifact_id,
+            promoted_by: user_id,
+            promoted_at: Utc::now().timestamp_millis(),
+            note: input.note.filter(|note| !note.trim().is_empty()),
+        }
+    }
+}
+
+/// A channel along with the build it currently offers, if any was promoted yet.
+#[derive(Serialize, ToSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelOutput {
+    #[serde(flatten)]
+    pub channel: Channel,
+    pub latest: Option<Promotion>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateChannelInput {
+    /// Lowercase letters, digits, `-` and `_`, up to 32 characters.
+    pub name: String,
+}
+
+impl CreateChannelInput {
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.name.is_empty() {
+            errors.push(FieldError::new("name", "Name must not be empty"));
+        } else if self.name.chars().count() > NAME_MAX_LENGTH {
+            errors.push(FieldError::new(
+                "name",
+                &format!("Name must be at most {NAME_MAX_LENGTH} characters long"),
+            ));
+        } else if !self
+            .name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        {
+            errors.push(FieldError::new(
+                "name",
+                "Name may only contain lowercase letters, digits, '-' and '_'",
+            ));
+        }
+        errors
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PromoteArtifactInput {
+    pub artifact_id: String,
+    /// Why the build was promoted, e.g. the QA sign-off it passed.
+    pub note: Option<String>,
+}
+
+impl PromoteArtifactInput {
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if ObjectId::parse_str(&self.artifact_id).is_err() {
+            errors.push(FieldError::new("artifactId", "Invalid artifact id"));
+        }
+        if let Some(note) = &self.note {
+            if note.chars().count() > NOTE_MAX_LENGTH {
+                errors.push(FieldError::new(
+                    "note",
+                    &format!("Note must be at most {NOTE_MAX_LENGTH} characters long"),
+                ));
+            }
+        }
+        errors
+    }
+}
@@ -0,0 +1,61 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Ready,
+    Unready,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct LivenessReport {
+    pub status: HealthStatus,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct CheckResult {
+    pub name: String,
+    pub ok: bool,
+    pub message: String,
+}
+
+impl CheckResult {
+    pub fn pass(name: &str, message: String) -> CheckResult {
+        CheckResult {
+            name: name.to_string(),
+            ok: true,
+            message,
+        }
+    }
+
+    pub fn fail(name: &str, message: String) -> CheckResult {
+        CheckResult {
+            name: name.to_string(),
+            ok: false,
+            message,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct ReadinessReport {
+    pub status: HealthStatus,
+    pub checks: Vec<CheckResult>,
+}
+
+impl ReadinessReport {
+    pub fn new(checks: Vec<CheckResult>) -> ReadinessReport {
+        let status = if checks.iter().all(|check| check.ok) {
+            HealthStatus::Ready
+        } else {
+            HealthStatus::Unready
+        };
+        ReadinessReport { status, checks }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        matches!(self.status, HealthStatus::Ready)
+    }
+}
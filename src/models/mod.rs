@@ -1,3 +1,9 @@
 pub mod artifact;
+pub mod channel;
+pub mod health;
+pub mod pagination;
 pub mod project;
+pub mod qrcode;
+pub mod search;
+pub mod update;
 pub mod user;
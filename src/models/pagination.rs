@@ -0,0 +1,293 @@
+use bson::{doc, oid::ObjectId, Bson, Document};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    error::AppError,
+    helpers::base64::{decode_base64_url, encode_base64_url},
+};
+
+use super::{
+    artifact::{is_metadata_key, Artifact, ArtifactExtensions},
+    project::{Platforms, Project},
+    user::User,
+};
+
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+const MAX_PAGE_LIMIT: i64 = 200;
+
+#[derive(Deserialize, IntoParams, Debug)]
+#[into_params(parameter_in = Query)]
+pub struct PageQuery {
+    /// Maximum number of items to return, between 1 and 200. Defaults to 50.
+    pub limit: Option<i64>,
+    /// Opaque cursor returned as `nextCursor` by the previous page.
+    pub cursor: Option<String>,
+    /// Field to sort by, prefixed with `-` for descending order.
+    pub sort: Option<String>,
+}
+
+pub struct SortSpec {
+    pub field: String,
+    pub descending: bool,
+}
+
+impl SortSpec {
+    fn direction(&self) -> i32 {
+        if self.descending {
+            -1
+        } else {
+            1
+        }
+    }
+
+    pub fn to_document(&self) -> Document {
+        doc! { self.field.clone(): self.direction(), "_id": self.direction() }
+    }
+}
+
+impl PageQuery {
+    pub fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_LIMIT)
+            .clamp(1, MAX_PAGE_LIMIT)
+    }
+
+    pub fn sort_spec(&self, allowed: &[&str], default: &str) -> Result<SortSpec, AppError> {
+        let sort = self.sort.as_deref().unwrap_or(default);
+        let (field, descending) = match sort.strip_prefix('-') {
+            Some(field) => (field, true),
+            None => (sort, false),
+        };
+        if !allowed.contains(&field) {
+            return Err(AppError::InvalidSortField(field.to_string()));
+        }
+        Ok(SortSpec {
+            field: field.to_string(),
+            descending,
+        })
+    }
+
+    /// Decodes the cursor, checking that it was issued for the same sort field.
+    pub fn cursor(&self, sort: &SortSpec) -> Result<Option<Cursor>, AppError> {
+        let cursor = match &self.cursor {
+            Some(cursor) => Cursor::decode(cursor)?,
+            None => return Ok(None),
+        };
+        if cursor.field != sort.field {
+            return Err(AppError::InvalidCursor);
+        }
+        Ok(Some(cursor))
+    }
+
+    /// Builds the keyset filter that skips every document up to and including the cursor.
+    pub fn cursor_filter(&self, sort: &SortSpec) -> Result<Option<Document>, AppError> {
+        let cursor = match self.cursor(sort)? {
+            Some(cursor) => cursor,
+            None => return Ok(None),
+        };
+
+        let op = if sort.descending { "$lt" } else { "$gt" };
+        Ok(Some(doc! {
+            "$or": [
+                { sort.field.clone(): { op: cursor.value.clone() } },
+                { sort.field.clone(): cursor.value, "_id": { op: cursor.id } },
+            ]
+        }))
+    }
+}
+
+pub struct Cursor {
+    pub field: String,
+    pub value: Bson,
+    pub id: ObjectId,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> Result<String, AppError> {
+        let document = doc! { "f": &self.field, "v": self.value.clone(), "id": self.id };
+        let bytes = bson::to_vec(&document).map_err(|_| AppError::InvalidCursor)?;
+        Ok(encode_base64_url(&bytes))
+    }
+
+    fn decode(cursor: &str) -> Result<Cursor, AppError> {
+        let bytes = decode_base64_url(cursor).map_err(|_| AppError::InvalidCursor)?;
+        let document: Document = bson::from_slice(&bytes).map_err(|_| AppError::InvalidCursor)?;
+        match (
+            document.get_str("f"),
+            document.get("v"),
+            document.get_object_id("id"),
+        ) {
+            (Ok(field), Some(value), Ok(id)) => Ok(Cursor {
+                field: field.to_string(),
+                value: value.clone(),
+                id,
+            }),
+            _ => Err(AppError::InvalidCursor),
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ArtifactFilters {
+    /// Only return artifacts of this project.
+    pub project_id: Option<String>,
+    /// Only return artifacts built from this branch.
+    pub branch: Option<String>,
+    /// Only return artifacts with this file extension.
+    #[param(inline)]
+    pub extension: Option<ArtifactExtensions>,
+    /// Only return artifacts created at or after this timestamp, in milliseconds.
+    pub created_after: Option<i64>,
+    /// Only return artifacts created at or before this timestamp, in milliseconds.
+    pub created_before: Option<i64>,
+    /// Only return artifacts installable on this platform.
+    #[param(inline)]
+    pub platform: Option<Platforms>,
+    /// Only return artifacts with this iOS bundle identifier or Android package name.
+    pub bundle_identifier: Option<String>,
+    /// Only return artifacts with this tag.
+    pub tag: Option<String>,
+    /// Only return artifacts with this metadata entry, written as `key:value`.
+    pub metadata: Option<String>,
+}
+
+impl ArtifactFilters {
+    pub fn to_document(&self) -> Result<Document, AppError> {
+        let mut conditions: Vec<Document> = Vec::new();
+        if let Some(project_id) = &self.project_id {
+            conditions.push(doc! { "projectId": ObjectId::parse_str(project_id)? });
+        }
+        if let Some(branch) = &self.branch {
+            conditions.push(doc! { "branch": branch });
+        }
+        if let Some(extension) = &self.extension {
+            conditions.push(doc! { "extension": bson::to_bson(extension)? });
+        }
+        if let Some(created_after) = self.created_after {
+            conditions.push(doc! { "createdAt": { "$gte": created_after } });
+        }
+        if let Some(created_before) = self.created_before {
+            conditions.push(doc! { "createdAt": { "$lte": created_before } });
+        }
+        match &self.platform {
+            Some(Platforms::Ios) => conditions.push(doc! { "extension": "ipa" }),
+            Some(Platforms::Android) => {
+                conditions.push(doc! { "extension": { "$in": ["apk", "aab"] } })
+            }
+            None => (),
+        }
+        if let Some(bundle_identifier) = &self.bundle_identifier {
+            conditions.push(doc! {
+                "$or": [
+                    { "iosMetadata.bundleIdentifier": bundle_identifier },
+                    { "androidMetadata.packageName": bundle_identifier },
+                ]
+            });
+        }
+        if let Some(tag) = &self.tag {
+            conditions.push(doc! { "tags": tag });
+        }
+        if let Some((key, value)) = self.metadata_entry()? {
+            conditions.push(doc! { format!("metadata.{key}"): value });
+        }
+        Ok(and_filter(conditions))
+    }
+
+    /// The `metadata` filter split into key and value.
+    pub fn metadata_entry(&self) -> Result<Option<(&str, &str)>, AppError> {
+        match self.metadata.as_deref().map(|entry| entry.split_once(':')) {
+            None => Ok(None),
+            Some(Some((key, value))) if is_metadata_key(key) => Ok(Some((key, value))),
+            Some(_) => Err(AppError::InvalidFilter("metadata".to_string())),
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams, Debug)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ProjectFilters {
+    /// Only return projects targeting this platform.
+    #[param(inline)]
+    pub platform: Option<Platforms>,
+    /// Also return archived projects. Defaults to false.
+    pub include_archived: Option<bool>,
+}
+
+impl ProjectFilters {
+    pub fn to_document(&self) -> Result<Document, AppError> {
+        let mut conditions: Vec<Document> = Vec::new();
+        if !self.include_archived.unwrap_or(false) {
+            conditions.push(doc! { "archived": { "$ne": true } });
+        }
+        if let Some(platform) = &self.platform {
+            conditions.push(doc! { "platforms": bson::to_bson(platform)? });
+        }
+        Ok(and_filter(conditions))
+    }
+}
+
+pub fn and_filter(conditions: Vec<Document>) -> Document {
+    match conditions.len() {
+        0 => Document::new(),
+        1 => conditions.into_iter().next().unwrap_or_default(),
+        _ => doc! { "$and": conditions },
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    pub limit: i64,
+    pub has_more: bool,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[aliases(ArtifactPage = Page<Artifact>, ProjectPage = Page<Project>, UserPage = Page<User>)]
+pub struct Page<T> {
+    pub data: Vec<T>,
+    pub pagination: PageInfo,
+}
+
+impl<T: DeserializeOwned> Page<T> {
+    /// Builds a page out of up to `limit + 1` sorted documents, the extra one only telling
+    /// whether there is a next page.
+    pub fn from_documents(
+        mut documents: Vec<Document>,
+        limit: i64,
+        sort: &SortSpec,
+    ) -> Result<Page<T>, AppError> {
+        let has_more = documents.len() as i64 > limit;
+        documents.truncate(limit as usize);
+
+        let next_cursor = match documents.last() {
+            Some(last) if has_more => Some(
+                Cursor {
+                    field: sort.field.clone(),
+                    value: last.get(&sort.field).cloned().unwrap_or(Bson::Null),
+                    id: last.get_object_id("_id").map_err(|_| AppError::Never)?,
+                }
+                .encode()?,
+            ),
+            _ => None,
+        };
+
+        let mut data: Vec<T> = Vec::with_capacity(documents.len());
+        for document in documents {
+            data.push(bson::from_document(document)?);
+        }
+
+        Ok(Page {
+            data,
+            pagination: PageInfo {
+                limit,
+                has_more,
+                next_cursor,
+            },
+        })
+    }
+}
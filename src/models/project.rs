@@ -1,12 +1,18 @@
 use bson::serde_helpers::{
     deserialize_hex_string_from_object_id, serialize_hex_string_as_object_id,
 };
+use chrono::Utc;
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Serialize, Deserialize, ToSchema, Debug)]
+use crate::error::FieldError;
+
+const NAME_MAX_LENGTH: usize = 100;
+const DESCRIPTION_MAX_LENGTH: usize = 1000;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy)]
 #[serde(rename_all = "lowercase")]
 pub enum Platforms {
     Android,
@@ -32,6 +38,9 @@ pub struct Project {
     pub platforms: Vec<Platforms>,
     key: String,
     pub image: Option<String>,
+    #[serde(default)]
+    pub archived: bool,
+    pub updated_at: Option<i64>,
 }
 
 impl Project {
@@ -41,11 +50,13 @@ impl Project {
         Project {
             id,
             key,
-            name: new_project.name,
+            name: new_project.name.trim().to_string(),
             description: new_project.description,
             owner: owner_id,
             platforms: new_project.platforms,
             image: None,
+            archived: false,
+            updated_at: Some(Utc::now().timestamp_millis()),
         }
     }
 
@@ -61,6 +72,69 @@ pub struct BaseProjectInput {
     pub platforms: Vec<Platforms>,
 }
 
+impl BaseProjectInput {
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        validate_name(&self.name, &mut errors);
+        validate_description(&self.description, &mut errors);
+        validate_platforms(&self.platforms, &mut errors);
+        errors
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateProjectInput {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub platforms: Option<Vec<Platforms>>,
+}
+
+impl UpdateProjectInput {
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if let Some(name) = &self.name {
+            validate_name(name, &mut errors);
+        }
+        if let Some(description) = &self.description {
+            validate_description(description, &mut errors);
+        }
+        if let Some(platforms) = &self.platforms {
+            validate_platforms(platforms, &mut errors);
+        }
+        errors
+    }
+}
+
+fn validate_name(name: &str, errors: &mut Vec<FieldError>) {
+    let name = name.trim();
+    if name.is_empty() {
+        errors.push(FieldError::new("name", "Name must not be empty"));
+    } else if name.chars().count() > NAME_MAX_LENGTH {
+        errors.push(FieldError::new(
+            "name",
+            &format!("Name must be at most {NAME_MAX_LENGTH} characters long"),
+        ));
+    }
+}
+
+fn validate_description(description: &str, errors: &mut Vec<FieldError>) {
+    if description.chars().count() > DESCRIPTION_MAX_LENGTH {
+        errors.push(FieldError::new(
+            "description",
+            &format!("Description must be at most {DESCRIPTION_MAX_LENGTH} characters long"),
+        ));
+    }
+}
+
+fn validate_platforms(platforms: &[Platforms], errors: &mut Vec<FieldError>) {
+    if platforms.is_empty() {
+        errors.push(FieldError::new(
+            "platforms",
+            "At least one platform must be selected",
+        ));
+    }
+}
+
 #[allow(dead_code)]
 #[derive(ToSchema, Debug)]
 pub struct EditImageInput {
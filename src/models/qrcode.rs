@@ -0,0 +1,76 @@
+use qrcode_generator::QrCodeEcc;
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::error::FieldError;
+
+const MIN_SIZE: usize = 64;
+const MAX_SIZE: usize = 1024;
+const DEFAULT_SIZE: usize = 240;
+
+#[derive(Deserialize, ToSchema, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum QrCodeFormat {
+    #[default]
+    Svg,
+    Png,
+}
+
+#[derive(Deserialize, ToSchema, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum QrCodeErrorCorrection {
+    #[default]
+    Low,
+    Medium,
+    Quartile,
+    High,
+}
+
+impl From<QrCodeErrorCorrection> for QrCodeEcc {
+    fn from(level: QrCodeErrorCorrection) -> QrCodeEcc {
+        match level {
+            QrCodeErrorCorrection::Low => QrCodeEcc::Low,
+            QrCodeErrorCorrection::Medium => QrCodeEcc::Medium,
+            QrCodeErrorCorrection::Quartile => QrCodeEcc::Quartile,
+            QrCodeErrorCorrection::High => QrCodeEcc::High,
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams, Debug)]
+#[into_params(parameter_in = Query)]
+pub struct QrCodeQuery {
+    /// Image format. Defaults to `svg`.
+    #[param(inline)]
+    pub format: Option<QrCodeFormat>,
+    /// Width and height of the image in pixels, from 64 to 1024. Defaults to 240.
+    pub size: Option<usize>,
+    /// Error correction level; higher levels survive more damage but are denser. Defaults to `low`.
+    #[param(inline)]
+    pub ecc: Option<QrCodeErrorCorrection>,
+}
+
+impl QrCodeQuery {
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if !(MIN_SIZE..=MAX_SIZE).contains(&self.size()) {
+            errors.push(FieldError::new(
+                "size",
+                &format!("Size must be between {MIN_SIZE} and {MAX_SIZE} pixels"),
+            ));
+        }
+        errors
+    }
+
+    pub fn format(&self) -> QrCodeFormat {
+        self.format.unwrap_or_default()
+    }
+
+    pub fn size(&self) -> usize {
+        self.size.unwrap_or(DEFAULT_SIZE)
+    }
+
+    pub fn ecc(&self) -> QrCodeErrorCorrection {
+        self.ecc.unwrap_or_default()
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::{artifact::Artifact, project::Project};
+
+const DEFAULT_SEARCH_LIMIT: i64 = 20;
+const MAX_SEARCH_LIMIT: i64 = 100;
+
+#[derive(Deserialize, IntoParams, Debug)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    /// Terms to search for, e.g. a ticket number, branch name or bundle identifier.
+    pub q: String,
+    /// Maximum number of results to return, between 1 and 100. Defaults to 20.
+    pub limit: Option<i64>,
+}
+
+impl SearchQuery {
+    pub fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .clamp(1, MAX_SEARCH_LIMIT)
+    }
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchResultKind {
+    Project,
+    Artifact,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct SearchResult {
+    pub kind: SearchResultKind,
+    /// Relevance relative to the best match of the same kind, between 0 and 1.
+    pub score: f64,
+    pub project: Option<Project>,
+    pub artifact: Option<Artifact>,
+}
+
+impl SearchResult {
+    pub fn from_project(project: Project, score: f64) -> SearchResult {
+        SearchResult {
+            kind: SearchResultKind::Project,
+            score,
+            project: Some(project),
+            artifact: None,
+        }
+    }
+
+    pub fn from_artifact(artifact: Artifact, score: f64) -> SearchResult {
+        SearchResult {
+            kind: SearchResultKind::Artifact,
+            score,
+            project: None,
+            artifact: Some(artifact),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::error::FieldError;
+
+use super::project::Platforms;
+
+#[derive(Deserialize, IntoParams, Debug)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct UpdateCheckQuery {
+    /// Platform the app runs on.
+    #[param(inline)]
+    pub platform: Option<Platforms>,
+    /// iOS bundle identifier or Android package name of the app.
+    pub bundle_identifier: Option<String>,
+    /// Version the app is running, e.g. `1.4.0`.
+    pub version: Option<String>,
+    /// Build number the app is running: the bundle version on iOS, the version code on Android.
+    pub build: Option<String>,
+    /// Only offer the build promoted into this release channel.
+    pub channel: Option<String>,
+}
+
+impl UpdateCheckQuery {
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.platform.is_none() {
+            errors.push(FieldError::new("platform", "Platform is required"));
+        }
+        if self.bundle_identifier.as_deref().unwrap_or("").is_empty() {
+            errors.push(FieldError::new(
+                "bundleIdentifier",
+                "Bundle identifier is required",
+            ));
+        }
+        if self.version.as_deref().unwrap_or("").is_empty() {
+            errors.push(FieldError::new("version", "Version is required"));
+        }
+        errors
+    }
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCheck {
+    pub update_available: bool,
+    /// The build to update to, when one is available.
+    pub update: Option<AvailableUpdate>,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AvailableUpdate {
+    pub artifact_id: String,
+    pub version: String,
+    pub build: Option<String>,
+    /// `itms-services` link for iOS builds, download link for Android builds.
+    pub install_url: String,
+    /// Markdown describing what changed in the build.
+    pub release_notes: Option<String>,
+    pub created_at: i64,
+}
@@ -8,7 +8,6 @@ use mongodb::bson::oid::ObjectId;
 use ring::rand::SecureRandom;
 use ring::{digest, pbkdf2, rand};
 use serde::{Deserialize, Serialize};
-use std::env;
 use std::num::NonZeroU32;
 use std::ops::Add;
 use utoipa::ToSchema;
@@ -37,28 +36,38 @@ pub struct Claims {
     pub exp: i64,
 }
 
+/// How long issued tokens stay valid.
+pub const TOKEN_LIFETIME_DAYS: i64 = 40;
+
 #[derive(Serialize, ToSchema)]
 pub struct AuthOutput {
     token: String,
 }
 
 impl AuthOutput {
-    pub fn new(user_email: String, user_id: String) -> Result<AuthOutput, AppError> {
+    pub fn new(
+        user_email: String,
+        user_id: String,
+        jwt_secret: &str,
+    ) -> Result<AuthOutput, AppError> {
         let iat = Utc::now();
-        let exp = iat.add(Duration::days(40));
+        let exp = iat.add(Duration::days(TOKEN_LIFETIME_DAYS));
         let claims = Claims {
             user_id,
             sub: user_email,
             iat: iat.timestamp(),
             exp: exp.timestamp(),
         };
-        let secret = env::var("JWT_SECRET").expect("JWT_SECRET is not set!");
-        let key = EncodingKey::from_secret(secret.as_ref());
+        let key = EncodingKey::from_secret(jwt_secret.as_ref());
         match encode(&Header::default(), &claims, &key) {
             Ok(token) => Ok(AuthOutput { token }),
             Err(e) => Err(AppError::Encode(e)),
         }
     }
+
+    pub fn get_token(&self) -> &str {
+        &self.token
+    }
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
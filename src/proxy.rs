@@ -0,0 +1,175 @@
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRef, FromRequestParts, State},
+    http::{header, request::Parts, HeaderMap, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use crate::{config::Config, error::AppError};
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+const X_FORWARDED_HOST: &str = "x-forwarded-host";
+
+/// Where a request originally came from, taking the headers of trusted proxies into account.
+#[derive(Clone, Debug)]
+pub struct RequestOrigin {
+    pub client_ip: Option<IpAddr>,
+    pub scheme: String,
+    pub host: Option<String>,
+}
+
+/// Resolves the [`RequestOrigin`] of every request and stores it in its extensions. Forwarding
+/// headers are only honoured when the peer is a trusted proxy, since anyone can send them.
+pub async fn resolve_origin<B>(
+    State(config): State<Arc<Config>>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let headers = request.headers();
+    let host = header_value(headers, header::HOST.as_str()).map(str::to_string);
+
+    let origin = match peer {
+        Some(peer) if config.trusts_proxy(peer) => {
+            let forwarded = parse_forwarded(headers);
+            let chain: Vec<IpAddr> = if forwarded.is_empty() {
+                header_values(headers, X_FORWARDED_FOR)
+                    .filter_map(parse_node)
+                    .collect()
+            } else {
+                forwarded.iter().filter_map(|f| f.client).collect()
+            };
+            // walk back from the closest hop, the client is the first address we don't trust
+            let client_ip = chain
+                .iter()
+                .rev()
+                .find(|ip| !config.trusts_proxy(**ip))
+                .or_else(|| chain.first())
+                .copied()
+                .or(Some(peer));
+            let first = forwarded.into_iter().next().unwrap_or_default();
+
+            RequestOrigin {
+                client_ip,
+                scheme: first
+                    .proto
+                    .or_else(|| {
+                        header_values(headers, X_FORWARDED_PROTO)
+                            .next()
+                            .map(str::to_string)
+                    })
+                    .unwrap_or_else(|| config.scheme().to_string()),
+                host: first
+                    .host
+                    .or_else(|| {
+                        header_values(headers, X_FORWARDED_HOST)
+                            .next()
+                            .map(str::to_string)
+                    })
+                    .or(host),
+            }
+        }
+        _ => RequestOrigin {
+            client_ip: peer,
+            scheme: config.scheme().to_string(),
+            host,
+        },
+    };
+
+    request.extensions_mut().insert(origin);
+    next.run(request).await
+}
+
+/// Base URL clients use to reach the server, for links that must be absolute such as the iOS
+/// manifest and QR codes. The configured public URL takes precedence over the request's origin.
+pub struct PublicUrl(pub String);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for PublicUrl
+where
+    Arc<Config>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(url) = &Arc::<Config>::from_ref(state).public_url {
+            return Ok(PublicUrl(url.clone()));
+        }
+
+        match parts.extensions.get::<RequestOrigin>() {
+            Some(RequestOrigin {
+                scheme,
+                host: Some(host),
+                ..
+            }) => Ok(PublicUrl(format!("{scheme}://{host}"))),
+            _ => Err(AppError::UnknownPublicUrl),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ForwardedElement {
+    client: Option<IpAddr>,
+    proto: Option<String>,
+    host: Option<String>,
+}
+
+/// Parses the RFC 7239 `Forwarded` header, one element per hop, closest to the client first.
+fn parse_forwarded(headers: &HeaderMap) -> Vec<ForwardedElement> {
+    header_values(headers, header::FORWARDED.as_str())
+        .map(|element| {
+            let mut forwarded = ForwardedElement::default();
+            for pair in element.split(';') {
+                let (key, value) = match pair.split_once('=') {
+                    Some((key, value)) => (key.trim(), value.trim().trim_matches('"')),
+                    None => continue,
+                };
+                match key.to_ascii_lowercase().as_str() {
+                    "for" => forwarded.client = parse_node(value),
+                    "proto" => forwarded.proto = Some(value.to_ascii_lowercase()),
+                    "host" => forwarded.host = Some(value.to_string()),
+                    _ => (),
+                }
+            }
+            forwarded
+        })
+        .collect()
+}
+
+/// Parses a node such as `192.0.2.1`, `192.0.2.1:4711` or `[2001:db8::1]:4711`. Obfuscated
+/// identifiers like `unknown` or `_hidden` yield `None`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+    if let Some(rest) = node.strip_prefix('[') {
+        return rest.split(']').next()?.parse().ok();
+    }
+    node.parse().ok().or_else(|| {
+        let (ip, _port) = node.rsplit_once(':')?;
+        ip.parse().ok()
+    })
+}
+
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Comma-separated values of every occurrence of a header, in order.
+fn header_values<'a>(headers: &'a HeaderMap, name: &str) -> impl Iterator<Item = &'a str> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
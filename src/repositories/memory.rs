@@ -0,0 +1,769 @@
+use axum::async_trait;
+use bson::{oid::ObjectId, Bson, Document};
+use chrono::Utc;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+use tokio::sync::RwLock;
+
+use crate::{
+    error::AppError,
+    models::{
+        artifact::{Artifact, BranchArtifacts},
+        channel::{Channel, Promotion},
+        pagination::{ArtifactFilters, Page, PageQuery, ProjectFilters, SortSpec},
+        project::{Platforms, Project, UpdateProjectInput},
+        user::User,
+    },
+};
+
+use super::{
+    ArtifactRepo, Backend, ChannelRepo, ProjectRepo, ProjectStorage, Repositories, UserRepo,
+};
+
+/// Same fields and weights as the Mongo text indexes.
+const PROJECT_SEARCH_WEIGHTS: [(&str, f64); 2] = [("name", 10.0), ("description", 2.0)];
+const ARTIFACT_SEARCH_WEIGHTS: [(&str, f64); 5] = [
+    ("branch", 5.0),
+    ("identifier", 5.0),
+    ("originalFilename", 2.0),
+    ("iosMetadata.bundleIdentifier", 2.0),
+    ("androidMetadata.packageName", 2.0),
+];
+
+pub fn repositories() -> Repositories {
+    Repositories {
+        backend: Arc::new(MemoryBackend),
+        projects: Arc::new(MemoryProjectRepo::default()),
+        artifacts: Arc::new(MemoryArtifactRepo::default()),
+        channels: Arc::new(MemoryChannelRepo::default()),
+        users: Arc::new(MemoryUserRepo::default()),
+    }
+}
+
+/// Documents are kept in the same BSON shape Mongo stores them in, so updates and sorting
+/// behave the same way.
+#[derive(Default)]
+struct Store(RwLock<Vec<Document>>);
+
+impl Store {
+    async fn insert<T: Serialize>(&self, value: &T) -> Result<(), AppError> {
+        let document = bson::to_document(value)?;
+        self.0.write().await.push(document);
+        Ok(())
+    }
+
+    async fn find_by_id<T: DeserializeOwned>(&self, id: &str) -> Result<Option<T>, AppError> {
+        let oid = ObjectId::parse_str(id)?;
+        let documents = self.0.read().await;
+        match documents.iter().find(|document| has_id(document, oid)) {
+            Some(document) => Ok(Some(bson::from_document(document.clone())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Sets `fields` on the document with this id. Returns whether it was found and whether
+    /// anything actually changed.
+    async fn update(&self, id: &str, fields: Document) -> Result<(bool, bool), AppError> {
+        let oid = ObjectId::parse_str(id)?;
+        let mut documents = self.0.write().await;
+        match documents.iter_mut().find(|document| has_id(document, oid)) {
+            Some(document) => {
+                let mut modified = false;
+                for (key, value) in fields {
+                    if document.get(&key) != Some(&value) {
+                        document.insert(key, value);
+                        modified = true;
+                    }
+                }
+                Ok((true, modified))
+            }
+            None => Ok((false, false)),
+        }
+    }
+
+    async fn filtered<F>(&self, predicate: F) -> Result<Vec<Document>, AppError>
+    where
+        F: Fn(&Document) -> Result<bool, AppError>,
+    {
+        let mut rows = Vec::new();
+        for document in self.0.read().await.iter() {
+            if predicate(document)? {
+                rows.push(document.clone());
+            }
+        }
+        Ok(rows)
+    }
+
+    async fn search<T: DeserializeOwned>(
+        &self,
+        terms: &str,
+        weights: &[(&str, f64)],
+        filter: impl Fn(&Document) -> bool,
+        limit: i64,
+    ) -> Result<Vec<(T, f64)>, AppError> {
+        let terms: Vec<String> = tokenize(terms).collect();
+        let mut scored: Vec<(Document, f64)> = Vec::new();
+        for document in self
+            .0
+            .read()
+            .await
+            .iter()
+            .filter(|document| filter(document))
+        {
+            let score = text_score(document, weights, &terms);
+            if score > 0.0 {
+                scored.push((document.clone(), score));
+            }
+        }
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(limit as usize);
+
+        let mut rows = Vec::with_capacity(scored.len());
+        for (document, score) in scored {
+            rows.push((bson::from_document(document)?, score));
+        }
+        Ok(rows)
+    }
+}
+
+pub struct MemoryBackend;
+
+#[async_trait]
+impl Backend for MemoryBackend {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn ping(&self) -> Result<(), AppError> {
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryProjectRepo {
+    store: Store,
+}
+
+#[async_trait]
+impl ProjectRepo for MemoryProjectRepo {
+    async fn find_page(
+        &self,
+        filters: &ProjectFilters,
+        page: &PageQuery,
+        sort: SortSpec,
+    ) -> Result<Page<Project>, AppError> {
+        let platform = filters.platform.as_ref().map(bson::to_bson).transpose()?;
+        let include_archived = filters.include_archived.unwrap_or(false);
+        let documents = self
+            .store
+            .filtered(|document| {
+                if !include_archived && is_archived(document) {
+                    return Ok(false);
+                }
+                match (&platform, document.get_array("platforms")) {
+                    (None, _) => Ok(true),
+                    (Some(platform), Ok(platforms)) => Ok(platforms.contains(platform)),
+                    (Some(_), Err(_)) => Ok(false),
+                }
+            })
+            .await?;
+        paginate(documents, page, sort)
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Project>, AppError> {
+        self.store.find_by_id(id).await
+    }
+
+    async fn insert(&self, project: &Project) -> Result<(), AppError> {
+        self.store.insert(project).await
+    }
+
+    async fn name_taken(
+        &self,
+        owner: &str,
+        name: &str,
+        exclude: Option<&str>,
+    ) -> Result<bool, AppError> {
+        let owner = ObjectId::parse_str(owner)?;
+        let exclude = exclude.map(ObjectId::parse_str).transpose()?;
+        let name = name.trim();
+        let documents = self
+            .store
+            .filtered(|document| {
+                Ok(document.get_object_id("owner").ok() == Some(owner)
+                    && document.get_str("name").ok() == Some(name)
+                    && !exclude.is_some_and(|oid| has_id(document, oid)))
+            })
+            .await?;
+        Ok(!documents.is_empty())
+    }
+
+    async fn update(&self, id: &str, changes: &UpdateProjectInput) -> Result<bool, AppError> {
+        let mut fields = Document::new();
+        fields.insert("updatedAt", Utc::now().timestamp_millis());
+        if let Some(name) = &changes.name {
+            fields.insert("name", name.trim());
+        }
+        if let Some(description) = &changes.description {
+            fields.insert("description", description);
+        }
+        if let Some(platforms) = &changes.platforms {
+            fields.insert("platforms", bson::to_bson(platforms)?);
+        }
+        let (found, _) = self.store.update(id, fields).await?;
+        Ok(found)
+    }
+
+    async fn set_image(&self, id: &str, image: &str) -> Result<bool, AppError> {
+        let mut fields = Document::new();
+        fields.insert("image", image);
+        let (_, modified) = self.store.update(id, fields).await?;
+        Ok(modified)
+    }
+
+    async fn set_archived(&self, id: &str, archived: bool) -> Result<(), AppError> {
+        let mut fields = Document::new();
+        fields.insert("archived", archived);
+        self.store.update(id, fields).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), AppError> {
+        let oid = ObjectId::parse_str(id)?;
+        self.store
+            .0
+            .write()
+            .await
+            .retain(|document| !has_id(document, oid));
+        Ok(())
+    }
+
+    async fn search(&self, terms: &str, limit: i64) -> Result<Vec<(Project, f64)>, AppError> {
+        let not_archived = |document: &Document| !is_archived(document);
+        self.store
+            .search(terms, &PROJECT_SEARCH_WEIGHTS, not_archived, limit)
+            .await
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryArtifactRepo {
+    store: Store,
+}
+
+#[async_trait]
+impl ArtifactRepo for MemoryArtifactRepo {
+    async fn find_page(
+        &self,
+        filters: &ArtifactFilters,
+        page: &PageQuery,
+        sort: SortSpec,
+    ) -> Result<Page<Artifact>, AppError> {
+        let project_id = filters
+            .project_id
+            .as_deref()
+            .map(ObjectId::parse_str)
+            .transpose()?;
+        let extension = filters.extension.as_ref().map(bson::to_bson).transpose()?;
+        let metadata = filters.metadata_entry()?;
+        let documents = self
+            .store
+            .filtered(|document| {
+                Ok(artifact_matches(
+                    document,
+                    filters,
+                    project_id,
+                    extension.as_ref(),
+                    metadata,
+                ))
+            })
+            .await?;
+        paginate(documents, page, sort)
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Artifact>, AppError> {
+        self.store.find_by_id(id).await
+    }
+
+    async fn find_by_branch(&self, project_id: &str) -> Result<Vec<BranchArtifacts>, AppError> {
+        let project_id = ObjectId::parse_str(project_id)?;
+        let documents = self
+            .store
+            .filtered(|document| Ok(document.get_object_id("projectId") == Ok(project_id)))
+            .await?;
+
+        let mut branches: BTreeMap<String, Vec<Artifact>> = BTreeMap::new();
+        for document in documents {
+            let branch = document.get_str("branch").unwrap_or_default().to_string();
+            branches
+                .entry(branch)
+                .or_default()
+                .push(bson::from_document(document)?);
+        }
+
+        Ok(branches
+            .into_iter()
+            .map(|(branch, artifacts)| BranchArtifacts { branch, artifacts })
+            .collect())
+    }
+
+    async fn insert(&self, artifact: &Artifact) -> Result<(), AppError> {
+        self.store.insert(artifact).await
+    }
+
+    async fn find_duplicates(&self, artifact: &Artifact) -> Result<Vec<Artifact>, AppError> {
+        let id = ObjectId::parse_str(artifact.get_id())?;
+        let project_id = ObjectId::parse_str(artifact.get_project_id())?;
+        let extension = bson::to_bson(artifact.get_extension())?;
+        let documents = self
+            .store
+            .filtered(|document| {
+                Ok(!has_id(document, id)
+                    && document.get_object_id("projectId") == Ok(project_id)
+                    && document.get_str("branch") == Ok(artifact.get_branch())
+                    && document.get_str("identifier") == Ok(artifact.get_identifier())
+                    && document.get("extension") == Some(&extension))
+            })
+            .await?;
+        documents
+            .into_iter()
+            .map(|document| Ok(bson::from_document(document)?))
+            .collect()
+    }
+
+    async fn update_details(&self, artifact: &Artifact) -> Result<(), AppError> {
+        let mut fields = Document::new();
+        fields.insert(
+            "releaseNotes",
+            bson::to_bson(&artifact.get_release_notes())?,
+        );
+        fields.insert("buildInfo", bson::to_bson(artifact.get_build_info())?);
+        fields.insert("tags", bson::to_bson(artifact.get_tags())?);
+        fields.insert("metadata", bson::to_bson(artifact.get_metadata())?);
+        self.store.update(artifact.get_id(), fields).await?;
+        Ok(())
+    }
+
+    async fn set_path(&self, id: &str, path: &str) -> Result<(), AppError> {
+        let mut fields = Document::new();
+        fields.insert("path", path);
+        self.store.update(id, fields).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), AppError> {
+        let oid = ObjectId::parse_str(id)?;
+        self.store
+            .0
+            .write()
+            .await
+            .retain(|document| !has_id(document, oid));
+        Ok(())
+    }
+
+    async fn delete_by_project(&self, project_id: &str) -> Result<(), AppError> {
+        let project_id = ObjectId::parse_str(project_id)?;
+        self.store
+            .0
+            .write()
+            .await
+            .retain(|document| document.get_object_id("projectId") != Ok(project_id));
+        Ok(())
+    }
+
+    async fn search(&self, terms: &str, limit: i64) -> Result<Vec<(Artifact, f64)>, AppError> {
+        self.store
+            .search(terms, &ARTIFACT_SEARCH_WEIGHTS, |_| true, limit)
+            .await
+    }
+
+    async fn storage_by_project(&self) -> Result<Vec<ProjectStorage>, AppError> {
+        let mut storage: HashMap<String, ProjectStorage> = HashMap::new();
+        for document in self.store.0.read().await.iter() {
+            let project_id = document
+                .get_object_id("projectId")
+                .map(|id| id.to_hex())
+                .unwrap_or_default();
+            let row = storage
+                .entry(project_id.clone())
+                .or_insert_with(|| ProjectStorage {
+                    project_id,
+                    artifacts: 0,
+                    bytes: 0,
+                });
+            row.artifacts += 1;
+            row.bytes += document.get("size").and_then(as_f64).unwrap_or_default() as i64;
+        }
+        Ok(storage.into_values().collect())
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryChannelRepo {
+    channels: Store,
+    promotions: Store,
+}
+
+#[async_trait]
+impl ChannelRepo for MemoryChannelRepo {
+    async fn find_by_project(&self, project_id: &str) -> Result<Vec<Channel>, AppError> {
+        let project_id = ObjectId::parse_str(project_id)?;
+        let mut documents = self
+            .channels
+            .filtered(|document| Ok(document.get_object_id("projectId") == Ok(project_id)))
+            .await?;
+        documents.sort_by(|a, b| a.get_str("name").ok().cmp(&b.get_str("name").ok()));
+        documents
+            .into_iter()
+            .map(|document| Ok(bson::from_document(document)?))
+            .collect()
+    }
+
+    async fn find(&self, project_id: &str, name: &str) -> Result<Option<Channel>, AppError> {
+        let project_id = ObjectId::parse_str(project_id)?;
+        let documents = self
+            .channels
+            .filtered(|document| Ok(is_channel(document, project_id, name)))
+            .await?;
+        match documents.into_iter().next() {
+            Some(document) => Ok(Some(bson::from_document(document)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn insert(&self, channel: &Channel) -> Result<bool, AppError> {
+        let project_id = ObjectId::parse_str(&channel.project_id)?;
+        let document = bson::to_document(channel)?;
+        let mut documents = self.channels.0.write().await;
+        if documents
+            .iter()
+            .any(|existing| is_channel(existing, project_id, &channel.name))
+        {
+            return Ok(false);
+        }
+        documents.push(document);
+        Ok(true)
+    }
+
+    async fn insert_promotion(&self, promotion: &Promotion) -> Result<(), AppError> {
+        self.promotions.insert(promotion).await
+    }
+
+    async fn reassign_promotions(&self, from: &str, to: &str) -> Result<(), AppError> {
+        let (from, to) = (ObjectId::parse_str(from)?, ObjectId::parse_str(to)?);
+        for document in self.promotions.0.write().await.iter_mut() {
+            if document.get_object_id("artifactId") == Ok(from) {
+                document.insert("artifactId", to);
+            }
+        }
+        Ok(())
+    }
+
+    async fn promotions(
+        &self,
+        project_id: &str,
+        channel: &str,
+    ) -> Result<Vec<Promotion>, AppError> {
+        let project_id = ObjectId::parse_str(project_id)?;
+        let mut documents = self
+            .promotions
+            .filtered(|document| {
+                Ok(document.get_object_id("projectId") == Ok(project_id)
+                    && document.get_str("channel") == Ok(channel))
+            })
+            .await?;
+        let key = |document: &Document| {
+            (
+                document
+                    .get("promotedAt")
+                    .and_then(as_f64)
+                    .unwrap_or_default(),
+                document.get_object_id("_id").ok(),
+            )
+        };
+        documents.sort_by(|a, b| {
+            let (a, b) = (key(a), key(b));
+            b.0.total_cmp(&a.0).then_with(|| b.1.cmp(&a.1))
+        });
+        documents
+            .into_iter()
+            .map(|document| Ok(bson::from_document(document)?))
+            .collect()
+    }
+
+    async fn latest_promotion(
+        &self,
+        project_id: &str,
+        channel: &str,
+    ) -> Result<Option<Promotion>, AppError> {
+        Ok(self
+            .promotions(project_id, channel)
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    async fn delete_by_project(&self, project_id: &str) -> Result<(), AppError> {
+        let project_id = ObjectId::parse_str(project_id)?;
+        for store in [&self.channels, &self.promotions] {
+            store
+                .0
+                .write()
+                .await
+                .retain(|document| document.get_object_id("projectId") != Ok(project_id));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryUserRepo {
+    store: Store,
+}
+
+#[async_trait]
+impl UserRepo for MemoryUserRepo {
+    async fn find_page(&self, page: &PageQuery, sort: SortSpec) -> Result<Page<User>, AppError> {
+        let documents = self.store.filtered(|_| Ok(true)).await?;
+        paginate(documents, page, sort)
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<User>, AppError> {
+        self.store.find_by_id(id).await
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>, AppError> {
+        let documents = self
+            .store
+            .filtered(|document| Ok(document.get_str("email") == Ok(email)))
+            .await?;
+        match documents.into_iter().next() {
+            Some(document) => Ok(Some(bson::from_document(document)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn insert(&self, user: &User) -> Result<(), AppError> {
+        let document = bson::to_document(user)?;
+        let mut documents = self.store.0.write().await;
+        if documents
+            .iter()
+            .any(|existing| existing.get("email") == document.get("email"))
+        {
+            return Err(AppError::UserAlreadyRegistered);
+        }
+        documents.push(document);
+        Ok(())
+    }
+
+    async fn set_favorite_project(
+        &self,
+        user_id: &str,
+        project_id: &str,
+        favorite: bool,
+    ) -> Result<(), AppError> {
+        let oid = ObjectId::parse_str(user_id)?;
+        let mut documents = self.store.0.write().await;
+        if let Some(document) = documents.iter_mut().find(|document| has_id(document, oid)) {
+            let project_id = Bson::String(project_id.to_string());
+            let mut favorites = document
+                .get_array("favoriteProjects")
+                .cloned()
+                .unwrap_or_default();
+            favorites.retain(|id| id != &project_id);
+            if favorite {
+                favorites.push(project_id);
+            }
+            document.insert("favoriteProjects", favorites);
+        }
+        Ok(())
+    }
+
+    async fn remove_favorite_project(&self, project_id: &str) -> Result<(), AppError> {
+        let project_id = Bson::String(project_id.to_string());
+        for document in self.store.0.write().await.iter_mut() {
+            if let Ok(favorites) = document.get_array_mut("favoriteProjects") {
+                favorites.retain(|id| id != &project_id);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn has_id(document: &Document, oid: ObjectId) -> bool {
+    document.get_object_id("_id").ok() == Some(oid)
+}
+
+fn is_channel(document: &Document, project_id: ObjectId, name: &str) -> bool {
+    document.get_object_id("projectId") == Ok(project_id) && document.get_str("name") == Ok(name)
+}
+
+fn artifact_matches(
+    document: &Document,
+    filters: &ArtifactFilters,
+    project_id: Option<ObjectId>,
+    extension: Option<&Bson>,
+    metadata: Option<(&str, &str)>,
+) -> bool {
+    if project_id.is_some_and(|project_id| document.get_object_id("projectId") != Ok(project_id)) {
+        return false;
+    }
+    if let Some(branch) = &filters.branch {
+        if document.get_str("branch") != Ok(branch) {
+            return false;
+        }
+    }
+    if extension.is_some() && document.get("extension") != extension {
+        return false;
+    }
+
+    let created_at = document
+        .get("createdAt")
+        .and_then(as_f64)
+        .unwrap_or_default();
+    if let Some(created_after) = filters.created_after {
+        if created_at < created_after as f64 {
+            return false;
+        }
+    }
+    if let Some(created_before) = filters.created_before {
+        if created_at > created_before as f64 {
+            return false;
+        }
+    }
+
+    if let Some(bundle_identifier) = &filters.bundle_identifier {
+        let matches = |metadata: &str, field: &str| {
+            document
+                .get_document(metadata)
+                .and_then(|metadata| metadata.get_str(field))
+                == Ok(bundle_identifier)
+        };
+        if !matches("iosMetadata", "bundleIdentifier") && !matches("androidMetadata", "packageName")
+        {
+            return false;
+        }
+    }
+
+    if let Some(tag) = &filters.tag {
+        let tags = document
+            .get_array("tags")
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        if !tags.iter().any(|item| item.as_str() == Some(tag)) {
+            return false;
+        }
+    }
+    if let Some((key, value)) = metadata {
+        let entry = document
+            .get_document("metadata")
+            .and_then(|metadata| metadata.get_str(key));
+        if entry != Ok(value) {
+            return false;
+        }
+    }
+
+    let extension = document.get_str("extension").unwrap_or_default();
+    match filters.platform {
+        Some(Platforms::Ios) => extension == "ipa",
+        Some(Platforms::Android) => extension == "apk" || extension == "aab",
+        None => true,
+    }
+}
+
+fn is_archived(document: &Document) -> bool {
+    document.get_bool("archived").unwrap_or(false)
+}
+
+/// Sorts by the sort field then `_id` and skips past the cursor, like `database::find_page`.
+fn paginate<T: DeserializeOwned>(
+    mut documents: Vec<Document>,
+    page: &PageQuery,
+    sort: SortSpec,
+) -> Result<Page<T>, AppError> {
+    let key = |document: &Document| {
+        (
+            document.get(&sort.field).cloned().unwrap_or(Bson::Null),
+            document.get_object_id("_id").ok(),
+        )
+    };
+    let order = |a: &(Bson, Option<ObjectId>), b: &(Bson, Option<ObjectId>)| {
+        let ordering = compare(&a.0, &b.0).then_with(|| a.1.cmp(&b.1));
+        if sort.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    };
+
+    documents.sort_by(|a, b| order(&key(a), &key(b)));
+    if let Some(cursor) = page.cursor(&sort)? {
+        let cursor = (cursor.value, Some(cursor.id));
+        documents.retain(|document| order(&key(document), &cursor) == Ordering::Greater);
+    }
+
+    let limit = page.limit();
+    documents.truncate(limit as usize + 1);
+    Page::from_documents(documents, limit, &sort)
+}
+
+/// Orders values the way Mongo does for the types we store: nulls, then numbers, then strings.
+fn compare(a: &Bson, b: &Bson) -> Ordering {
+    fn rank(value: &Bson) -> u8 {
+        match value {
+            Bson::Null => 0,
+            Bson::Int32(_) | Bson::Int64(_) | Bson::Double(_) => 1,
+            Bson::String(_) => 2,
+            _ => 3,
+        }
+    }
+
+    match (a, b) {
+        (Bson::String(a), Bson::String(b)) => a.cmp(b),
+        _ => match (as_f64(a), as_f64(b)) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            _ => rank(a).cmp(&rank(b)),
+        },
+    }
+}
+
+fn as_f64(value: &Bson) -> Option<f64> {
+    match value {
+        Bson::Int32(value) => Some(f64::from(*value)),
+        Bson::Int64(value) => Some(*value as f64),
+        Bson::Double(value) => Some(*value),
+        _ => None,
+    }
+}
+
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Rough stand-in for Mongo's text score: the weight of every field containing a search term.
+fn text_score(document: &Document, weights: &[(&str, f64)], terms: &[String]) -> f64 {
+    let mut score = 0.0;
+    for (path, weight) in weights {
+        let mut value = Some(document);
+        let mut parts = path.split('.').peekable();
+        let mut text = None;
+        while let (Some(part), Some(current)) = (parts.next(), value) {
+            if parts.peek().is_some() {
+                value = current.get_document(part).ok();
+            } else {
+                text = current.get_str(part).ok();
+            }
+        }
+        if let Some(text) = text {
+            let words: Vec<String> = tokenize(text).collect();
+            score += weight * terms.iter().filter(|term| words.contains(term)).count() as f64;
+        }
+    }
+    score
+}
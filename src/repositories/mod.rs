@@ -0,0 +1,185 @@
+use axum::async_trait;
+use mongodb::Client;
+use std::{path::Path, sync::Arc};
+
+use crate::{
+    error::AppError,
+    models::{
+        artifact::{Artifact, BranchArtifacts},
+        channel::{Channel, Promotion},
+        pagination::{ArtifactFilters, Page, PageQuery, ProjectFilters, SortSpec},
+        project::{Project, UpdateProjectInput},
+        user::User,
+    },
+};
+
+pub mod memory;
+pub mod mongo;
+pub mod sqlite;
+
+/// The storage backend itself, as opposed to the collections it holds.
+#[async_trait]
+pub trait Backend: Send + Sync {
+    /// Name of the backend, used to label its readiness check.
+    fn name(&self) -> &'static str;
+
+    /// Checks that the backend is reachable.
+    async fn ping(&self) -> Result<(), AppError>;
+}
+
+#[async_trait]
+pub trait ProjectRepo: Send + Sync {
+    async fn find_page(
+        &self,
+        filters: &ProjectFilters,
+        page: &PageQuery,
+        sort: SortSpec,
+    ) -> Result<Page<Project>, AppError>;
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Project>, AppError>;
+
+    async fn insert(&self, project: &Project) -> Result<(), AppError>;
+
+    /// Whether `owner` already has a project named `name`, other than `exclude`.
+    async fn name_taken(
+        &self,
+        owner: &str,
+        name: &str,
+        exclude: Option<&str>,
+    ) -> Result<bool, AppError>;
+
+    /// Applies the fields present in `changes` and bumps `updatedAt`. Returns `false` if the
+    /// project doesn't exist.
+    async fn update(&self, id: &str, changes: &UpdateProjectInput) -> Result<bool, AppError>;
+
+    /// Replaces the project image. Returns `false` if the project doesn't exist or already had
+    /// this image.
+    async fn set_image(&self, id: &str, image: &str) -> Result<bool, AppError>;
+
+    async fn set_archived(&self, id: &str, archived: bool) -> Result<(), AppError>;
+
+    async fn delete(&self, id: &str) -> Result<(), AppError>;
+
+    /// Full-text search over non-archived projects, best matches first, with their score.
+    async fn search(&self, terms: &str, limit: i64) -> Result<Vec<(Project, f64)>, AppError>;
+}
+
+#[async_trait]
+pub trait ArtifactRepo: Send + Sync {
+    async fn find_page(
+        &self,
+        filters: &ArtifactFilters,
+        page: &PageQuery,
+        sort: SortSpec,
+    ) -> Result<Page<Artifact>, AppError>;
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Artifact>, AppError>;
+
+    /// Artifacts of a project, grouped by the branch they were built from.
+    async fn find_by_branch(&self, project_id: &str) -> Result<Vec<BranchArtifacts>, AppError>;
+
+    async fn insert(&self, artifact: &Artifact) -> Result<(), AppError>;
+
+    /// Other artifacts of the same project, branch, identifier and file extension.
+    async fn find_duplicates(&self, artifact: &Artifact) -> Result<Vec<Artifact>, AppError>;
+
+    /// Saves the release notes and build information of an existing artifact.
+    async fn update_details(&self, artifact: &Artifact) -> Result<(), AppError>;
+
+    /// Points an artifact at a different file.
+    async fn set_path(&self, id: &str, path: &str) -> Result<(), AppError>;
+
+    async fn delete(&self, id: &str) -> Result<(), AppError>;
+
+    async fn delete_by_project(&self, project_id: &str) -> Result<(), AppError>;
+
+    /// Full-text search over artifacts, best matches first, with their score.
+    async fn search(&self, terms: &str, limit: i64) -> Result<Vec<(Artifact, f64)>, AppError>;
+
+    /// Number of artifacts and bytes stored, by project.
+    async fn storage_by_project(&self) -> Result<Vec<ProjectStorage>, AppError>;
+}
+
+#[async_trait]
+pub trait ChannelRepo: Send + Sync {
+    /// Channels of a project, by name.
+    async fn find_by_project(&self, project_id: &str) -> Result<Vec<Channel>, AppError>;
+
+    async fn find(&self, project_id: &str, name: &str) -> Result<Option<Channel>, AppError>;
+
+    /// Returns `false` if the project already has a channel with this name.
+    async fn insert(&self, channel: &Channel) -> Result<bool, AppError>;
+
+    async fn insert_promotion(&self, promotion: &Promotion) -> Result<(), AppError>;
+
+    /// Moves every promotion of artifact `from` over to artifact `to`, which replaced it.
+    async fn reassign_promotions(&self, from: &str, to: &str) -> Result<(), AppError>;
+
+    /// Promotions into a channel, newest first.
+    async fn promotions(&self, project_id: &str, channel: &str)
+        -> Result<Vec<Promotion>, AppError>;
+
+    /// The newest promotion into a channel, i.e. the build it currently offers.
+    async fn latest_promotion(
+        &self,
+        project_id: &str,
+        channel: &str,
+    ) -> Result<Option<Promotion>, AppError>;
+
+    /// Removes the channels of a project along with their promotions.
+    async fn delete_by_project(&self, project_id: &str) -> Result<(), AppError>;
+}
+
+#[async_trait]
+pub trait UserRepo: Send + Sync {
+    async fn find_page(&self, page: &PageQuery, sort: SortSpec) -> Result<Page<User>, AppError>;
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<User>, AppError>;
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>, AppError>;
+
+    /// Fails with [`AppError::UserAlreadyRegistered`] if the email is taken.
+    async fn insert(&self, user: &User) -> Result<(), AppError>;
+
+    async fn set_favorite_project(
+        &self,
+        user_id: &str,
+        project_id: &str,
+        favorite: bool,
+    ) -> Result<(), AppError>;
+
+    /// Removes a project from every user's favorite projects.
+    async fn remove_favorite_project(&self, project_id: &str) -> Result<(), AppError>;
+}
+
+pub struct ProjectStorage {
+    pub project_id: String,
+    pub artifacts: i64,
+    pub bytes: i64,
+}
+
+/// Every repository the handlers need, backed by the same storage.
+#[derive(Clone)]
+pub struct Repositories {
+    pub backend: Arc<dyn Backend>,
+    pub projects: Arc<dyn ProjectRepo>,
+    pub artifacts: Arc<dyn ArtifactRepo>,
+    pub channels: Arc<dyn ChannelRepo>,
+    pub users: Arc<dyn UserRepo>,
+}
+
+impl Repositories {
+    pub fn mongo(client: Client) -> Repositories {
+        mongo::repositories(client)
+    }
+
+    /// Repositories stored in the SQLite database at `path`, created and migrated if needed.
+    pub fn sqlite(path: &Path) -> Result<Repositories, AppError> {
+        Ok(sqlite::repositories(sqlite::Sqlite::open(path)?))
+    }
+
+    /// Repositories that live in memory and are lost on exit, for tests.
+    pub fn in_memory() -> Repositories {
+        memory::repositories()
+    }
+}
@@ -0,0 +1,528 @@
+use axum::async_trait;
+use bson::{doc, oid::ObjectId, Bson, Document};
+use chrono::Utc;
+use mongodb::{
+    error::{ErrorKind, WriteError, WriteFailure},
+    options::{
+        AggregateOptions, DeleteOptions, FindOneOptions, FindOptions, InsertOneOptions,
+        UpdateOptions,
+    },
+    Client, Collection,
+};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+
+use crate::{
+    database::find_page,
+    error::AppError,
+    models::{
+        artifact::{Artifact, BranchArtifacts},
+        channel::{Channel, Promotion},
+        pagination::{ArtifactFilters, Page, PageQuery, ProjectFilters, SortSpec},
+        project::{Project, UpdateProjectInput},
+        user::User,
+    },
+};
+
+use super::{
+    ArtifactRepo, Backend, ChannelRepo, ProjectRepo, ProjectStorage, Repositories, UserRepo,
+};
+
+const DB_NAME: &str = "appdist";
+
+pub fn repositories(client: Client) -> Repositories {
+    let db = client.database(DB_NAME);
+    Repositories {
+        projects: Arc::new(MongoProjectRepo {
+            coll: db.collection("projects"),
+        }),
+        artifacts: Arc::new(MongoArtifactRepo {
+            coll: db.collection("artifacts"),
+        }),
+        channels: Arc::new(MongoChannelRepo {
+            channels: db.collection("channels"),
+            promotions: db.collection("promotions"),
+        }),
+        users: Arc::new(MongoUserRepo {
+            coll: db.collection("users"),
+        }),
+        backend: Arc::new(MongoBackend { client }),
+    }
+}
+
+pub struct MongoBackend {
+    client: Client,
+}
+
+#[async_trait]
+impl Backend for MongoBackend {
+    fn name(&self) -> &'static str {
+        "mongo"
+    }
+
+    async fn ping(&self) -> Result<(), AppError> {
+        let admin = self.client.database("admin");
+        admin.run_command(doc! { "ping": 1 }, None).await?;
+        Ok(())
+    }
+}
+
+pub struct MongoProjectRepo {
+    coll: Collection<Project>,
+}
+
+#[async_trait]
+impl ProjectRepo for MongoProjectRepo {
+    async fn find_page(
+        &self,
+        filters: &ProjectFilters,
+        page: &PageQuery,
+        sort: SortSpec,
+    ) -> Result<Page<Project>, AppError> {
+        find_page(&self.coll, filters.to_document()?, page, sort).await
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Project>, AppError> {
+        let filter = doc! { "_id": ObjectId::parse_str(id)? };
+        Ok(self
+            .coll
+            .find_one(filter, FindOneOptions::default())
+            .await?)
+    }
+
+    async fn insert(&self, project: &Project) -> Result<(), AppError> {
+        self.coll
+            .insert_one(project, InsertOneOptions::default())
+            .await?;
+        Ok(())
+    }
+
+    async fn name_taken(
+        &self,
+        owner: &str,
+        name: &str,
+        exclude: Option<&str>,
+    ) -> Result<bool, AppError> {
+        let mut filter = doc! {
+            "owner": ObjectId::parse_str(owner)?,
+            "name": name.trim(),
+        };
+        if let Some(id) = exclude {
+            filter.insert("_id", doc! { "$ne": ObjectId::parse_str(id)? });
+        }
+        Ok(self.coll.count_documents(filter, None).await? > 0)
+    }
+
+    async fn update(&self, id: &str, changes: &UpdateProjectInput) -> Result<bool, AppError> {
+        let mut set = doc! { "updatedAt": Utc::now().timestamp_millis() };
+        if let Some(name) = &changes.name {
+            set.insert("name", name.trim());
+        }
+        if let Some(description) = &changes.description {
+            set.insert("description", description);
+        }
+        if let Some(platforms) = &changes.platforms {
+            set.insert("platforms", bson::to_bson(platforms)?);
+        }
+
+        let filter = doc! { "_id": ObjectId::parse_str(id)? };
+        let result = self
+            .coll
+            .update_one(filter, doc! { "$set": set }, UpdateOptions::default())
+            .await?;
+        Ok(result.matched_count == 1)
+    }
+
+    async fn set_image(&self, id: &str, image: &str) -> Result<bool, AppError> {
+        let filter = doc! { "_id": ObjectId::parse_str(id)? };
+        let update = doc! { "$set": { "image": image } };
+        let result = self
+            .coll
+            .update_one(filter, update, UpdateOptions::default())
+            .await?;
+        Ok(result.modified_count == 1)
+    }
+
+    async fn set_archived(&self, id: &str, archived: bool) -> Result<(), AppError> {
+        let filter = doc! { "_id": ObjectId::parse_str(id)? };
+        let update = doc! { "$set": { "archived": archived } };
+        self.coll
+            .update_one(filter, update, UpdateOptions::default())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), AppError> {
+        let filter = doc! { "_id": ObjectId::parse_str(id)? };
+        self.coll
+            .delete_one(filter, DeleteOptions::default())
+            .await?;
+        Ok(())
+    }
+
+    async fn search(&self, terms: &str, limit: i64) -> Result<Vec<(Project, f64)>, AppError> {
+        let archived = doc! { "archived": { "$ne": true } };
+        text_search(&self.coll, terms, archived, limit).await
+    }
+}
+
+pub struct MongoArtifactRepo {
+    coll: Collection<Artifact>,
+}
+
+#[async_trait]
+impl ArtifactRepo for MongoArtifactRepo {
+    async fn find_page(
+        &self,
+        filters: &ArtifactFilters,
+        page: &PageQuery,
+        sort: SortSpec,
+    ) -> Result<Page<Artifact>, AppError> {
+        find_page(&self.coll, filters.to_document()?, page, sort).await
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Artifact>, AppError> {
+        let filter = doc! { "_id": ObjectId::parse_str(id)? };
+        Ok(self
+            .coll
+            .find_one(filter, FindOneOptions::default())
+            .await?)
+    }
+
+    async fn find_by_branch(&self, project_id: &str) -> Result<Vec<BranchArtifacts>, AppError> {
+        let pipeline = vec![
+            doc! {
+                "$match": doc! {
+                  "projectId": ObjectId::parse_str(project_id)?,
+                },
+            },
+            doc! {
+              "$group": doc! {
+                "_id": "$branch",
+                "artifacts": {
+                  "$push": "$$ROOT",
+                },
+              },
+            },
+        ];
+        let options = AggregateOptions::default();
+        let mut cursor = self
+            .coll
+            .aggregate(pipeline, options)
+            .await?
+            .with_type::<BranchArtifacts>();
+
+        let mut rows = Vec::new();
+        while cursor.advance().await? {
+            rows.push(cursor.deserialize_current()?);
+        }
+
+        Ok(rows)
+    }
+
+    async fn insert(&self, artifact: &Artifact) -> Result<(), AppError> {
+        self.coll
+            .insert_one(artifact, InsertOneOptions::default())
+            .await?;
+        Ok(())
+    }
+
+    async fn find_duplicates(&self, artifact: &Artifact) -> Result<Vec<Artifact>, AppError> {
+        let filter = doc! {
+            "_id": { "$ne": ObjectId::parse_str(artifact.get_id())? },
+            "projectId": ObjectId::parse_str(artifact.get_project_id())?,
+            "branch": artifact.get_branch(),
+            "identifier": artifact.get_identifier(),
+            "extension": bson::to_bson(artifact.get_extension())?,
+        };
+        let mut cursor = self.coll.find(filter, FindOptions::default()).await?;
+
+        let mut rows = Vec::new();
+        while cursor.advance().await? {
+            rows.push(cursor.deserialize_current()?);
+        }
+        Ok(rows)
+    }
+
+    async fn update_details(&self, artifact: &Artifact) -> Result<(), AppError> {
+        let filter = doc! { "_id": ObjectId::parse_str(artifact.get_id())? };
+        let update = doc! {
+            "$set": {
+                "releaseNotes": bson::to_bson(&artifact.get_release_notes())?,
+                "buildInfo": bson::to_bson(artifact.get_build_info())?,
+                "tags": bson::to_bson(artifact.get_tags())?,
+                "metadata": bson::to_bson(artifact.get_metadata())?,
+            }
+        };
+        self.coll
+            .update_one(filter, update, UpdateOptions::default())
+            .await?;
+        Ok(())
+    }
+
+    async fn set_path(&self, id: &str, path: &str) -> Result<(), AppError> {
+        let filter = doc! { "_id": ObjectId::parse_str(id)? };
+        let update = doc! { "$set": { "path": path } };
+        self.coll
+            .update_one(filter, update, UpdateOptions::default())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), AppError> {
+        let filter = doc! { "_id": ObjectId::parse_str(id)? };
+        self.coll
+            .delete_one(filter, DeleteOptions::default())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_by_project(&self, project_id: &str) -> Result<(), AppError> {
+        let filter = doc! { "projectId": ObjectId::parse_str(project_id)? };
+        self.coll
+            .delete_many(filter, DeleteOptions::default())
+            .await?;
+        Ok(())
+    }
+
+    async fn search(&self, terms: &str, limit: i64) -> Result<Vec<(Artifact, f64)>, AppError> {
+        text_search(&self.coll, terms, doc! {}, limit).await
+    }
+
+    async fn storage_by_project(&self) -> Result<Vec<ProjectStorage>, AppError> {
+        let pipeline = vec![doc! {
+            "$group": doc! {
+                "_id": "$projectId",
+                "count": { "$sum": 1 },
+                "size": { "$sum": "$size" },
+            },
+        }];
+        let options = AggregateOptions::default();
+        let mut cursor = self
+            .coll
+            .clone_with_type::<Document>()
+            .aggregate(pipeline, options)
+            .await?;
+
+        let mut rows = Vec::new();
+        while cursor.advance().await? {
+            let row = cursor.deserialize_current()?;
+            rows.push(ProjectStorage {
+                project_id: row
+                    .get_object_id("_id")
+                    .map(|id| id.to_hex())
+                    .unwrap_or_default(),
+                artifacts: row.get_i32("count").map(i64::from).unwrap_or_default(),
+                bytes: match row.get("size") {
+                    Some(Bson::Int32(size)) => i64::from(*size),
+                    Some(Bson::Int64(size)) => *size,
+                    _ => 0,
+                },
+            });
+        }
+
+        Ok(rows)
+    }
+}
+
+pub struct MongoChannelRepo {
+    channels: Collection<Channel>,
+    promotions: Collection<Promotion>,
+}
+
+#[async_trait]
+impl ChannelRepo for MongoChannelRepo {
+    async fn find_by_project(&self, project_id: &str) -> Result<Vec<Channel>, AppError> {
+        let filter = doc! { "projectId": ObjectId::parse_str(project_id)? };
+        let options = FindOptions::builder().sort(doc! { "name": 1 }).build();
+        let mut cursor = self.channels.find(filter, options).await?;
+
+        let mut rows = Vec::new();
+        while cursor.advance().await? {
+            rows.push(cursor.deserialize_current()?);
+        }
+        Ok(rows)
+    }
+
+    async fn find(&self, project_id: &str, name: &str) -> Result<Option<Channel>, AppError> {
+        let filter = doc! { "projectId": ObjectId::parse_str(project_id)?, "name": name };
+        Ok(self
+            .channels
+            .find_one(filter, FindOneOptions::default())
+            .await?)
+    }
+
+    async fn insert(&self, channel: &Channel) -> Result<bool, AppError> {
+        match self
+            .channels
+            .insert_one(channel, InsertOneOptions::default())
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(e) => match *e.kind.to_owned() {
+                ErrorKind::Write(WriteFailure::WriteError(WriteError { code: 11000, .. })) => {
+                    Ok(false)
+                }
+                _ => Err(AppError::MongoError(e)),
+            },
+        }
+    }
+
+    async fn insert_promotion(&self, promotion: &Promotion) -> Result<(), AppError> {
+        self.promotions
+            .insert_one(promotion, InsertOneOptions::default())
+            .await?;
+        Ok(())
+    }
+
+    async fn reassign_promotions(&self, from: &str, to: &str) -> Result<(), AppError> {
+        let filter = doc! { "artifactId": ObjectId::parse_str(from)? };
+        let update = doc! { "$set": { "artifactId": ObjectId::parse_str(to)? } };
+        self.promotions
+            .update_many(filter, update, UpdateOptions::default())
+            .await?;
+        Ok(())
+    }
+
+    async fn promotions(
+        &self,
+        project_id: &str,
+        channel: &str,
+    ) -> Result<Vec<Promotion>, AppError> {
+        let filter = doc! { "projectId": ObjectId::parse_str(project_id)?, "channel": channel };
+        let options = FindOptions::builder()
+            .sort(doc! { "promotedAt": -1, "_id": -1 })
+            .build();
+        let mut cursor = self.promotions.find(filter, options).await?;
+
+        let mut rows = Vec::new();
+        while cursor.advance().await? {
+            rows.push(cursor.deserialize_current()?);
+        }
+        Ok(rows)
+    }
+
+    async fn latest_promotion(
+        &self,
+        project_id: &str,
+        channel: &str,
+    ) -> Result<Option<Promotion>, AppError> {
+        let filter = doc! { "projectId": ObjectId::parse_str(project_id)?, "channel": channel };
+        let options = FindOneOptions::builder()
+            .sort(doc! { "promotedAt": -1, "_id": -1 })
+            .build();
+        Ok(self.promotions.find_one(filter, options).await?)
+    }
+
+    async fn delete_by_project(&self, project_id: &str) -> Result<(), AppError> {
+        let filter = doc! { "projectId": ObjectId::parse_str(project_id)? };
+        self.promotions
+            .delete_many(filter.clone(), DeleteOptions::default())
+            .await?;
+        self.channels
+            .delete_many(filter, DeleteOptions::default())
+            .await?;
+        Ok(())
+    }
+}
+
+pub struct MongoUserRepo {
+    coll: Collection<User>,
+}
+
+#[async_trait]
+impl UserRepo for MongoUserRepo {
+    async fn find_page(&self, page: &PageQuery, sort: SortSpec) -> Result<Page<User>, AppError> {
+        find_page(&self.coll, doc! {}, page, sort).await
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<User>, AppError> {
+        let filter = doc! { "_id": ObjectId::parse_str(id)? };
+        Ok(self
+            .coll
+            .find_one(filter, FindOneOptions::default())
+            .await?)
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>, AppError> {
+        let filter = doc! { "email": email };
+        Ok(self
+            .coll
+            .find_one(filter, FindOneOptions::default())
+            .await?)
+    }
+
+    async fn insert(&self, user: &User) -> Result<(), AppError> {
+        match self
+            .coll
+            .insert_one(user, InsertOneOptions::default())
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => match *e.kind.to_owned() {
+                ErrorKind::Write(WriteFailure::WriteError(WriteError { code: 11000, .. })) => {
+                    Err(AppError::UserAlreadyRegistered)
+                }
+                _ => Err(AppError::MongoError(e)),
+            },
+        }
+    }
+
+    async fn set_favorite_project(
+        &self,
+        user_id: &str,
+        project_id: &str,
+        favorite: bool,
+    ) -> Result<(), AppError> {
+        let filter = doc! { "_id": ObjectId::parse_str(user_id)? };
+        let update = if favorite {
+            doc! { "$addToSet": { "favoriteProjects": project_id } }
+        } else {
+            doc! { "$pull": { "favoriteProjects": project_id } }
+        };
+        self.coll
+            .update_one(filter, update, UpdateOptions::default())
+            .await?;
+        Ok(())
+    }
+
+    async fn remove_favorite_project(&self, project_id: &str) -> Result<(), AppError> {
+        let filter = doc! { "favoriteProjects": project_id };
+        let update = doc! { "$pull": { "favoriteProjects": project_id } };
+        self.coll
+            .update_many(filter, update, UpdateOptions::default())
+            .await?;
+        Ok(())
+    }
+}
+
+async fn text_search<T>(
+    coll: &Collection<T>,
+    terms: &str,
+    mut filter: Document,
+    limit: i64,
+) -> Result<Vec<(T, f64)>, AppError>
+where
+    T: DeserializeOwned,
+{
+    filter.insert("$text", doc! { "$search": terms });
+    let options = FindOptions::builder()
+        .projection(doc! { "score": { "$meta": "textScore" } })
+        .sort(doc! { "score": { "$meta": "textScore" } })
+        .limit(limit)
+        .build();
+    let mut cursor = coll
+        .clone_with_type::<Document>()
+        .find(filter, options)
+        .await?;
+
+    let mut rows = Vec::new();
+    while cursor.advance().await? {
+        let mut document: Document = cursor.deserialize_current()?;
+        let score = document.get_f64("score").unwrap_or_default();
+        document.remove("score");
+        rows.push((bson::from_document(document)?, score));
+    }
+
+    Ok(rows)
+}
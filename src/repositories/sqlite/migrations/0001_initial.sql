@@ -0,0 +1,98 @@
+CREATE TABLE users (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL UNIQUE,
+    role TEXT NOT NULL,
+    password TEXT NOT NULL,
+    salt TEXT NOT NULL
+);
+
+CREATE TABLE projects (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    owner TEXT NOT NULL,
+    platforms TEXT NOT NULL,
+    project_key TEXT NOT NULL,
+    image TEXT,
+    archived INTEGER NOT NULL DEFAULT 0,
+    updated_at INTEGER
+);
+
+CREATE INDEX projects_owner_name ON projects (owner, name);
+
+CREATE TABLE favorite_projects (
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    project_id TEXT NOT NULL,
+    PRIMARY KEY (user_id, project_id)
+);
+
+CREATE INDEX favorite_projects_project ON favorite_projects (project_id);
+
+CREATE TABLE artifacts (
+    id TEXT PRIMARY KEY NOT NULL,
+    project_id TEXT NOT NULL REFERENCES projects (id),
+    original_filename TEXT NOT NULL,
+    branch TEXT NOT NULL,
+    extension TEXT NOT NULL,
+    path TEXT NOT NULL,
+    mime_type TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    identifier TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    qrcode TEXT,
+    bundle_identifier TEXT,
+    bundle_version TEXT
+);
+
+CREATE INDEX artifacts_project_branch ON artifacts (project_id, branch);
+CREATE INDEX artifacts_created_at ON artifacts (created_at, id);
+
+-- full-text search, kept in sync with the tables by triggers
+CREATE VIRTUAL TABLE projects_search USING fts5 (
+    name,
+    description,
+    content = 'projects'
+);
+
+CREATE TRIGGER projects_search_insert AFTER INSERT ON projects BEGIN
+    INSERT INTO projects_search (rowid, name, description)
+    VALUES (new.rowid, new.name, new.description);
+END;
+
+CREATE TRIGGER projects_search_delete AFTER DELETE ON projects BEGIN
+    INSERT INTO projects_search (projects_search, rowid, name, description)
+    VALUES ('delete', old.rowid, old.name, old.description);
+END;
+
+CREATE TRIGGER projects_search_update AFTER UPDATE ON projects BEGIN
+    INSERT INTO projects_search (projects_search, rowid, name, description)
+    VALUES ('delete', old.rowid, old.name, old.description);
+    INSERT INTO projects_search (rowid, name, description)
+    VALUES (new.rowid, new.name, new.description);
+END;
+
+CREATE VIRTUAL TABLE artifacts_search USING fts5 (
+    branch,
+    identifier,
+    original_filename,
+    bundle_identifier,
+    content = 'artifacts'
+);
+
+CREATE TRIGGER artifacts_search_insert AFTER INSERT ON artifacts BEGIN
+    INSERT INTO artifacts_search (rowid, branch, identifier, original_filename, bundle_identifier)
+    VALUES (new.rowid, new.branch, new.identifier, new.original_filename, new.bundle_identifier);
+END;
+
+CREATE TRIGGER artifacts_search_delete AFTER DELETE ON artifacts BEGIN
+    INSERT INTO artifacts_search (artifacts_search, rowid, branch, identifier, original_filename, bundle_identifier)
+    VALUES ('delete', old.rowid, old.branch, old.identifier, old.original_filename, old.bundle_identifier);
+END;
+
+CREATE TRIGGER artifacts_search_update AFTER UPDATE ON artifacts BEGIN
+    INSERT INTO artifacts_search (artifacts_search, rowid, branch, identifier, original_filename, bundle_identifier)
+    VALUES ('delete', old.rowid, old.branch, old.identifier, old.original_filename, old.bundle_identifier);
+    INSERT INTO artifacts_search (rowid, branch, identifier, original_filename, bundle_identifier)
+    VALUES (new.rowid, new.branch, new.identifier, new.original_filename, new.bundle_identifier);
+END;
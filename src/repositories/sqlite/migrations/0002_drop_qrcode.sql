@@ -0,0 +1,2 @@
+-- QR codes are rendered on request instead of being stored with each artifact.
+ALTER TABLE artifacts DROP COLUMN qrcode;
@@ -0,0 +1,20 @@
+CREATE TABLE channels (
+    id TEXT PRIMARY KEY NOT NULL,
+    project_id TEXT NOT NULL REFERENCES projects (id),
+    name TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    UNIQUE (project_id, name)
+);
+
+CREATE TABLE promotions (
+    id TEXT PRIMARY KEY NOT NULL,
+    project_id TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    artifact_id TEXT NOT NULL REFERENCES artifacts (id),
+    promoted_by TEXT NOT NULL,
+    promoted_at INTEGER NOT NULL,
+    note TEXT,
+    FOREIGN KEY (project_id, channel) REFERENCES channels (project_id, name)
+);
+
+CREATE INDEX promotions_channel ON promotions (project_id, channel, promoted_at);
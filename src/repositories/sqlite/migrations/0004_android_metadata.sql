@@ -0,0 +1,6 @@
+ALTER TABLE artifacts ADD COLUMN package_name TEXT;
+ALTER TABLE artifacts ADD COLUMN version_code TEXT;
+
+-- update checks look builds up by bundle identifier or package name
+CREATE INDEX artifacts_bundle_identifier ON artifacts (bundle_identifier, created_at);
+CREATE INDEX artifacts_package_name ON artifacts (package_name, created_at);
@@ -0,0 +1,5 @@
+ALTER TABLE artifacts ADD COLUMN release_notes TEXT;
+ALTER TABLE artifacts ADD COLUMN commit_sha TEXT;
+ALTER TABLE artifacts ADD COLUMN commit_message TEXT;
+ALTER TABLE artifacts ADD COLUMN build_url TEXT;
+ALTER TABLE artifacts ADD COLUMN author TEXT;
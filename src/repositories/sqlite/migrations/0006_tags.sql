@@ -0,0 +1,35 @@
+ALTER TABLE artifacts ADD COLUMN tags TEXT;
+ALTER TABLE artifacts ADD COLUMN metadata TEXT;
+
+-- tags and metadata entries are copied into their own tables by triggers, so filtering by
+-- them can use an index
+CREATE TABLE artifact_tags (
+    artifact_id TEXT NOT NULL REFERENCES artifacts (id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (tag, artifact_id)
+);
+
+CREATE TABLE artifact_metadata (
+    artifact_id TEXT NOT NULL REFERENCES artifacts (id) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (artifact_id, key)
+);
+
+CREATE INDEX artifact_metadata_entry ON artifact_metadata (key, value);
+
+CREATE TRIGGER artifact_tags_insert AFTER INSERT ON artifacts BEGIN
+    INSERT OR IGNORE INTO artifact_tags (artifact_id, tag)
+    SELECT new.id, value FROM json_each(new.tags);
+    INSERT OR IGNORE INTO artifact_metadata (artifact_id, key, value)
+    SELECT new.id, key, value FROM json_each(new.metadata);
+END;
+
+CREATE TRIGGER artifact_tags_update AFTER UPDATE OF tags, metadata ON artifacts BEGIN
+    DELETE FROM artifact_tags WHERE artifact_id = old.id;
+    INSERT OR IGNORE INTO artifact_tags (artifact_id, tag)
+    SELECT new.id, value FROM json_each(new.tags);
+    DELETE FROM artifact_metadata WHERE artifact_id = old.id;
+    INSERT OR IGNORE INTO artifact_metadata (artifact_id, key, value)
+    SELECT new.id, key, value FROM json_each(new.metadata);
+END;
@@ -0,0 +1,34 @@
+-- Android package names are searched like iOS bundle identifiers; fts5 tables can't gain a
+-- column, so the index is rebuilt with it
+DROP TRIGGER artifacts_search_insert;
+DROP TRIGGER artifacts_search_delete;
+DROP TRIGGER artifacts_search_update;
+DROP TABLE artifacts_search;
+
+CREATE VIRTUAL TABLE artifacts_search USING fts5 (
+    branch,
+    identifier,
+    original_filename,
+    bundle_identifier,
+    package_name,
+    content = 'artifacts'
+);
+
+CREATE TRIGGER artifacts_search_insert AFTER INSERT ON artifacts BEGIN
+    INSERT INTO artifacts_search (rowid, branch, identifier, original_filename, bundle_identifier, package_name)
+    VALUES (new.rowid, new.branch, new.identifier, new.original_filename, new.bundle_identifier, new.package_name);
+END;
+
+CREATE TRIGGER artifacts_search_delete AFTER DELETE ON artifacts BEGIN
+    INSERT INTO artifacts_search (artifacts_search, rowid, branch, identifier, original_filename, bundle_identifier, package_name)
+    VALUES ('delete', old.rowid, old.branch, old.identifier, old.original_filename, old.bundle_identifier, old.package_name);
+END;
+
+CREATE TRIGGER artifacts_search_update AFTER UPDATE ON artifacts BEGIN
+    INSERT INTO artifacts_search (artifacts_search, rowid, branch, identifier, original_filename, bundle_identifier, package_name)
+    VALUES ('delete', old.rowid, old.branch, old.identifier, old.original_filename, old.bundle_identifier, old.package_name);
+    INSERT INTO artifacts_search (rowid, branch, identifier, original_filename, bundle_identifier, package_name)
+    VALUES (new.rowid, new.branch, new.identifier, new.original_filename, new.bundle_identifier, new.package_name);
+END;
+
+INSERT INTO artifacts_search (artifacts_search) VALUES ('rebuild');
@@ -0,0 +1,987 @@
+use axum::async_trait;
+use bson::{oid::ObjectId, Bson, Document};
+use chrono::Utc;
+use rusqlite::{
+    params_from_iter,
+    types::{Type, Value},
+    Connection, ErrorCode, Row,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    error::AppError,
+    models::{
+        artifact::{Artifact, BranchArtifacts},
+        channel::{Channel, Promotion},
+        pagination::{ArtifactFilters, Page, PageQuery, ProjectFilters, SortSpec},
+        project::{Platforms, Project, UpdateProjectInput},
+        user::User,
+    },
+};
+
+use super::{
+    ArtifactRepo, Backend, ChannelRepo, ProjectRepo, ProjectStorage, Repositories, UserRepo,
+};
+
+/// Applied in order; `PRAGMA user_version` records how many already ran.
+const MIGRATIONS: [&str; 7] = [
+    include_str!("migrations/0001_initial.sql"),
+    include_str!("migrations/0002_drop_qrcode.sql"),
+    include_str!("migrations/0003_channels.sql"),
+    include_str!("migrations/0004_android_metadata.sql"),
+    include_str!("migrations/0005_release_notes.sql"),
+    include_str!("migrations/0006_tags.sql"),
+    include_str!("migrations/0007_package_name_search.sql"),
+];
+
+/// Columns of each table, with the document field they hold. Rows are converted to and from
+/// the same BSON documents Mongo stores, so the models deserialize unchanged.
+const PROJECT_COLUMNS: [Column; 9] = [
+    Column::new("id", "_id", Kind::ObjectId),
+    Column::new("name", "name", Kind::Text),
+    Column::new("description", "description", Kind::Text),
+    Column::new("owner", "owner", Kind::ObjectId),
+    Column::new("platforms", "platforms", Kind::List),
+    Column::new("project_key", "key", Kind::Text),
+    Column::new("image", "image", Kind::Text),
+    Column::new("archived", "archived", Kind::Boolean),
+    Column::new("updated_at", "updatedAt", Kind::Integer),
+];
+const ARTIFACT_COLUMNS: [Column; 21] = [
+    Column::new("id", "_id", Kind::ObjectId),
+    Column::new("project_id", "projectId", Kind::ObjectId),
+    Column::new("original_filename", "originalFilename", Kind::Text),
+    Column::new("branch", "branch", Kind::Text),
+    Column::new("extension", "extension", Kind::Text),
+    Column::new("path", "path", Kind::Text),
+    Column::new("mime_type", "mimeType", Kind::Text),
+    Column::new("size", "size", Kind::Integer),
+    Column::new("identifier", "identifier", Kind::Text),
+    Column::new("created_at", "createdAt", Kind::Integer),
+    Column::new(
+        "bundle_identifier",
+        "iosMetadata.bundleIdentifier",
+        Kind::Text,
+    ),
+    Column::new("bundle_version", "iosMetadata.bundleVersion", Kind::Text),
+    Column::new("package_name", "androidMetadata.packageName", Kind::Text),
+    Column::new("version_code", "androidMetadata.versionCode", Kind::Text),
+    Column::new("release_notes", "releaseNotes", Kind::Text),
+    Column::new("commit_sha", "buildInfo.commitSha", Kind::Text),
+    Column::new("commit_message", "buildInfo.commitMessage", Kind::Text),
+    Column::new("build_url", "buildInfo.buildUrl", Kind::Text),
+    Column::new("author", "buildInfo.author", Kind::Text),
+    Column::new("tags", "tags", Kind::List),
+    Column::new("metadata", "metadata", Kind::Map),
+];
+const CHANNEL_COLUMNS: [Column; 4] = [
+    Column::new("id", "_id", Kind::ObjectId),
+    Column::new("project_id", "projectId", Kind::ObjectId),
+    Column::new("name", "name", Kind::Text),
+    Column::new("created_at", "createdAt", Kind::Integer),
+];
+const PROMOTION_COLUMNS: [Column; 7] = [
+    Column::new("id", "_id", Kind::ObjectId),
+    Column::new("project_id", "projectId", Kind::ObjectId),
+    Column::new("channel", "channel", Kind::Text),
+    Column::new("artifact_id", "artifactId", Kind::ObjectId),
+    Column::new("promoted_by", "promotedBy", Kind::ObjectId),
+    Column::new("promoted_at", "promotedAt", Kind::Integer),
+    Column::new("note", "note", Kind::Text),
+];
+const USER_COLUMNS: [Column; 7] = [
+    Column::new("id", "_id", Kind::ObjectId),
+    Column::new("name", "name", Kind::Text),
+    Column::new("email", "email", Kind::Text),
+    Column::new("role", "role", Kind::Text),
+    Column::new("password", "password", Kind::Text),
+    Column::new("salt", "salt", Kind::Text),
+    Column::new("favorite_projects", "favoriteProjects", Kind::List),
+];
+
+/// Favorites live in their own table and are folded back into an array, oldest first.
+const USER_SELECT: &str = "SELECT users.*, (
+    SELECT json_group_array(project_id) FROM (
+        SELECT project_id FROM favorite_projects WHERE user_id = users.id ORDER BY rowid
+    )
+) AS favorite_projects FROM users";
+
+/// Same fields and weights as the Mongo text indexes, in the order of the FTS columns.
+const PROJECT_SEARCH: &str = "SELECT projects.*, -bm25(projects_search, 10.0, 2.0) AS score
+    FROM projects_search JOIN projects ON projects.rowid = projects_search.rowid
+    WHERE projects_search MATCH ? AND projects.archived = 0
+    ORDER BY score DESC LIMIT ?";
+const ARTIFACT_SEARCH: &str =
+    "SELECT artifacts.*, -bm25(artifacts_search, 5.0, 5.0, 2.0, 2.0, 2.0) AS score
+    FROM artifacts_search JOIN artifacts ON artifacts.rowid = artifacts_search.rowid
+    WHERE artifacts_search MATCH ?
+    ORDER BY score DESC LIMIT ?";
+
+#[derive(Clone, Copy)]
+enum Kind {
+    ObjectId,
+    Text,
+    Integer,
+    Boolean,
+    /// An array of strings, stored as JSON.
+    List,
+    /// A document of strings, stored as a JSON object.
+    Map,
+}
+
+struct Column {
+    name: &'static str,
+    field: &'static str,
+    kind: Kind,
+}
+
+impl Column {
+    const fn new(name: &'static str, field: &'static str, kind: Kind) -> Column {
+        Column { name, field, kind }
+    }
+}
+
+pub fn repositories(db: Sqlite) -> Repositories {
+    Repositories {
+        backend: Arc::new(SqliteBackend { db: db.clone() }),
+        projects: Arc::new(SqliteProjectRepo { db: db.clone() }),
+        artifacts: Arc::new(SqliteArtifactRepo { db: db.clone() }),
+        channels: Arc::new(SqliteChannelRepo { db: db.clone() }),
+        users: Arc::new(SqliteUserRepo { db }),
+    }
+}
+
+/// A single connection shared by every repository. Queries are short, so they run one at a
+/// time on the blocking thread pool.
+#[derive(Clone)]
+pub struct Sqlite(Arc<Mutex<Connection>>);
+
+impl Sqlite {
+    /// Opens or creates the database file and applies pending migrations.
+    pub fn open(path: &Path) -> Result<Sqlite, AppError> {
+        let mut connection = Connection::open(path)?;
+        connection.execute_batch(
+            "PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;",
+        )?;
+        migrate(&mut connection)?;
+        Ok(Sqlite(Arc::new(Mutex::new(connection))))
+    }
+
+    async fn call<T, F>(&self, f: F) -> Result<T, AppError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, AppError> + Send + 'static,
+    {
+        let connection = self.0.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut connection = connection.lock().map_err(|_| AppError::Never)?;
+            f(&mut connection)
+        })
+        .await
+        .map_err(|_| AppError::Never)?
+    }
+
+    async fn query(
+        &self,
+        sql: impl Into<String>,
+        params: Vec<Value>,
+        columns: &'static [Column],
+    ) -> Result<Vec<Document>, AppError> {
+        let sql = sql.into();
+        self.call(move |connection| {
+            let mut statement = connection.prepare(&sql)?;
+            let rows =
+                statement.query_map(params_from_iter(params), |row| from_row(row, columns))?;
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
+        })
+        .await
+    }
+
+    async fn find_one<T: DeserializeOwned>(
+        &self,
+        sql: impl Into<String>,
+        params: Vec<Value>,
+        columns: &'static [Column],
+    ) -> Result<Option<T>, AppError> {
+        match self.query(sql, params, columns).await?.into_iter().next() {
+            Some(document) => Ok(Some(bson::from_document(document)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Runs a statement and returns the number of rows it changed.
+    async fn execute(&self, sql: impl Into<String>, params: Vec<Value>) -> Result<usize, AppError> {
+        let sql = sql.into();
+        self.call(move |connection| Ok(connection.execute(&sql, params_from_iter(params))?))
+            .await
+    }
+
+    async fn insert<T: Serialize>(
+        &self,
+        table: &str,
+        columns: &[Column],
+        value: &T,
+    ) -> Result<usize, AppError> {
+        let document = bson::to_document(value)?;
+        let values = columns
+            .iter()
+            .map(|column| sql_value(get_path(&document, column.field).unwrap_or(&Bson::Null)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let names: Vec<&str> = columns.iter().map(|column| column.name).collect();
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let sql = format!(
+            "INSERT INTO {table} ({}) VALUES ({placeholders})",
+            names.join(", ")
+        );
+        self.execute(sql, values).await
+    }
+
+    /// Keyset pagination over `select`, which mirrors `database::find_page`: rows come ordered
+    /// by the sort column, then id, starting after the cursor.
+    async fn find_page<T: DeserializeOwned>(
+        &self,
+        select: &str,
+        columns: &'static [Column],
+        mut conditions: Vec<String>,
+        mut params: Vec<Value>,
+        page: &PageQuery,
+        sort: SortSpec,
+    ) -> Result<Page<T>, AppError> {
+        let column = columns
+            .iter()
+            .find(|column| column.field == sort.field)
+            .map(|column| column.name)
+            .ok_or_else(|| AppError::InvalidSortField(sort.field.clone()))?;
+        let (operator, direction) = if sort.descending {
+            ("<", "DESC")
+        } else {
+            (">", "ASC")
+        };
+
+        if let Some(cursor) = page.cursor(&sort)? {
+            conditions.push(format!(
+                "({column} {operator} ? OR ({column} = ? AND id {operator} ?))"
+            ));
+            let value = sql_value(&cursor.value)?;
+            params.extend([value.clone(), value, Value::Text(cursor.id.to_hex())]);
+        }
+
+        let limit = page.limit();
+        let sql = format!(
+            "{select} {} ORDER BY {column} {direction}, id {direction} LIMIT {}",
+            where_clause(&conditions),
+            limit + 1
+        );
+        let documents = self.query(sql, params, columns).await?;
+        Page::from_documents(documents, limit, &sort)
+    }
+
+    /// Full-text search with one of the `*_SEARCH` statements, scored by bm25.
+    async fn search<T: DeserializeOwned>(
+        &self,
+        sql: &'static str,
+        columns: &'static [Column],
+        terms: &str,
+        limit: i64,
+    ) -> Result<Vec<(T, f64)>, AppError> {
+        let query = match fts_query(terms) {
+            Some(query) => query,
+            None => return Ok(vec![]),
+        };
+        let rows = self
+            .call(move |connection| {
+                let mut statement = connection.prepare(sql)?;
+                let rows = statement.query_map(
+                    params_from_iter([Value::Text(query), Value::Integer(limit)]),
+                    |row| Ok((from_row(row, columns)?, row.get::<_, f64>("score")?)),
+                )?;
+                Ok(rows.collect::<Result<Vec<_>, _>>()?)
+            })
+            .await?;
+
+        let mut results = Vec::new();
+        for (document, score) in rows {
+            results.push((bson::from_document(document)?, score));
+        }
+        Ok(results)
+    }
+}
+
+fn migrate(connection: &mut Connection) -> Result<(), AppError> {
+    let version: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", index + 1)?;
+        transaction.commit()?;
+        tracing::info!(version = index + 1, "Applied SQLite migration");
+    }
+    Ok(())
+}
+
+pub struct SqliteBackend {
+    db: Sqlite,
+}
+
+#[async_trait]
+impl Backend for SqliteBackend {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    async fn ping(&self) -> Result<(), AppError> {
+        self.db
+            .call(|connection| Ok(connection.query_row("SELECT 1", [], |_| Ok(()))?))
+            .await
+    }
+}
+
+pub struct SqliteProjectRepo {
+    db: Sqlite,
+}
+
+#[async_trait]
+impl ProjectRepo for SqliteProjectRepo {
+    async fn find_page(
+        &self,
+        filters: &ProjectFilters,
+        page: &PageQuery,
+        sort: SortSpec,
+    ) -> Result<Page<Project>, AppError> {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        if !filters.include_archived.unwrap_or(false) {
+            conditions.push("archived = 0".to_string());
+        }
+        if let Some(platform) = &filters.platform {
+            conditions.push(
+                "EXISTS (SELECT 1 FROM json_each(projects.platforms) WHERE value = ?)".to_string(),
+            );
+            params.push(sql_value(&bson::to_bson(platform)?)?);
+        }
+        self.db
+            .find_page(
+                "SELECT * FROM projects",
+                &PROJECT_COLUMNS,
+                conditions,
+                params,
+                page,
+                sort,
+            )
+            .await
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Project>, AppError> {
+        self.db
+            .find_one(
+                "SELECT * FROM projects WHERE id = ?",
+                vec![object_id(id)?],
+                &PROJECT_COLUMNS,
+            )
+            .await
+    }
+
+    async fn insert(&self, project: &Project) -> Result<(), AppError> {
+        self.db
+            .insert("projects", &PROJECT_COLUMNS, project)
+            .await?;
+        Ok(())
+    }
+
+    async fn name_taken(
+        &self,
+        owner: &str,
+        name: &str,
+        exclude: Option<&str>,
+    ) -> Result<bool, AppError> {
+        let exclude = match exclude {
+            Some(id) => object_id(id)?,
+            None => Value::Null,
+        };
+        let params = vec![
+            object_id(owner)?,
+            Value::Text(name.trim().to_string()),
+            exclude,
+        ];
+        self.db
+            .call(move |connection| {
+                Ok(connection.query_row(
+                    "SELECT EXISTS (
+                        SELECT 1 FROM projects
+                        WHERE owner = ?1 AND name = ?2 AND (?3 IS NULL OR id != ?3)
+                    )",
+                    params_from_iter(params),
+                    |row| row.get(0),
+                )?)
+            })
+            .await
+    }
+
+    async fn update(&self, id: &str, changes: &UpdateProjectInput) -> Result<bool, AppError> {
+        let mut sets = vec!["updated_at = ?"];
+        let mut params = vec![Value::Integer(Utc::now().timestamp_millis())];
+        if let Some(name) = &changes.name {
+            sets.push("name = ?");
+            params.push(Value::Text(name.trim().to_string()));
+        }
+        if let Some(description) = &changes.description {
+            sets.push("description = ?");
+            params.push(Value::Text(description.clone()));
+        }
+        if let Some(platforms) = &changes.platforms {
+            sets.push("platforms = ?");
+            params.push(sql_value(&bson::to_bson(platforms)?)?);
+        }
+        params.push(object_id(id)?);
+
+        let sql = format!("UPDATE projects SET {} WHERE id = ?", sets.join(", "));
+        Ok(self.db.execute(sql, params).await? == 1)
+    }
+
+    async fn set_image(&self, id: &str, image: &str) -> Result<bool, AppError> {
+        let params = vec![Value::Text(image.to_string()), object_id(id)?];
+        let changed = self
+            .db
+            .execute(
+                "UPDATE projects SET image = ?1 WHERE id = ?2 AND image IS NOT ?1",
+                params,
+            )
+            .await?;
+        Ok(changed == 1)
+    }
+
+    async fn set_archived(&self, id: &str, archived: bool) -> Result<(), AppError> {
+        let params = vec![Value::Integer(archived.into()), object_id(id)?];
+        self.db
+            .execute("UPDATE projects SET archived = ? WHERE id = ?", params)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), AppError> {
+        self.db
+            .execute("DELETE FROM projects WHERE id = ?", vec![object_id(id)?])
+            .await?;
+        Ok(())
+    }
+
+    async fn search(&self, terms: &str, limit: i64) -> Result<Vec<(Project, f64)>, AppError> {
+        self.db
+            .search(PROJECT_SEARCH, &PROJECT_COLUMNS, terms, limit)
+            .await
+    }
+}
+
+pub struct SqliteArtifactRepo {
+    db: Sqlite,
+}
+
+#[async_trait]
+impl ArtifactRepo for SqliteArtifactRepo {
+    async fn find_page(
+        &self,
+        filters: &ArtifactFilters,
+        page: &PageQuery,
+        sort: SortSpec,
+    ) -> Result<Page<Artifact>, AppError> {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        if let Some(project_id) = &filters.project_id {
+            conditions.push("project_id = ?".to_string());
+            params.push(object_id(project_id)?);
+        }
+        if let Some(branch) = &filters.branch {
+            conditions.push("branch = ?".to_string());
+            params.push(Value::Text(branch.clone()));
+        }
+        if let Some(extension) = &filters.extension {
+            conditions.push("extension = ?".to_string());
+            params.push(sql_value(&bson::to_bson(extension)?)?);
+        }
+        if let Some(created_after) = filters.created_after {
+            conditions.push("created_at >= ?".to_string());
+            params.push(Value::Integer(created_after));
+        }
+        if let Some(created_before) = filters.created_before {
+            conditions.push("created_at <= ?".to_string());
+            params.push(Value::Integer(created_before));
+        }
+        match &filters.platform {
+            Some(Platforms::Ios) => conditions.push("extension = 'ipa'".to_string()),
+            Some(Platforms::Android) => conditions.push("extension IN ('apk', 'aab')".to_string()),
+            None => (),
+        }
+        if let Some(bundle_identifier) = &filters.bundle_identifier {
+            conditions.push("(bundle_identifier = ? OR package_name = ?)".to_string());
+            params.push(Value::Text(bundle_identifier.clone()));
+            params.push(Value::Text(bundle_identifier.clone()));
+        }
+        if let Some(tag) = &filters.tag {
+            conditions
+                .push("id IN (SELECT artifact_id FROM artifact_tags WHERE tag = ?)".to_string());
+            params.push(Value::Text(tag.clone()));
+        }
+        if let Some((key, value)) = filters.metadata_entry()? {
+            conditions.push(
+                "id IN (SELECT artifact_id FROM artifact_metadata WHERE key = ? AND value = ?)"
+                    .to_string(),
+            );
+            params.push(Value::Text(key.to_string()));
+            params.push(Value::Text(value.to_string()));
+        }
+        self.db
+            .find_page(
+                "SELECT * FROM artifacts",
+                &ARTIFACT_COLUMNS,
+                conditions,
+                params,
+                page,
+                sort,
+            )
+            .await
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Artifact>, AppError> {
+        self.db
+            .find_one(
+                "SELECT * FROM artifacts WHERE id = ?",
+                vec![object_id(id)?],
+                &ARTIFACT_COLUMNS,
+            )
+            .await
+    }
+
+    async fn find_by_branch(&self, project_id: &str) -> Result<Vec<BranchArtifacts>, AppError> {
+        let documents = self
+            .db
+            .query(
+                "SELECT * FROM artifacts WHERE project_id = ? ORDER BY branch, created_at, id",
+                vec![object_id(project_id)?],
+                &ARTIFACT_COLUMNS,
+            )
+            .await?;
+
+        let mut groups: Vec<BranchArtifacts> = Vec::new();
+        for document in documents {
+            let branch = document.get_str("branch").unwrap_or_default().to_string();
+            let artifact = bson::from_document(document)?;
+            match groups.last_mut() {
+                Some(group) if group.branch == branch => group.artifacts.push(artifact),
+                _ => groups.push(BranchArtifacts {
+                    branch,
+                    artifacts: vec![artifact],
+                }),
+            }
+        }
+        Ok(groups)
+    }
+
+    async fn insert(&self, artifact: &Artifact) -> Result<(), AppError> {
+        self.db
+            .insert("artifacts", &ARTIFACT_COLUMNS, artifact)
+            .await?;
+        Ok(())
+    }
+
+    async fn find_duplicates(&self, artifact: &Artifact) -> Result<Vec<Artifact>, AppError> {
+        let params = vec![
+            object_id(artifact.get_project_id())?,
+            Value::Text(artifact.get_branch().clone()),
+            Value::Text(artifact.get_identifier().clone()),
+            sql_value(&bson::to_bson(artifact.get_extension())?)?,
+            object_id(artifact.get_id())?,
+        ];
+        let documents = self
+            .db
+            .query(
+                "SELECT * FROM artifacts
+                WHERE project_id = ? AND branch = ? AND identifier = ? AND extension = ?
+                    AND id != ?",
+                params,
+                &ARTIFACT_COLUMNS,
+            )
+            .await?;
+        documents
+            .into_iter()
+            .map(|document| Ok(bson::from_document(document)?))
+            .collect()
+    }
+
+    async fn update_details(&self, artifact: &Artifact) -> Result<(), AppError> {
+        let text = |value: Option<&String>| value.cloned().map_or(Value::Null, Value::Text);
+        let build_info = artifact.get_build_info();
+        let params = vec![
+            text(artifact.get_release_notes()),
+            text(build_info.commit_sha.as_ref()),
+            text(build_info.commit_message.as_ref()),
+            text(build_info.build_url.as_ref()),
+            text(build_info.author.as_ref()),
+            sql_value(&bson::to_bson(artifact.get_tags())?)?,
+            sql_value(&bson::to_bson(artifact.get_metadata())?)?,
+            object_id(artifact.get_id())?,
+        ];
+        self.db
+            .execute(
+                "UPDATE artifacts SET release_notes = ?, commit_sha = ?, commit_message = ?,
+                    build_url = ?, author = ?, tags = ?, metadata = ?
+                WHERE id = ?",
+                params,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn set_path(&self, id: &str, path: &str) -> Result<(), AppError> {
+        let params = vec![Value::Text(path.to_string()), object_id(id)?];
+        self.db
+            .execute("UPDATE artifacts SET path = ? WHERE id = ?", params)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), AppError> {
+        self.db
+            .execute("DELETE FROM artifacts WHERE id = ?", vec![object_id(id)?])
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_by_project(&self, project_id: &str) -> Result<(), AppError> {
+        let params = vec![object_id(project_id)?];
+        self.db
+            .execute("DELETE FROM artifacts WHERE project_id = ?", params)
+            .await?;
+        Ok(())
+    }
+
+    async fn search(&self, terms: &str, limit: i64) -> Result<Vec<(Artifact, f64)>, AppError> {
+        self.db
+            .search(ARTIFACT_SEARCH, &ARTIFACT_COLUMNS, terms, limit)
+            .await
+    }
+
+    async fn storage_by_project(&self) -> Result<Vec<ProjectStorage>, AppError> {
+        self.db
+            .call(|connection| {
+                let mut statement = connection.prepare(
+                    "SELECT project_id, COUNT(*), COALESCE(SUM(size), 0)
+                    FROM artifacts GROUP BY project_id",
+                )?;
+                let rows = statement.query_map([], |row| {
+                    Ok(ProjectStorage {
+                        project_id: row.get(0)?,
+                        artifacts: row.get(1)?,
+                        bytes: row.get(2)?,
+                    })
+                })?;
+                Ok(rows.collect::<Result<Vec<_>, _>>()?)
+            })
+            .await
+    }
+}
+
+pub struct SqliteChannelRepo {
+    db: Sqlite,
+}
+
+#[async_trait]
+impl ChannelRepo for SqliteChannelRepo {
+    async fn find_by_project(&self, project_id: &str) -> Result<Vec<Channel>, AppError> {
+        let documents = self
+            .db
+            .query(
+                "SELECT * FROM channels WHERE project_id = ? ORDER BY name",
+                vec![object_id(project_id)?],
+                &CHANNEL_COLUMNS,
+            )
+            .await?;
+        documents
+            .into_iter()
+            .map(|document| Ok(bson::from_document(document)?))
+            .collect()
+    }
+
+    async fn find(&self, project_id: &str, name: &str) -> Result<Option<Channel>, AppError> {
+        self.db
+            .find_one(
+                "SELECT * FROM channels WHERE project_id = ? AND name = ?",
+                vec![object_id(project_id)?, Value::Text(name.to_string())],
+                &CHANNEL_COLUMNS,
+            )
+            .await
+    }
+
+    async fn insert(&self, channel: &Channel) -> Result<bool, AppError> {
+        match self.db.insert("channels", &CHANNEL_COLUMNS, channel).await {
+            Ok(_) => Ok(true),
+            Err(AppError::SqliteError(rusqlite::Error::SqliteFailure(e, _)))
+                if e.code == ErrorCode::ConstraintViolation =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn insert_promotion(&self, promotion: &Promotion) -> Result<(), AppError> {
+        self.db
+            .insert("promotions", &PROMOTION_COLUMNS, promotion)
+            .await?;
+        Ok(())
+    }
+
+    async fn reassign_promotions(&self, from: &str, to: &str) -> Result<(), AppError> {
+        let params = vec![object_id(to)?, object_id(from)?];
+        self.db
+            .execute(
+                "UPDATE promotions SET artifact_id = ? WHERE artifact_id = ?",
+                params,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn promotions(
+        &self,
+        project_id: &str,
+        channel: &str,
+    ) -> Result<Vec<Promotion>, AppError> {
+        let documents = self
+            .db
+            .query(
+                "SELECT * FROM promotions WHERE project_id = ? AND channel = ?
+                ORDER BY promoted_at DESC, id DESC",
+                vec![object_id(project_id)?, Value::Text(channel.to_string())],
+                &PROMOTION_COLUMNS,
+            )
+            .await?;
+        documents
+            .into_iter()
+            .map(|document| Ok(bson::from_document(document)?))
+            .collect()
+    }
+
+    async fn latest_promotion(
+        &self,
+        project_id: &str,
+        channel: &str,
+    ) -> Result<Option<Promotion>, AppError> {
+        self.db
+            .find_one(
+                "SELECT * FROM promotions WHERE project_id = ? AND channel = ?
+                ORDER BY promoted_at DESC, id DESC LIMIT 1",
+                vec![object_id(project_id)?, Value::Text(channel.to_string())],
+                &PROMOTION_COLUMNS,
+            )
+            .await
+    }
+
+    async fn delete_by_project(&self, project_id: &str) -> Result<(), AppError> {
+        let project_id = object_id(project_id)?;
+        self.db
+            .execute(
+                "DELETE FROM promotions WHERE project_id = ?",
+                vec![project_id.clone()],
+            )
+            .await?;
+        self.db
+            .execute(
+                "DELETE FROM channels WHERE project_id = ?",
+                vec![project_id],
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+pub struct SqliteUserRepo {
+    db: Sqlite,
+}
+
+#[async_trait]
+impl UserRepo for SqliteUserRepo {
+    async fn find_page(&self, page: &PageQuery, sort: SortSpec) -> Result<Page<User>, AppError> {
+        self.db
+            .find_page(USER_SELECT, &USER_COLUMNS, vec![], vec![], page, sort)
+            .await
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<User>, AppError> {
+        let sql = format!("{USER_SELECT} WHERE id = ?");
+        self.db
+            .find_one(sql, vec![object_id(id)?], &USER_COLUMNS)
+            .await
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>, AppError> {
+        let sql = format!("{USER_SELECT} WHERE email = ?");
+        self.db
+            .find_one(sql, vec![Value::Text(email.to_string())], &USER_COLUMNS)
+            .await
+    }
+
+    async fn insert(&self, user: &User) -> Result<(), AppError> {
+        // favorites start empty and are added through `set_favorite_project`
+        let columns = &USER_COLUMNS[..USER_COLUMNS.len() - 1];
+        match self.db.insert("users", columns, user).await {
+            Ok(_) => Ok(()),
+            Err(AppError::SqliteError(rusqlite::Error::SqliteFailure(e, _)))
+                if e.code == ErrorCode::ConstraintViolation =>
+            {
+                Err(AppError::UserAlreadyRegistered)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn set_favorite_project(
+        &self,
+        user_id: &str,
+        project_id: &str,
+        favorite: bool,
+    ) -> Result<(), AppError> {
+        let sql = if favorite {
+            "INSERT OR IGNORE INTO favorite_projects (user_id, project_id)
+            SELECT id, ?2 FROM users WHERE id = ?1"
+        } else {
+            "DELETE FROM favorite_projects WHERE user_id = ?1 AND project_id = ?2"
+        };
+        let params = vec![object_id(user_id)?, Value::Text(project_id.to_string())];
+        self.db.execute(sql, params).await?;
+        Ok(())
+    }
+
+    async fn remove_favorite_project(&self, project_id: &str) -> Result<(), AppError> {
+        let params = vec![Value::Text(project_id.to_string())];
+        self.db
+            .execute("DELETE FROM favorite_projects WHERE project_id = ?", params)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Builds the document a row stands for. NULL columns are left out, like unset fields.
+fn from_row(row: &Row, columns: &[Column]) -> rusqlite::Result<Document> {
+    let mut document = Document::new();
+    for column in columns {
+        let value = match (column.kind, row.get::<_, Value>(column.name)?) {
+            (_, Value::Null) => continue,
+            (Kind::ObjectId, Value::Text(hex)) => {
+                Bson::ObjectId(ObjectId::parse_str(hex).map_err(|e| conversion_error(column, e))?)
+            }
+            (Kind::List, Value::Text(json)) => {
+                let items: Vec<String> =
+                    serde_json::from_str(&json).map_err(|e| conversion_error(column, e))?;
+                Bson::Array(items.into_iter().map(Bson::String).collect())
+            }
+            (Kind::Map, Value::Text(json)) => {
+                let entries: BTreeMap<String, String> =
+                    serde_json::from_str(&json).map_err(|e| conversion_error(column, e))?;
+                Bson::Document(
+                    entries
+                        .into_iter()
+                        .map(|(key, value)| (key, Bson::String(value)))
+                        .collect(),
+                )
+            }
+            (Kind::Boolean, Value::Integer(value)) => Bson::Boolean(value != 0),
+            (Kind::Text, Value::Text(text)) => Bson::String(text),
+            (Kind::Integer, Value::Integer(value)) => Bson::Int64(value),
+            (_, value) => {
+                return Err(rusqlite::Error::InvalidColumnType(
+                    0,
+                    column.name.to_string(),
+                    value.data_type(),
+                ))
+            }
+        };
+        set_path(&mut document, column.field, value);
+    }
+    Ok(document)
+}
+
+fn conversion_error<E>(column: &Column, error: E) -> rusqlite::Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    tracing::error!(column = column.name, "Invalid value in SQLite column");
+    rusqlite::Error::FromSqlConversionFailure(0, Type::Text, Box::new(error))
+}
+
+/// The SQL value of a document field. Arrays and documents only ever hold strings, and are
+/// stored as JSON.
+fn sql_value(value: &Bson) -> Result<Value, AppError> {
+    Ok(match value {
+        Bson::Null => Value::Null,
+        Bson::ObjectId(oid) => Value::Text(oid.to_hex()),
+        Bson::String(text) => Value::Text(text.clone()),
+        Bson::Int32(value) => Value::Integer(i64::from(*value)),
+        Bson::Int64(value) => Value::Integer(*value),
+        Bson::Boolean(value) => Value::Integer(i64::from(*value)),
+        Bson::Array(items) => {
+            let items: Vec<&str> = items.iter().filter_map(Bson::as_str).collect();
+            Value::Text(serde_json::to_string(&items).map_err(|_| AppError::Never)?)
+        }
+        Bson::Document(fields) => {
+            let entries: BTreeMap<&str, &str> = fields
+                .iter()
+                .filter_map(|(key, value)| Some((key.as_str(), value.as_str()?)))
+                .collect();
+            Value::Text(serde_json::to_string(&entries).map_err(|_| AppError::Never)?)
+        }
+        _ => return Err(AppError::Never),
+    })
+}
+
+/// Ids are validated like the Mongo backend does, so malformed ones are still rejected.
+fn object_id(id: &str) -> Result<Value, AppError> {
+    Ok(Value::Text(ObjectId::parse_str(id)?.to_hex()))
+}
+
+fn where_clause(conditions: &[String]) -> String {
+    if conditions.is_empty() {
+        return String::new();
+    }
+    format!("WHERE {}", conditions.join(" AND "))
+}
+
+/// Any of the search terms, quoted so FTS5 doesn't read them as operators.
+fn fts_query(terms: &str) -> Option<String> {
+    let words: Vec<String> = terms
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"", word.to_lowercase()))
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    Some(words.join(" OR "))
+}
+
+fn get_path<'a>(document: &'a Document, path: &str) -> Option<&'a Bson> {
+    match path.split_once('.') {
+        Some((parent, field)) => document.get_document(parent).ok()?.get(field),
+        None => document.get(path),
+    }
+}
+
+fn set_path(document: &mut Document, path: &str, value: Bson) {
+    match path.split_once('.') {
+        Some((parent, field)) => {
+            if !document.contains_key(parent) {
+                document.insert(parent, Document::new());
+            }
+            if let Ok(parent) = document.get_document_mut(parent) {
+                parent.insert(field, value);
+            }
+        }
+        None => {
+            document.insert(path, value);
+        }
+    }
+}
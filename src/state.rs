@@ -0,0 +1,49 @@
+use axum::extract::FromRef;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use crate::{config::Config, repositories::Repositories, tls::TlsStatus};
+
+#[derive(Clone)]
+pub struct AppState {
+    pub config: Arc<Config>,
+    pub repos: Repositories,
+    /// `None` when TLS is terminated by a reverse proxy.
+    pub tls: Option<TlsStatus>,
+    pub draining: Draining,
+}
+
+/// Set once the server starts shutting down, so new uploads can be refused while in-flight
+/// requests are drained.
+#[derive(Clone, Default)]
+pub struct Draining(Arc<AtomicBool>);
+
+impl Draining {
+    pub fn start(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl FromRef<AppState> for Repositories {
+    fn from_ref(state: &AppState) -> Repositories {
+        state.repos.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<Config> {
+    fn from_ref(state: &AppState) -> Arc<Config> {
+        state.config.clone()
+    }
+}
+
+impl FromRef<AppState> for Draining {
+    fn from_ref(state: &AppState) -> Draining {
+        state.draining.clone()
+    }
+}
@@ -0,0 +1,220 @@
+use axum::{
+    http::{HeaderMap, HeaderName, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use mongodb::event::command::{
+    CommandEventHandler, CommandFailedEvent, CommandStartedEvent, CommandSucceededEvent,
+};
+use opentelemetry::{
+    global,
+    propagation::{Extractor, Injector},
+    sdk::{propagation::TraceContextPropagator, trace as sdktrace, Resource},
+    trace::{TraceError, TracerProvider},
+    KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use std::{collections::HashMap, env, sync::Mutex};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+use crate::{metrics::METRICS, proxy::RequestOrigin};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Installs the tracing subscriber and the W3C trace context propagator.
+///
+/// Spans are exported over OTLP when `OTEL_EXPORTER_OTLP_ENDPOINT` is set; otherwise they are
+/// only used to correlate logs and propagate `traceparent` headers.
+pub fn init() -> Result<(), TraceError> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let service_name =
+        env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| "app_repository_server".into());
+    let trace_config = sdktrace::config().with_resource(Resource::new(vec![KeyValue::new(
+        "service.name",
+        service_name,
+    )]));
+
+    let tracer = match env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+        Ok(endpoint) => opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .tonic()
+                    .with_endpoint(endpoint),
+            )
+            .with_trace_config(trace_config)
+            .install_batch(opentelemetry::runtime::Tokio)?,
+        Err(_) => {
+            let provider = sdktrace::TracerProvider::builder()
+                .with_config(trace_config)
+                .build();
+            let tracer = provider.tracer("app_repository_server");
+            global::set_tracer_provider(provider);
+            tracer
+        }
+    };
+
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::EnvFilter::new(
+            env::var("RUST_LOG").unwrap_or_else(|_| {
+                "app_repository_server=debug,axum=debug,tower_http=debug,mongodb=debug".into()
+            }),
+        ))
+        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .init();
+
+    Ok(())
+}
+
+/// Flushes spans that haven't been exported yet.
+pub fn shutdown() {
+    global::shutdown_tracer_provider();
+}
+
+/// Returns the id of the request being handled by the current task, if any.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Creates the span of an incoming request, continuing the trace from its `traceparent` header.
+pub fn make_request_span<B>(request: &Request<B>) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let client_ip = request
+        .extensions()
+        .get::<RequestOrigin>()
+        .and_then(|origin| origin.client_ip)
+        .map(|ip| ip.to_string())
+        .unwrap_or_default();
+
+    let span = tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        version = ?request.version(),
+        request_id = %request_id,
+        client_ip = %client_ip,
+    );
+
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    span.set_parent(parent);
+
+    span
+}
+
+/// Makes the request id available to error responses and returns the trace context to the
+/// caller in a `traceparent` response header.
+pub async fn request_context<B>(request: Request<B>, next: Next<B>) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    let mut response = REQUEST_ID.scope(request_id, next.run(request)).await;
+
+    let context = Span::current().context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(response.headers_mut()))
+    });
+
+    response
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl<'a> Extractor for HeaderExtractor<'a> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl<'a> Injector for HeaderInjector<'a> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+/// Opens a span for every command sent to Mongo, as a child of the span that issued it.
+#[derive(Default)]
+pub struct MongoCommandTracer {
+    spans: Mutex<HashMap<i32, Span>>,
+}
+
+impl MongoCommandTracer {
+    fn finish(&self, request_id: i32) -> Option<Span> {
+        self.spans
+            .lock()
+            .ok()
+            .and_then(|mut spans| spans.remove(&request_id))
+    }
+}
+
+impl CommandEventHandler for MongoCommandTracer {
+    fn handle_command_started_event(&self, event: CommandStartedEvent) {
+        let collection = event
+            .command
+            .iter()
+            .next()
+            .and_then(|(_, value)| value.as_str())
+            .unwrap_or_default();
+        let span = tracing::debug_span!(
+            "mongodb",
+            db.system = "mongodb",
+            db.name = %event.db,
+            db.operation = %event.command_name,
+            db.collection = %collection,
+            duration_ms = tracing::field::Empty,
+            error = tracing::field::Empty,
+        );
+        if let Ok(mut spans) = self.spans.lock() {
+            spans.insert(event.request_id, span);
+        }
+    }
+
+    fn handle_command_succeeded_event(&self, event: CommandSucceededEvent) {
+        METRICS
+            .mongo_command_duration
+            .with_label_values(&[&event.command_name])
+            .observe(event.duration.as_secs_f64());
+        if let Some(span) = self.finish(event.request_id) {
+            span.record("duration_ms", event.duration.as_millis() as u64);
+        }
+    }
+
+    fn handle_command_failed_event(&self, event: CommandFailedEvent) {
+        METRICS
+            .mongo_command_duration
+            .with_label_values(&[&event.command_name])
+            .observe(event.duration.as_secs_f64());
+        if let Some(span) = self.finish(event.request_id) {
+            span.record("duration_ms", event.duration.as_millis() as u64);
+            span.record("error", tracing::field::display(&event.failure));
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use axum_server::tls_rustls::RustlsConfig;
+use chrono::Utc;
+use serde::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+#[cfg(unix)]
+use tokio::signal::unix::{signal, SignalKind};
+use utoipa::ToSchema;
+use x509_parser::pem::Pem;
+
+use crate::{error::AppError, metrics::METRICS};
+
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateInfo {
+    pub subject: String,
+    /// Expiry of the certificate, as seconds since the Unix epoch.
+    pub not_after: i64,
+}
+
+impl CertificateInfo {
+    /// Reads the leaf (first) certificate of a PEM chain.
+    pub fn from_pem_file(path: &Path) -> Result<CertificateInfo, AppError> {
+        let pem = std::fs::read(path)?;
+        let (pem, _) = Pem::read(std::io::Cursor::new(pem.as_slice()))
+            .map_err(|_| AppError::InvalidCertificate)?;
+        let certificate = pem.parse_x509().map_err(|_| AppError::InvalidCertificate)?;
+
+        Ok(CertificateInfo {
+            subject: certificate.subject().to_string(),
+            not_after: certificate.validity().not_after.timestamp(),
+        })
+    }
+}
+
+/// Certificate currently served by the HTTPS listener, shared with the readiness checks.
+#[derive(Clone, Default)]
+pub struct TlsStatus(Arc<RwLock<Option<CertificateInfo>>>);
+
+impl TlsStatus {
+    pub fn get(&self) -> Option<CertificateInfo> {
+        self.0.read().ok().and_then(|info| info.clone())
+    }
+
+    pub fn set(&self, certificate: CertificateInfo) {
+        let remaining_days = (certificate.not_after - Utc::now().timestamp()) / (24 * 60 * 60);
+        tracing::info!(
+            subject = %certificate.subject,
+            not_after = certificate.not_after,
+            remaining_days,
+            "serving TLS certificate"
+        );
+        METRICS.tls_certificate_expiry.set(certificate.not_after);
+
+        if let Ok(mut info) = self.0.write() {
+            *info = Some(certificate);
+        }
+    }
+}
+
+/// Paths of the certificate chain and private key served over HTTPS.
+#[derive(Clone)]
+pub struct CertificatePaths {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    /// Held while the pair is read or replaced, so a reload never mixes a new certificate with
+    /// the previous key.
+    swap: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl CertificatePaths {
+    pub fn new(certs_path: &Path) -> CertificatePaths {
+        CertificatePaths {
+            cert: certs_path.join("cert.pem"),
+            key: certs_path.join("key.pem"),
+            swap: Arc::default(),
+        }
+    }
+
+    /// Waits for any replacement of the pair in progress and blocks new ones until dropped.
+    pub async fn lock(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.swap.lock().await
+    }
+
+    fn modified_at(&self) -> Option<(SystemTime, SystemTime)> {
+        let cert = std::fs::metadata(&self.cert).and_then(|m| m.modified());
+        let key = std::fs::metadata(&self.key).and_then(|m| m.modified());
+        match (cert, key) {
+            (Ok(cert), Ok(key)) => Some((cert, key)),
+            _ => None,
+        }
+    }
+}
+
+/// Reloads the certificate and key into `config` on SIGHUP, where there are signals, or whenever
+/// either file changes on disk, e.g. after a certbot renewal. When the new files can't be loaded
+/// the previous certificate keeps being served.
+pub async fn watch_certificates(
+    config: RustlsConfig,
+    paths: CertificatePaths,
+    status: TlsStatus,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    let mut last_modified = paths.modified_at();
+
+    #[cfg(unix)]
+    let mut hangup = signal(SignalKind::hangup()).expect("Failed to install SIGHUP handler");
+
+    loop {
+        #[cfg(unix)]
+        let hung_up = hangup.recv();
+        #[cfg(not(unix))]
+        let hung_up = std::future::pending::<()>();
+
+        let forced = tokio::select! {
+            _ = ticker.tick() => false,
+            _ = hung_up => true,
+        };
+
+        let modified = paths.modified_at();
+        if !forced && (modified.is_none() || modified == last_modified) {
+            continue;
+        }
+        last_modified = modified;
+
+        if let Err(error) = reload_certificate(&config, &paths, &status).await {
+            tracing::error!(
+                ?error,
+                "failed to reload TLS certificate, keeping the previous one"
+            );
+        }
+    }
+}
+
+/// Loads the certificate and key on disk into `config`, keeping the previous ones on error.
+pub async fn reload_certificate(
+    config: &RustlsConfig,
+    paths: &CertificatePaths,
+    status: &TlsStatus,
+) -> Result<(), AppError> {
+    let _swap = paths.lock().await;
+    let certificate = CertificateInfo::from_pem_file(&paths.cert)?;
+    config.reload_from_pem_file(&paths.cert, &paths.key).await?;
+    status.set(certificate);
+    Ok(())
+}
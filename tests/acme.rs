@@ -0,0 +1,395 @@
+use app_repository_server::{
+    acme::{self, AcmeChallenges, AcmeConfig},
+    helpers::base64::{decode_base64_url, encode_base64_url},
+    tls::{CertificateInfo, CertificatePaths},
+};
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, head, post},
+    Json, Router,
+};
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, CertificateSigningRequest, IsCa, KeyPair,
+    SanType,
+};
+use ring::digest;
+use serde_json::{json, Value};
+use std::{
+    collections::HashSet,
+    fs,
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+use x509_parser::pem::Pem;
+
+const DOMAIN: &str = "dist.example.com";
+const BAD_NONCE: &str = "urn:ietf:params:acme:error:badNonce";
+
+/// In-memory CA following the RFC 8555 flow, in the spirit of Pebble: it hands out nonces,
+/// rejects the first one it sees, validates the HTTP-01 challenge by fetching it from the client
+/// and signs the CSR it is sent.
+struct FakeCa {
+    base: String,
+    challenge_server: SocketAddr,
+    ca: Certificate,
+    nonces: HashSet<String>,
+    rejected_nonce: bool,
+    accounts: Vec<String>,
+    jwk: Option<Value>,
+    domains: Vec<String>,
+    token: String,
+    authorization: &'static str,
+    order: &'static str,
+    chain: Option<String>,
+}
+
+type SharedCa = Arc<Mutex<FakeCa>>;
+
+impl FakeCa {
+    fn nonce(&mut self) -> String {
+        let nonce = Uuid::new_v4().simple().to_string();
+        self.nonces.insert(nonce.clone());
+        nonce
+    }
+
+    fn order(&self) -> Value {
+        let mut order = json!({
+            "status": self.order,
+            "authorizations": [format!("{}/authz/1", self.base)],
+            "finalize": format!("{}/finalize/1", self.base),
+        });
+        if self.chain.is_some() {
+            order["certificate"] = json!(format!("{}/cert/1", self.base));
+        }
+        order
+    }
+}
+
+/// Checks the JWS of a request to `path` and returns its decoded payload, `Value::Null` for a
+/// POST-as-GET.
+fn open_jws(ca: &mut FakeCa, path: &str, body: &[u8]) -> Result<Value, Box<Response>> {
+    let jws: Value = serde_json::from_slice(body).unwrap();
+    let protected = decode_base64_url(jws["protected"].as_str().unwrap()).unwrap();
+    let protected: Value = serde_json::from_slice(&protected).unwrap();
+
+    assert_eq!(protected["alg"], "ES256");
+    assert_eq!(protected["url"], format!("{}{path}", ca.base));
+    let nonce = protected["nonce"].as_str().unwrap().to_string();
+    if !ca.nonces.remove(&nonce) || !ca.rejected_nonce {
+        ca.rejected_nonce = true;
+        return Err(Box::new(reply(
+            ca,
+            StatusCode::BAD_REQUEST,
+            None,
+            json!({ "type": BAD_NONCE, "detail": "stale nonce" }),
+        )));
+    }
+
+    match (&protected["jwk"], &protected["kid"]) {
+        (Value::Object(_), Value::Null) => ca.jwk = Some(protected["jwk"].clone()),
+        (Value::Null, Value::String(kid)) => assert!(ca.accounts.contains(kid)),
+        _ => panic!("request must be signed with either a jwk or a kid"),
+    }
+
+    let payload = jws["payload"].as_str().unwrap();
+    if payload.is_empty() {
+        return Ok(Value::Null);
+    }
+    Ok(serde_json::from_slice(&decode_base64_url(payload).unwrap()).unwrap())
+}
+
+fn reply(ca: &mut FakeCa, status: StatusCode, location: Option<String>, body: Value) -> Response {
+    let mut headers = HeaderMap::new();
+    headers.insert("replay-nonce", ca.nonce().parse().unwrap());
+    if let Some(location) = location {
+        headers.insert(header::LOCATION, location.parse().unwrap());
+    }
+    (status, headers, Json(body)).into_response()
+}
+
+async fn directory(State(ca): State<SharedCa>) -> Json<Value> {
+    let base = ca.lock().unwrap().base.clone();
+    Json(json!({
+        "newNonce": format!("{base}/nonce"),
+        "newAccount": format!("{base}/account"),
+        "newOrder": format!("{base}/order"),
+    }))
+}
+
+async fn new_nonce(State(ca): State<SharedCa>) -> impl IntoResponse {
+    let nonce = ca.lock().unwrap().nonce();
+    [("replay-nonce", nonce)]
+}
+
+async fn new_account(State(ca): State<SharedCa>, body: Bytes) -> Response {
+    let mut ca = ca.lock().unwrap();
+    let payload = match open_jws(&mut ca, "/account", &body) {
+        Ok(payload) => payload,
+        Err(response) => return *response,
+    };
+    assert_eq!(payload["termsOfServiceAgreed"], true);
+
+    let account = format!("{}/account/1", ca.base);
+    let status = if ca.accounts.contains(&account) {
+        StatusCode::OK
+    } else {
+        ca.accounts.push(account.clone());
+        StatusCode::CREATED
+    };
+    reply(&mut ca, status, Some(account), json!({ "status": "valid" }))
+}
+
+async fn new_order(State(ca): State<SharedCa>, body: Bytes) -> Response {
+    let mut ca = ca.lock().unwrap();
+    let payload = match open_jws(&mut ca, "/order", &body) {
+        Ok(payload) => payload,
+        Err(response) => return *response,
+    };
+
+    ca.domains = payload["identifiers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|identifier| identifier["value"].as_str().unwrap().to_string())
+        .collect();
+    ca.token = Uuid::new_v4().simple().to_string();
+    ca.authorization = "pending";
+    ca.order = "pending";
+    ca.chain = None;
+
+    let location = format!("{}/order/1", ca.base);
+    let order = ca.order();
+    reply(&mut ca, StatusCode::CREATED, Some(location), order)
+}
+
+async fn authorization(State(ca): State<SharedCa>, body: Bytes) -> Response {
+    let mut ca = ca.lock().unwrap();
+    if let Err(response) = open_jws(&mut ca, "/authz/1", &body) {
+        return *response;
+    }
+
+    let authorization = json!({
+        "status": ca.authorization,
+        "identifier": { "type": "dns", "value": ca.domains[0] },
+        "challenges": [
+            { "type": "dns-01", "url": format!("{}/challenge/2", ca.base), "token": "unused" },
+            { "type": "http-01", "url": format!("{}/challenge/1", ca.base), "token": ca.token },
+        ],
+    });
+    reply(&mut ca, StatusCode::OK, None, authorization)
+}
+
+/// Fetches the key authorization the way the CA would over port 80, then marks the
+/// authorization and order as done.
+async fn respond_to_challenge(State(shared): State<SharedCa>, body: Bytes) -> Response {
+    let (server, token, jwk) = {
+        let mut ca = shared.lock().unwrap();
+        if let Err(response) = open_jws(&mut ca, "/challenge/1", &body) {
+            return *response;
+        }
+        (
+            ca.challenge_server,
+            ca.token.clone(),
+            ca.jwk.clone().unwrap(),
+        )
+    };
+
+    let uri = format!("http://{server}/.well-known/acme-challenge/{token}");
+    let response = hyper::Client::new()
+        .get(uri.parse().unwrap())
+        .await
+        .unwrap();
+    let served = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let expected = format!("{token}.{}", thumbprint(&jwk));
+
+    let mut ca = shared.lock().unwrap();
+    if served == expected.as_bytes() {
+        ca.authorization = "valid";
+        ca.order = "ready";
+    } else {
+        ca.authorization = "invalid";
+        ca.order = "invalid";
+    }
+    reply(
+        &mut ca,
+        StatusCode::OK,
+        None,
+        json!({ "status": "processing" }),
+    )
+}
+
+async fn order(State(ca): State<SharedCa>, body: Bytes) -> Response {
+    let mut ca = ca.lock().unwrap();
+    if let Err(response) = open_jws(&mut ca, "/order/1", &body) {
+        return *response;
+    }
+
+    let order = ca.order();
+    reply(&mut ca, StatusCode::OK, None, order)
+}
+
+async fn finalize(State(ca): State<SharedCa>, body: Bytes) -> Response {
+    let mut ca = ca.lock().unwrap();
+    let payload = match open_jws(&mut ca, "/finalize/1", &body) {
+        Ok(payload) => payload,
+        Err(response) => return *response,
+    };
+    assert_eq!(ca.order, "ready", "order finalized before it was ready");
+
+    let csr = decode_base64_url(payload["csr"].as_str().unwrap()).unwrap();
+    let csr = CertificateSigningRequest::from_der(&csr).unwrap();
+    let names: Vec<String> = csr
+        .params
+        .subject_alt_names
+        .iter()
+        .map(|name| match name {
+            SanType::DnsName(name) => name.clone(),
+            other => panic!("unexpected subject alt name {other:?}"),
+        })
+        .collect();
+    assert_eq!(names, ca.domains);
+
+    let leaf = csr.serialize_pem_with_signer(&ca.ca).unwrap();
+    let root = ca.ca.serialize_pem().unwrap();
+    ca.chain = Some(format!("{leaf}{root}"));
+    ca.order = "valid";
+
+    let order = ca.order();
+    reply(&mut ca, StatusCode::OK, None, order)
+}
+
+async fn certificate(State(ca): State<SharedCa>, body: Bytes) -> Response {
+    let mut ca = ca.lock().unwrap();
+    if let Err(response) = open_jws(&mut ca, "/cert/1", &body) {
+        return *response;
+    }
+
+    let chain = ca.chain.clone().unwrap();
+    let mut response = reply(&mut ca, StatusCode::OK, None, Value::Null);
+    *response.body_mut() = axum::body::boxed(axum::body::Full::from(chain));
+    response
+}
+
+fn thumbprint(jwk: &Value) -> String {
+    let canonical = format!(
+        r#"{{"crv":{},"kty":{},"x":{},"y":{}}}"#,
+        jwk["crv"], jwk["kty"], jwk["x"], jwk["y"]
+    );
+    encode_base64_url(digest::digest(&digest::SHA256, canonical.as_bytes()).as_ref())
+}
+
+fn serve(app: Router) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(app.into_make_service()),
+    );
+    addr
+}
+
+/// Starts the fake CA and the client's challenge responder, returning the CA and its config.
+fn start(challenges: &AcmeChallenges) -> (SharedCa, AcmeConfig) {
+    let challenge_server = serve(
+        Router::new()
+            .route(
+                "/.well-known/acme-challenge/:token",
+                get(acme::serve_challenge),
+            )
+            .with_state(challenges.clone()),
+    );
+
+    let mut params = CertificateParams::new(vec![]);
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca = Arc::new(Mutex::new(FakeCa {
+        base: String::new(),
+        challenge_server,
+        ca: Certificate::from_params(params).unwrap(),
+        nonces: HashSet::new(),
+        rejected_nonce: false,
+        accounts: Vec::new(),
+        jwk: None,
+        domains: Vec::new(),
+        token: String::new(),
+        authorization: "pending",
+        order: "pending",
+        chain: None,
+    }));
+
+    let addr = serve(
+        Router::new()
+            .route("/directory", get(directory))
+            .route("/nonce", head(new_nonce))
+            .route("/account", post(new_account))
+            .route("/order", post(new_order))
+            .route("/authz/1", post(authorization))
+            .route("/challenge/1", post(respond_to_challenge))
+            .route("/order/1", post(order))
+            .route("/finalize/1", post(finalize))
+            .route("/cert/1", post(certificate))
+            .with_state(ca.clone()),
+    );
+    ca.lock().unwrap().base = format!("http://{addr}");
+
+    let config = AcmeConfig {
+        directory_url: format!("http://{addr}/directory"),
+        domains: vec![DOMAIN.to_string()],
+        email: Some("ops@example.com".to_string()),
+        ca_cert: None,
+        renew_before_days: acme::DEFAULT_RENEW_BEFORE_DAYS,
+    };
+    (ca, config)
+}
+
+/// Public key of the leaf certificate in the chain at `path`.
+fn certificate_public_key(path: &std::path::Path) -> Vec<u8> {
+    let pem = fs::read(path).unwrap();
+    let (pem, _) = Pem::read(std::io::Cursor::new(pem.as_slice())).unwrap();
+    let certificate = pem.parse_x509().unwrap();
+    certificate.public_key().subject_public_key.data.to_vec()
+}
+
+#[tokio::test]
+async fn issues_certificates_over_acme() {
+    let dir = std::env::temp_dir().join(format!("appdist-acme-{}", Uuid::new_v4()));
+    let paths = CertificatePaths::new(&dir);
+    let challenges = AcmeChallenges::default();
+    let (ca, config) = start(&challenges);
+    assert!(acme::needs_renewal(&paths, config.renew_before_days));
+
+    acme::issue_certificate(&config, &challenges, &paths)
+        .await
+        .unwrap();
+
+    assert!(CertificateInfo::from_pem_file(&paths.cert).is_ok());
+    assert!(!acme::needs_renewal(&paths, config.renew_before_days));
+
+    // the served pair must match: the leaf was issued for the key written next to it
+    let key = KeyPair::from_pem(&fs::read_to_string(&paths.key).unwrap()).unwrap();
+    assert_eq!(certificate_public_key(&paths.cert), key.public_key_raw());
+
+    let leftovers: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".tmp"))
+        .collect();
+    assert!(leftovers.is_empty(), "temporary files left: {leftovers:?}");
+    assert!(dir.join("acme-account.key").exists());
+
+    // renewing reuses the account bound to the stored key and replaces the whole pair
+    let first_key = fs::read(&paths.key).unwrap();
+    acme::issue_certificate(&config, &challenges, &paths)
+        .await
+        .unwrap();
+    assert_eq!(ca.lock().unwrap().accounts.len(), 1);
+    assert_ne!(fs::read(&paths.key).unwrap(), first_key);
+    let key = KeyPair::from_pem(&fs::read_to_string(&paths.key).unwrap()).unwrap();
+    assert_eq!(certificate_public_key(&paths.cert), key.public_key_raw());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,622 @@
+#[macro_use]
+mod common;
+
+use app_repository_server::helpers::artifact::remove_incomplete_uploads;
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+};
+use serde_json::json;
+use std::fs;
+
+use common::{id, Backend, TestApp, PUBLIC_URL};
+
+async fn uploads_and_downloads_artifacts(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+
+    let fields = [("branch", "main"), ("identifier", "1.0.0")];
+    let (status, artifact) = app
+        .upload(&project_id, &fields, "wallet.apk", b"apk bytes")
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{artifact}");
+    assert_eq!(artifact["branch"], "main");
+    assert_eq!(artifact["size"], 9);
+    assert_eq!(artifact["projectId"]["$oid"], project_id.as_str());
+    let artifact_id = id(&artifact);
+
+    let (_, body) = app.get("/artifacts").await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert!(body["data"][0].get("qrcode").is_none());
+
+    let download = format!("/artifacts/{artifact_id}/download");
+    let response = app
+        .send(Request::get(&download).body(Body::empty()).unwrap())
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(&bytes[..], b"apk bytes");
+
+    let response = app
+        .send(Request::head(&download).body(Body::empty()).unwrap())
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_LENGTH], "9");
+    assert_eq!(
+        response.headers()[header::CONTENT_DISPOSITION],
+        "attachment; filename=wallet.apk"
+    );
+
+    let (status, _) = app
+        .get("/artifacts/64b7f0c2a1b2c3d4e5f60718/download")
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, body) = app
+        .upload("64b7f0c2a1b2c3d4e5f60718", &[], "a.apk", b"a")
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "not_found");
+}
+
+async fn refuses_uploads_while_draining(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+
+    app.draining.start();
+    let (status, body) = app.upload(&project_id, &[], "a.apk", b"a").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["code"], "shutting_down");
+    let (_, body) = app.get("/artifacts").await;
+    assert!(body["data"].as_array().unwrap().is_empty());
+    assert!(!app.uploads_path.join(&project_id).exists());
+}
+
+#[test]
+fn removes_incomplete_uploads() {
+    let root = std::env::temp_dir().join(format!("appdist-test-{}", uuid::Uuid::new_v4()));
+    let project = root.join("64b7f0c2a1b2c3d4e5f60718");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("complete.apk"), b"a").unwrap();
+    fs::write(project.join("interrupted.apk.part"), b"b").unwrap();
+
+    assert_eq!(remove_incomplete_uploads(&root).unwrap(), 1);
+    assert!(project.join("complete.apk").exists());
+    assert!(!project.join("interrupted.apk.part").exists());
+    assert_eq!(remove_incomplete_uploads(&root.join("missing")).unwrap(), 0);
+    fs::remove_dir_all(&root).unwrap();
+}
+
+async fn generates_ios_manifests(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+
+    let (status, body) = app.upload(&project_id, &[], "wallet.ipa", b"ipa").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_ios_metadata");
+
+    let fields = [
+        ("bundle_identifier", "com.example.wallet"),
+        ("bundle_version", "42"),
+    ];
+    let (status, artifact) = app.upload(&project_id, &fields, "wallet.ipa", b"ipa").await;
+    assert_eq!(status, StatusCode::CREATED, "{artifact}");
+    let artifact_id = id(&artifact);
+
+    let response = app
+        .send(
+            Request::get(format!("/artifacts/{artifact_id}/ios-plist"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let plist = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(plist.contains("com.example.wallet"));
+    assert!(plist.contains("Wallet"));
+    assert!(plist.contains(&format!("{PUBLIC_URL}/artifacts/{artifact_id}/download")));
+}
+
+async fn filters_and_groups_artifacts(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+
+    let ios = [
+        ("branch", "main"),
+        ("identifier", "ios-build"),
+        ("bundle_identifier", "com.example.wallet"),
+        ("bundle_version", "1"),
+    ];
+    for (fields, file_name) in [
+        (&[("branch", "main"), ("identifier", "one")][..], "a.apk"),
+        (&[("branch", "feature"), ("identifier", "two")][..], "b.aab"),
+        (&ios[..], "c.ipa"),
+    ] {
+        let (status, body) = app.upload(&project_id, fields, file_name, b"x").await;
+        assert_eq!(status, StatusCode::CREATED, "{body}");
+    }
+
+    let (_, body) = app.get("/artifacts?branch=main").await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+    let (_, body) = app.get("/artifacts?platform=android&sort=identifier").await;
+    let identifiers: Vec<&str> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|artifact| artifact["identifier"].as_str().unwrap())
+        .collect();
+    assert_eq!(identifiers, ["one", "two"]);
+    let (_, body) = app.get("/artifacts?extension=ipa").await;
+    assert_eq!(body["data"][0]["identifier"], "ios-build");
+
+    let (status, body) = app.get("/artifacts?sort=path").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_sort_field");
+
+    let (status, body) = app.get(&format!("/projects/{project_id}/artifacts")).await;
+    assert_eq!(status, StatusCode::OK);
+    let branches: Vec<(&str, usize)> = body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|group| {
+            (
+                group["_id"].as_str().unwrap(),
+                group["artifacts"].as_array().unwrap().len(),
+            )
+        })
+        .collect();
+    assert_eq!(branches, [("feature", 1), ("main", 2)]);
+}
+
+async fn serves_install_pages(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet <Beta>").await;
+    let fields = [("branch", "main"), ("identifier", "1.2.0")];
+    let (_, apk) = app.upload(&project_id, &fields, "wallet.apk", b"apk").await;
+    let fields = [
+        ("bundle_identifier", "com.example.wallet"),
+        ("bundle_version", "42"),
+    ];
+    let (_, ipa) = app.upload(&project_id, &fields, "wallet.ipa", b"ipa").await;
+
+    let html = app
+        .page(
+            &format!("/artifacts/{}/file", id(&apk)),
+            "Mozilla/5.0 (Linux; Android 14; Pixel 8)",
+        )
+        .await;
+    assert!(html.contains("Wallet &lt;Beta&gt;"));
+    assert!(html.contains("1.2.0"));
+    assert!(html.contains(&format!(
+        "href=\"{PUBLIC_URL}/artifacts/{}/download\" download=\"wallet.apk\"",
+        id(&apk)
+    )));
+    assert!(!html.contains("Open this page on your phone"));
+
+    let html = app
+        .page(
+            &format!("/artifacts/{}/file", id(&ipa)),
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X)",
+        )
+        .await;
+    assert!(html.contains(&format!(
+        "itms-services://?action=download-manifest&amp;url={PUBLIC_URL}/artifacts/{}/ios-plist",
+        id(&ipa)
+    )));
+    assert!(html.contains("<dd>42</dd>"));
+
+    let html = app
+        .page(
+            &format!("/artifacts/{}/file", id(&ipa)),
+            "Mozilla/5.0 (X11; Linux x86_64)",
+        )
+        .await;
+    assert!(html.contains("Open this page on your phone"));
+
+    let (status, _) = app.get("/artifacts/64b7f0c2a1b2c3d4e5f60718/file").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+async fn renders_qr_codes(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+    let fields = [("branch", "main"), ("identifier", "1.2.0")];
+    let (_, apk) = app.upload(&project_id, &fields, "wallet.apk", b"apk").await;
+    let qrcode = format!("/artifacts/{}/qrcode", id(&apk));
+
+    let response = app
+        .send(Request::get(&qrcode).body(Body::empty()).unwrap())
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/svg+xml");
+    assert_eq!(
+        response.headers()[header::CACHE_CONTROL],
+        "public, max-age=3600"
+    );
+    let etag = response.headers()[header::ETAG].clone();
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert!(String::from_utf8(bytes.to_vec()).unwrap().contains("<svg"));
+
+    let request = Request::get(&qrcode)
+        .header(header::IF_NONE_MATCH, &etag)
+        .body(Body::empty())
+        .unwrap();
+    let response = app.send(request).await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    let png = format!("{qrcode}?format=png&size=512&ecc=high");
+    let request = Request::get(&png)
+        .header(header::IF_NONE_MATCH, &etag)
+        .body(Body::empty())
+        .unwrap();
+    let response = app.send(request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+    assert_ne!(response.headers()[header::ETAG], etag);
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert!(bytes.starts_with(b"\x89PNG"));
+
+    let (status, body) = app.get(&format!("{qrcode}?size=8")).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "validation_failed");
+    assert_eq!(body["details"][0]["field"], "size");
+    let (status, _) = app.get("/artifacts/64b7f0c2a1b2c3d4e5f60718/qrcode").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+async fn redirect(app: &TestApp, uri: &str) -> String {
+    let response = app
+        .send(Request::get(uri).body(Body::empty()).unwrap())
+        .await;
+    assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT, "{uri}");
+    response.headers()[header::LOCATION]
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn links_to_latest_builds(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+    let develop = [("branch", "develop"), ("identifier", "1.0.0")];
+    app.upload(&project_id, &develop, "wallet.apk", b"old")
+        .await;
+    let (_, apk) = app
+        .upload(&project_id, &develop, "wallet.apk", b"new")
+        .await;
+    let main = [("branch", "main"), ("identifier", "0.9.0")];
+    app.upload(&project_id, &main, "wallet.apk", b"main").await;
+    let ios = [
+        ("branch", "develop"),
+        ("identifier", "1.0.0"),
+        ("bundle_identifier", "com.example.wallet"),
+        ("bundle_version", "7"),
+    ];
+    let (_, ipa) = app.upload(&project_id, &ios, "wallet.ipa", b"ipa").await;
+
+    let latest = format!("/projects/{project_id}/latest");
+    assert_eq!(
+        redirect(&app, &format!("{latest}?branch=develop&platform=android")).await,
+        format!("{PUBLIC_URL}/artifacts/{}/file", id(&apk))
+    );
+    assert_eq!(
+        redirect(
+            &app,
+            &format!("{latest}/download?branch=develop&platform=android")
+        )
+        .await,
+        format!("{PUBLIC_URL}/artifacts/{}/download", id(&apk))
+    );
+    assert_eq!(
+        redirect(&app, &format!("{latest}?branch=develop")).await,
+        format!("{PUBLIC_URL}/artifacts/{}/file", id(&ipa))
+    );
+
+    let response = app
+        .send(
+            Request::get(format!("{latest}/ios-plist?branch=develop"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let plist = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(plist.contains(&format!("/artifacts/{}/download", id(&ipa))));
+    assert!(plist.contains("com.example.wallet"));
+
+    let response = app
+        .send(
+            Request::get(format!("{latest}/qrcode?branch=develop&platform=ios"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/svg+xml");
+
+    let (status, _) = app.get(&format!("{latest}?branch=feature")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app
+        .get("/projects/64b7f0c2a1b2c3d4e5f60718/latest/qrcode")
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+async fn attaches_release_notes(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let owner = app.register("ada@example.com").await;
+    let other = app.register("grace@example.com").await;
+    let project_id = app.create_project(&owner, "Wallet").await;
+    let fields = [
+        ("identifier", "1.0.0"),
+        ("release_notes", "Fixes **login** <script>alert(1)</script>"),
+        ("commit_sha", "0123456789abcdef"),
+        ("build_url", "https://ci.example.com/builds/42"),
+        ("author", "Ada"),
+    ];
+    let (status, artifact) = app.upload(&project_id, &fields, "a.apk", b"x").await;
+    assert_eq!(status, StatusCode::CREATED, "{artifact}");
+    assert_eq!(artifact["buildInfo"]["commitSha"], "0123456789abcdef");
+
+    let (_, body) = app.get(&format!("/artifacts?projectId={project_id}")).await;
+    assert!(body["data"][0]["releaseNotes"]
+        .as_str()
+        .unwrap()
+        .starts_with("Fixes **login**"));
+    assert_eq!(body["data"][0]["buildInfo"]["author"], "Ada");
+
+    let page = app
+        .page(&format!("/artifacts/{}/file", id(&artifact)), "Android")
+        .await;
+    assert!(page.contains("<strong>login</strong>"));
+    assert!(page.contains("&lt;script&gt;"));
+    assert!(!page.contains("<script>"));
+    assert!(page.contains("0123456"));
+    assert!(page.contains("https://ci.example.com/builds/42"));
+
+    let uri = format!("/artifacts/{}", id(&artifact));
+    let changes = json!({ "releaseNotes": "Adds dark mode", "author": "" });
+    let (status, _) = app
+        .json(Method::PATCH, &uri, Some(&other), Some(changes.clone()))
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = app
+        .json(Method::PATCH, &uri, Some(&owner), Some(changes))
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, body) = app.get(&format!("/artifacts?projectId={project_id}")).await;
+    assert_eq!(body["data"][0]["releaseNotes"], "Adds dark mode");
+    assert!(body["data"][0]["buildInfo"]["author"].is_null());
+    assert_eq!(
+        body["data"][0]["buildInfo"]["commitSha"],
+        "0123456789abcdef"
+    );
+
+    let invalid = json!({ "commitSha": "not-a-sha", "buildUrl": "javascript:alert(1)" });
+    let (status, body) = app
+        .json(Method::PATCH, &uri, Some(&owner), Some(invalid))
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["details"].as_array().unwrap().len(), 2);
+}
+
+async fn tags_and_annotates_artifacts(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+    let fields = [
+        ("identifier", "1.0.0"),
+        ("tags", "staging, JIRA-1234"),
+        ("tags", "staging"),
+        ("metadata.flavor", "free"),
+        ("metadata.buildType", "release"),
+    ];
+    let (status, free) = app.upload(&project_id, &fields, "a.apk", b"x").await;
+    assert_eq!(status, StatusCode::CREATED, "{free}");
+    assert_eq!(free["tags"], json!(["staging", "JIRA-1234"]));
+    assert_eq!(free["metadata"]["flavor"], "free");
+    let fields = [
+        ("identifier", "1.0.0"),
+        ("tags", "prod"),
+        ("metadata.flavor", "paid"),
+    ];
+    let (_, paid) = app.upload(&project_id, &fields, "b.apk", b"y").await;
+
+    let ids = |body: &serde_json::Value| -> Vec<String> {
+        body["data"].as_array().unwrap().iter().map(id).collect()
+    };
+    let (_, body) = app.get("/artifacts?tag=staging").await;
+    assert_eq!(ids(&body), [id(&free)]);
+    let (_, body) = app.get("/artifacts?metadata=flavor:paid").await;
+    assert_eq!(ids(&body), [id(&paid)]);
+    let (_, body) = app.get("/artifacts?tag=prod&metadata=flavor:free").await;
+    assert!(ids(&body).is_empty());
+    let (status, body) = app.get("/artifacts?metadata=flavor").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_filter");
+
+    let uri = format!("/artifacts/{}", id(&paid));
+    let changes = json!({ "tags": ["staging"], "metadata": { "flavor": "paid", "env": "qa" } });
+    let (status, _) = app
+        .json(Method::PATCH, &uri, Some(&token), Some(changes))
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, body) = app.get("/artifacts?tag=staging&sort=createdAt").await;
+    assert_eq!(ids(&body), [id(&free), id(&paid)]);
+    let (_, body) = app.get("/artifacts?tag=prod").await;
+    assert!(ids(&body).is_empty());
+    let (_, body) = app.get("/artifacts?metadata=env:qa").await;
+    assert_eq!(ids(&body), [id(&paid)]);
+
+    let invalid = json!({ "tags": ["two words"], "metadata": { "build type": "debug" } });
+    let (status, body) = app
+        .json(Method::PATCH, &uri, Some(&token), Some(invalid))
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["details"].as_array().unwrap().len(), 2);
+}
+
+async fn searches_projects_and_artifacts(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+    app.create_project(&token, "Atlas").await;
+    let fields = [
+        ("branch", "feature/wallet-login"),
+        ("identifier", "JIRA-1234"),
+    ];
+    let (status, _) = app.upload(&project_id, &fields, "a.apk", b"x").await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, body) = app.get("/search?q=wallet").await;
+    assert_eq!(status, StatusCode::OK);
+    let kinds: Vec<&str> = body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| result["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, ["project", "artifact"]);
+    assert_eq!(body[0]["project"]["name"], "Wallet");
+
+    let (_, body) = app.get("/search?q=jira-1234").await;
+    assert_eq!(body[0]["artifact"]["identifier"], "JIRA-1234");
+
+    let fields = [("package_name", "org.sample.ledger")];
+    let (status, _) = app.upload(&project_id, &fields, "b.apk", b"y").await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (_, body) = app.get("/search?q=ledger").await;
+    assert_eq!(body.as_array().unwrap().len(), 1, "{body}");
+    assert_eq!(
+        body[0]["artifact"]["androidMetadata"]["packageName"],
+        "org.sample.ledger"
+    );
+
+    let (status, body) = app.get("/search?q=%20").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "missing_search_terms");
+}
+
+async fn ranks_search_results_relative_to_their_kind(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+    let body = json!({
+        "name": "Atlas",
+        "description": "Maps, with a wallet screen",
+        "platforms": ["android"],
+    });
+    let (status, _) = app
+        .json(Method::POST, "/projects", Some(&token), Some(body))
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let fields = [("branch", "wallet"), ("identifier", "wallet-2")];
+    app.upload(&project_id, &fields, "wallet.apk", b"x").await;
+
+    let (status, body) = app.get("/search?q=wallet").await;
+    assert_eq!(status, StatusCode::OK);
+    let results: Vec<(&str, f64)> = body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| {
+            let kind = result["kind"].as_str().unwrap();
+            (kind, result["score"].as_f64().unwrap())
+        })
+        .collect();
+
+    // the best match of each kind scores 1, whatever the scale of its index
+    assert_eq!(results[0], ("project", 1.0));
+    assert_eq!(results[1], ("artifact", 1.0));
+    assert_eq!(results[2].0, "project");
+    assert!(results[2].1 > 0.0 && results[2].1 < 1.0, "{body}");
+    assert_eq!(body[2]["project"]["name"], "Atlas");
+}
+
+async fn reports_readiness(backend: Backend) {
+    let app = TestApp::new(backend).await;
+
+    let (status, body) = app.json(Method::GET, "/readyz", None, None).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let check = body["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|check| check["name"] == backend.name())
+        .cloned();
+    assert_eq!(check.map(|check| check["ok"].clone()), Some(json!(true)));
+}
+
+async fn pages_through_artifacts_with_equal_sort_keys(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+
+    let mut uploaded = Vec::new();
+    for (branch, identifier) in [
+        ("main", "1"),
+        ("develop", "2"),
+        ("main", "3"),
+        ("main", "4"),
+        ("develop", "5"),
+    ] {
+        let fields = [("branch", branch), ("identifier", identifier)];
+        let (_, artifact) = app.upload(&project_id, &fields, "a.apk", b"apk").await;
+        uploaded.push(id(&artifact));
+    }
+
+    // ties on the sort field are broken by id, so no artifact is skipped or repeated
+    let mut seen = Vec::new();
+    let mut branches = Vec::new();
+    let mut uri = "/artifacts?limit=2&sort=branch".to_string();
+    loop {
+        let (status, body) = app.get(&uri).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        for artifact in body["data"].as_array().unwrap() {
+            seen.push(id(artifact));
+            branches.push(artifact["branch"].as_str().unwrap().to_string());
+        }
+        match body["pagination"]["nextCursor"].as_str() {
+            Some(cursor) => uri = format!("/artifacts?limit=2&sort=branch&cursor={cursor}"),
+            None => break,
+        }
+    }
+    assert_eq!(branches, ["develop", "develop", "main", "main", "main"]);
+    seen.sort();
+    uploaded.sort();
+    assert_eq!(seen, uploaded);
+
+    // a cursor only continues the sort it was issued for
+    let (_, body) = app.get("/artifacts?limit=2&sort=branch").await;
+    let cursor = body["pagination"]["nextCursor"].as_str().unwrap();
+    let (status, body) = app
+        .get(&format!("/artifacts?limit=2&sort=size&cursor={cursor}"))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_cursor");
+}
+
+backend_tests!(
+    uploads_and_downloads_artifacts,
+    refuses_uploads_while_draining,
+    generates_ios_manifests,
+    filters_and_groups_artifacts,
+    serves_install_pages,
+    renders_qr_codes,
+    links_to_latest_builds,
+    attaches_release_notes,
+    tags_and_annotates_artifacts,
+    searches_projects_and_artifacts,
+    ranks_search_results_relative_to_their_kind,
+    reports_readiness,
+    pages_through_artifacts_with_equal_sort_keys,
+);
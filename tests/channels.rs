@@ -0,0 +1,129 @@
+#[macro_use]
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use common::{id, Backend, TestApp};
+
+fn promote(artifact: &Value, note: &str) -> Value {
+    json!({ "artifactId": id(artifact), "note": note })
+}
+
+async fn promotes_builds_into_channels(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let owner = app.register("ada@example.com").await;
+    let qa = app.register("grace@example.com").await;
+    let project_id = app.create_project(&owner, "Wallet").await;
+    let channels = format!("/projects/{project_id}/channels");
+
+    let beta = json!({ "name": "beta" });
+    let (status, _) = app
+        .json(Method::POST, &channels, Some(&qa), Some(beta.clone()))
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, channel) = app
+        .json(Method::POST, &channels, Some(&owner), Some(beta.clone()))
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{channel}");
+    assert_eq!(channel["name"], "beta");
+    assert_eq!(channel["projectId"]["$oid"], project_id.as_str());
+
+    let (status, body) = app
+        .json(Method::POST, &channels, Some(&owner), Some(beta))
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["details"][0]["field"], "name");
+    let invalid = json!({ "name": "Release Candidate" });
+    let (status, _) = app
+        .json(Method::POST, &channels, Some(&owner), Some(invalid))
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let internal = json!({ "name": "internal" });
+    app.json(Method::POST, &channels, Some(&owner), Some(internal))
+        .await;
+
+    let fields = [("branch", "main"), ("identifier", "1.0.0")];
+    let (_, first) = app.upload(&project_id, &fields, "wallet.apk", b"1").await;
+    let fields = [("branch", "main"), ("identifier", "1.1.0")];
+    let (_, second) = app.upload(&project_id, &fields, "wallet.apk", b"2").await;
+
+    let latest = format!("{channels}/beta/latest");
+    let (status, _) = app.get(&latest).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let promotions = format!("{channels}/beta/promotions");
+    let (status, _) = app
+        .json(Method::POST, &promotions, None, Some(promote(&first, "")))
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, promotion) = app
+        .json(
+            Method::POST,
+            &promotions,
+            Some(&qa),
+            Some(promote(&first, "Smoke tested")),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{promotion}");
+    assert_eq!(promotion["channel"], "beta");
+    assert_eq!(promotion["note"], "Smoke tested");
+    assert!(promotion["promotedBy"]["$oid"].is_string());
+    app.json(
+        Method::POST,
+        &promotions,
+        Some(&qa),
+        Some(promote(&second, "Signed off")),
+    )
+    .await;
+
+    let (status, artifact) = app.get(&latest).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(id(&artifact), id(&second));
+
+    let (_, history) = app.get(&promotions).await;
+    let history = history.as_array().unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0]["artifactId"]["$oid"], id(&second).as_str());
+    assert_eq!(history[1]["artifactId"]["$oid"], id(&first).as_str());
+
+    let (_, list) = app.get(&channels).await;
+    let list = list.as_array().unwrap();
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0]["name"], "beta");
+    assert_eq!(
+        list[0]["latest"]["artifactId"]["$oid"],
+        id(&second).as_str()
+    );
+    assert_eq!(list[1]["name"], "internal");
+    assert!(list[1]["latest"].is_null());
+
+    let other_project = app.create_project(&owner, "Atlas").await;
+    let (_, foreign) = app.upload(&other_project, &fields, "atlas.apk", b"3").await;
+    let (status, body) = app
+        .json(
+            Method::POST,
+            &promotions,
+            Some(&qa),
+            Some(promote(&foreign, "")),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["details"][0]["field"], "artifactId");
+    let (status, _) = app.get(&format!("{channels}/production/promotions")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = app
+        .json(
+            Method::DELETE,
+            &format!("/projects/{project_id}"),
+            Some(&owner),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = app.get(&channels).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+backend_tests!(promotes_builds_into_channels);
@@ -0,0 +1,260 @@
+#![allow(dead_code)]
+
+use app_repository_server::{
+    config::{Config, DatabaseConfig, DuplicateIdentifierPolicy},
+    repositories::Repositories,
+    router,
+    state::{AppState, Draining},
+};
+use axum::{
+    body::Body,
+    http::{header, Method, Request, Response, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use std::{env, fs, path::PathBuf, sync::Arc, time::Duration};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+pub const PUBLIC_URL: &str = "https://dist.example.com";
+const BOUNDARY: &str = "appdist-test-boundary";
+
+/// Runs each listed test, an `async fn(Backend)` in the calling file, against every backend.
+macro_rules! backend_tests {
+    ($($test:ident),* $(,)?) => {
+        mod memory {
+            $(
+                #[tokio::test]
+                async fn $test() {
+                    super::$test($crate::common::Backend::Memory).await
+                }
+            )*
+        }
+
+        mod sqlite {
+            $(
+                #[tokio::test]
+                async fn $test() {
+                    super::$test($crate::common::Backend::Sqlite).await
+                }
+            )*
+        }
+    };
+}
+
+/// Storage the app under test runs on. Neither needs a server.
+#[derive(Clone, Copy)]
+pub enum Backend {
+    Memory,
+    Sqlite,
+}
+
+impl Backend {
+    /// Name of its readiness check.
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Memory => "memory",
+            Backend::Sqlite => "sqlite",
+        }
+    }
+}
+
+/// The full router on a fresh backend, with its own uploads directory.
+pub struct TestApp {
+    router: Router,
+    root: PathBuf,
+    pub uploads_path: PathBuf,
+    pub repos: Repositories,
+    pub draining: Draining,
+}
+
+impl TestApp {
+    pub async fn new(backend: Backend) -> TestApp {
+        TestApp::with_policy(backend, DuplicateIdentifierPolicy::Version).await
+    }
+
+    /// Like [`TestApp::new`], handling uploads with a taken identifier according to `policy`.
+    pub async fn with_policy(backend: Backend, policy: DuplicateIdentifierPolicy) -> TestApp {
+        let root = env::temp_dir().join(format!("appdist-test-{}", Uuid::new_v4()));
+        let uploads_path = root.join("uploads");
+        fs::create_dir_all(&uploads_path).expect("Couldn't create uploads directory");
+        let sqlite_path = root.join("appdist.sqlite");
+
+        let config = Config {
+            http_port: 3001,
+            https_port: 3002,
+            listen_addr: "127.0.0.1:3001".parse().unwrap(),
+            public_url: Some(PUBLIC_URL.to_string()),
+            trusted_proxies: vec![],
+            shutdown_timeout: Duration::from_secs(1),
+            database: match backend {
+                Backend::Memory => DatabaseConfig::Mongo {
+                    uri: "mongodb://localhost:27017".to_string(),
+                },
+                Backend::Sqlite => DatabaseConfig::Sqlite {
+                    path: sqlite_path.clone(),
+                },
+            },
+            uploads_path: uploads_path.clone(),
+            duplicate_identifier_policy: policy,
+            jwt_secret: "test-secret".to_string(),
+            metrics_token: None,
+            tls: None,
+        };
+        let repos = match backend {
+            Backend::Memory => Repositories::in_memory(),
+            Backend::Sqlite => Repositories::sqlite(&sqlite_path).unwrap(),
+        };
+        let draining = Draining::default();
+        let state = AppState {
+            config: Arc::new(config),
+            repos: repos.clone(),
+            tls: None,
+            draining: draining.clone(),
+        };
+
+        TestApp {
+            router: router(state).await,
+            root,
+            uploads_path,
+            repos,
+            draining,
+        }
+    }
+
+    pub async fn send(&self, request: Request<Body>) -> Response<Body> {
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let bytes = hyper::body::to_bytes(body).await.unwrap();
+        Response::from_parts(parts, Body::from(bytes))
+    }
+
+    /// Sends a request with an optional JSON body and returns the status and JSON response.
+    pub async fn json(
+        &self,
+        method: Method,
+        uri: &str,
+        token: Option<&str>,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+
+        let response = self.send(request).await;
+        let status = response.status();
+        let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = if bytes.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null)
+        };
+        (status, body)
+    }
+
+    pub async fn get(&self, uri: &str) -> (StatusCode, Value) {
+        self.json(Method::GET, uri, None, None).await
+    }
+
+    /// Fetches an HTML page as seen from a browser with this user agent.
+    pub async fn page(&self, uri: &str, user_agent: &str) -> String {
+        let request = Request::get(uri)
+            .header(header::USER_AGENT, user_agent)
+            .body(Body::empty())
+            .unwrap();
+        let response = self.send(request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html"));
+        let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    /// Registers a user and returns their token.
+    pub async fn register(&self, email: &str) -> String {
+        let body = json!({ "email": email, "name": email, "password": "secret" });
+        let (status, body) = self.json(Method::POST, "/users", None, Some(body)).await;
+        assert_eq!(status, StatusCode::CREATED, "{body}");
+        body["token"].as_str().unwrap().to_string()
+    }
+
+    /// Creates a project targeting both platforms and returns its id.
+    pub async fn create_project(&self, token: &str, name: &str) -> String {
+        let body = json!({
+            "name": name,
+            "description": format!("{name} description"),
+            "platforms": ["android", "ios"],
+        });
+        let (status, body) = self
+            .json(Method::POST, "/projects", Some(token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{body}");
+        id(&body)
+    }
+
+    /// Uploads an artifact with the given text fields and file.
+    pub async fn upload(
+        &self,
+        project_id: &str,
+        fields: &[(&str, &str)],
+        file_name: &str,
+        content: &[u8],
+    ) -> (StatusCode, Value) {
+        let mut body = Vec::new();
+        for (name, value) in fields {
+            body.extend_from_slice(
+                format!(
+                    "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+                )
+                .as_bytes(),
+            );
+        }
+        body.extend_from_slice(
+            format!(
+                "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{file_name}\"\r\nContent-Type: application/octet-stream\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(content);
+        body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/projects/{project_id}/artifacts"))
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(Body::from(body))
+            .unwrap();
+
+        let response = self.send(request).await;
+        let status = response.status();
+        let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Ids are serialized as extended JSON object ids.
+pub fn id(value: &Value) -> String {
+    value["_id"]["$oid"].as_str().unwrap().to_string()
+}
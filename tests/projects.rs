@@ -0,0 +1,204 @@
+#[macro_use]
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::{id, Backend, TestApp};
+
+async fn creates_updates_and_lists_projects(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+    app.create_project(&token, "Atlas").await;
+
+    let changes = json!({ "name": "  Wallet Beta ", "platforms": ["ios"] });
+    let (status, _) = app
+        .json(
+            Method::PATCH,
+            &format!("/projects/{project_id}"),
+            None,
+            Some(changes),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, body) = app.get(&format!("/projects/{project_id}")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["name"], "Wallet Beta");
+    assert_eq!(body["description"], "Wallet description");
+    assert_eq!(body["platforms"], json!(["ios"]));
+
+    let (status, body) = app.get("/projects").await;
+    assert_eq!(status, StatusCode::OK);
+    let names: Vec<&str> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|project| project["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["Atlas", "Wallet Beta"]);
+
+    let (_, body) = app.get("/projects?platform=android").await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["name"], "Atlas");
+}
+
+async fn validates_project_input(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    app.create_project(&token, "Wallet").await;
+
+    let invalid = json!({ "name": " ", "description": "", "platforms": [] });
+    let (status, body) = app
+        .json(Method::POST, "/projects", Some(&token), Some(invalid))
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "validation_failed");
+    assert_eq!(body["details"].as_array().unwrap().len(), 2);
+
+    let duplicate = json!({ "name": "Wallet ", "description": "", "platforms": ["ios"] });
+    let (status, body) = app
+        .json(Method::POST, "/projects", Some(&token), Some(duplicate))
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["details"][0]["field"], "name");
+
+    let (status, body) = app.get("/projects/not-an-id").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_object_id");
+
+    let (status, body) = app.get("/projects/64b7f0c2a1b2c3d4e5f60718").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "not_found");
+}
+
+async fn archives_projects_and_refuses_their_uploads(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let owner = app.register("ada@example.com").await;
+    let other = app.register("bob@example.com").await;
+    let project_id = app.create_project(&owner, "Wallet").await;
+    let archive = format!("/projects/{project_id}/archive");
+
+    let (status, _) = app.json(Method::POST, &archive, Some(&other), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = app.json(Method::POST, &archive, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (_, body) = app.get("/projects").await;
+    assert_eq!(body["data"], json!([]));
+    let (_, body) = app.get("/projects?includeArchived=true").await;
+    assert_eq!(body["data"][0]["archived"], true);
+
+    let (status, body) = app.upload(&project_id, &[], "app.apk", b"apk").await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["code"], "project_archived");
+
+    let (status, _) = app.json(Method::DELETE, &archive, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = app.upload(&project_id, &[], "app.apk", b"apk").await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+async fn deletes_projects_with_their_artifacts_and_favorites(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+
+    let (status, _) = app.upload(&project_id, &[], "app.apk", b"apk").await;
+    assert_eq!(status, StatusCode::CREATED);
+    let favorite = json!({ "projectId": project_id });
+    let (status, _) = app
+        .json(
+            Method::PATCH,
+            "/users/favorite-projects",
+            Some(&token),
+            Some(favorite),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, body) = app.json(Method::GET, "/users/me", Some(&token), None).await;
+    assert_eq!(body["favoriteProjects"], json!([project_id]));
+
+    let (status, _) = app
+        .json(
+            Method::DELETE,
+            &format!("/projects/{project_id}"),
+            Some(&token),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = app.get(&format!("/projects/{project_id}")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, body) = app.get("/artifacts").await;
+    assert_eq!(body["data"], json!([]));
+    let (_, body) = app.json(Method::GET, "/users/me", Some(&token), None).await;
+    assert_eq!(body["favoriteProjects"], json!([]));
+    assert!(!app.uploads_path.join(&project_id).exists());
+}
+
+async fn removes_project_images(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+    let image = format!("/projects/{project_id}/image");
+
+    let (status, _) = app.json(Method::DELETE, &image, None, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, body) = app.get(&format!("/projects/{project_id}")).await;
+    assert_eq!(id(&body), project_id);
+    assert_eq!(body["image"], "");
+
+    let (status, _) = app
+        .json(
+            Method::DELETE,
+            "/projects/64b7f0c2a1b2c3d4e5f60718/image",
+            None,
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+async fn pages_through_projects(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    for name in ["Cargo", "Atlas", "Beacon"] {
+        app.create_project(&token, name).await;
+    }
+
+    let (status, body) = app.get("/projects?limit=2").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["name"], "Atlas");
+    assert_eq!(body["data"][1]["name"], "Beacon");
+    assert_eq!(body["pagination"]["hasMore"], true);
+
+    let cursor = body["pagination"]["nextCursor"].as_str().unwrap();
+    let (_, body) = app.get(&format!("/projects?limit=2&cursor={cursor}")).await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["name"], "Cargo");
+    assert_eq!(body["pagination"]["hasMore"], false);
+    assert!(body["pagination"]["nextCursor"].is_null());
+
+    // the limit is clamped to 1..=200 rather than rejected
+    let (_, body) = app.get("/projects?limit=0").await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    let (_, body) = app.get("/projects?limit=1000").await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 3);
+
+    let (status, body) = app.get("/projects?cursor=garbage").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_cursor");
+}
+
+backend_tests!(
+    creates_updates_and_lists_projects,
+    validates_project_input,
+    archives_projects_and_refuses_their_uploads,
+    deletes_projects_with_their_artifacts_and_favorites,
+    removes_project_images,
+    pages_through_projects,
+);
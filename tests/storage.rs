@@ -0,0 +1,211 @@
+#[macro_use]
+mod common;
+
+use app_repository_server::{config::DuplicateIdentifierPolicy, helpers::storage::repair_storage};
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode},
+};
+use serde_json::json;
+use std::{fs, path::Path};
+
+use common::{id, Backend, TestApp};
+
+const FIELDS: [(&str, &str); 2] = [("branch", "main"), ("identifier", "1.0.0")];
+
+async fn download(app: &TestApp, artifact_id: &str) -> (StatusCode, Vec<u8>) {
+    let uri = format!("/artifacts/{artifact_id}/download");
+    let response = app
+        .send(Request::get(&uri).body(Body::empty()).unwrap())
+        .await;
+    let status = response.status();
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, bytes.to_vec())
+}
+
+async fn keeps_every_version_by_default(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+
+    let (_, first) = app.upload(&project_id, &FIELDS, "a.apk", b"first").await;
+    let (status, second) = app.upload(&project_id, &FIELDS, "a.apk", b"second").await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_ne!(first["path"], second["path"]);
+    assert_eq!(download(&app, &id(&first)).await.1, b"first");
+    assert_eq!(download(&app, &id(&second)).await.1, b"second");
+}
+
+async fn rejects_duplicate_identifiers(backend: Backend) {
+    let app = TestApp::with_policy(backend, DuplicateIdentifierPolicy::Reject).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+
+    app.upload(&project_id, &FIELDS, "a.apk", b"first").await;
+    let (status, body) = app.upload(&project_id, &FIELDS, "a.apk", b"second").await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["code"], "duplicate_identifier");
+
+    let (status, _) = app.upload(&project_id, &FIELDS, "a.aab", b"bundle").await;
+    assert_eq!(status, StatusCode::CREATED);
+    let fields = [("branch", "develop"), ("identifier", "1.0.0")];
+    let (status, _) = app.upload(&project_id, &fields, "a.apk", b"develop").await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+async fn replaces_duplicate_identifiers(backend: Backend) {
+    let app = TestApp::with_policy(backend, DuplicateIdentifierPolicy::Replace).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+    let channels = format!("/projects/{project_id}/channels");
+    app.json(
+        Method::POST,
+        &channels,
+        Some(&token),
+        Some(json!({ "name": "beta" })),
+    )
+    .await;
+
+    let (_, first) = app.upload(&project_id, &FIELDS, "a.apk", b"first").await;
+    let promotion = json!({ "artifactId": id(&first) });
+    let (status, _) = app
+        .json(
+            Method::POST,
+            &format!("{channels}/beta/promotions"),
+            Some(&token),
+            Some(promotion),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, second) = app.upload(&project_id, &FIELDS, "a.apk", b"second").await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (_, body) = app.get("/artifacts").await;
+    let data = body["data"].as_array().unwrap();
+    assert_eq!(data.len(), 1);
+    assert_eq!(id(&data[0]), id(&second));
+    assert!(!Path::new(first["path"].as_str().unwrap()).exists());
+    assert_eq!(download(&app, &id(&first)).await.0, StatusCode::NOT_FOUND);
+
+    let (_, latest) = app.get(&format!("{channels}/beta/latest")).await;
+    assert_eq!(id(&latest), id(&second));
+}
+
+async fn repairs_shared_files(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+    let (_, older) = app.upload(&project_id, &FIELDS, "a.apk", b"older").await;
+    let (_, newer) = app.upload(&project_id, &FIELDS, "a.apk", b"newer").await;
+    let (_, single) = app.upload(&project_id, &[], "b.apk", b"single").await;
+
+    // the layout before files were named after artifacts, where the newer upload overwrote
+    // the older one's file
+    let branch_path = app.uploads_path.join(&project_id).join("main");
+    fs::create_dir_all(&branch_path).unwrap();
+    let shared = branch_path.join("1.0.0.apk");
+    fs::write(&shared, b"newer").unwrap();
+    let legacy = app.uploads_path.join(&project_id).join("develop");
+    fs::create_dir_all(&legacy).unwrap();
+    let unidentified = legacy.join("unidentified.apk");
+    fs::write(&unidentified, b"single").unwrap();
+    for (artifact, path) in [
+        (&older, &shared),
+        (&newer, &shared),
+        (&single, &unidentified),
+    ] {
+        fs::remove_file(artifact["path"].as_str().unwrap()).unwrap();
+        app.repos
+            .artifacts
+            .set_path(&id(artifact), path.to_str().unwrap())
+            .await
+            .unwrap();
+    }
+
+    let repair = repair_storage(&app.repos, &app.uploads_path, true, false)
+        .await
+        .unwrap();
+    assert_eq!(repair.moved.len(), 2);
+    assert!(shared.exists());
+    assert_eq!(download(&app, &id(&newer)).await.1, b"newer");
+
+    let repair = repair_storage(&app.repos, &app.uploads_path, false, false)
+        .await
+        .unwrap();
+    let mut moved = repair.moved.clone();
+    moved.sort();
+    let mut expected = vec![id(&newer), id(&single)];
+    expected.sort();
+    assert_eq!(moved, expected);
+    assert_eq!(repair.overwritten, [(id(&older), id(&newer))]);
+    assert!(repair.missing.is_empty());
+    assert!(!shared.exists());
+
+    assert_eq!(download(&app, &id(&newer)).await.1, b"newer");
+    assert_eq!(download(&app, &id(&single)).await.1, b"single");
+    assert_eq!(download(&app, &id(&older)).await.0, StatusCode::NOT_FOUND);
+
+    let repair = repair_storage(&app.repos, &app.uploads_path, false, false)
+        .await
+        .unwrap();
+    assert!(repair.moved.is_empty() && repair.overwritten.is_empty());
+    // the overwritten artifact now points at a file of its own that doesn't exist
+    assert_eq!(repair.missing, [id(&older)]);
+
+    let repair = repair_storage(&app.repos, &app.uploads_path, true, true)
+        .await
+        .unwrap();
+    assert_eq!(repair.deleted, [id(&older)]);
+    let (_, body) = app.get("/artifacts").await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 3);
+
+    let repair = repair_storage(&app.repos, &app.uploads_path, false, true)
+        .await
+        .unwrap();
+    assert_eq!(repair.deleted, [id(&older)]);
+    let (_, body) = app.get("/artifacts").await;
+    let mut remaining: Vec<String> = body["data"].as_array().unwrap().iter().map(id).collect();
+    remaining.sort();
+    assert_eq!(remaining, expected);
+
+    let repair = repair_storage(&app.repos, &app.uploads_path, false, false)
+        .await
+        .unwrap();
+    assert!(repair.missing.is_empty() && repair.deleted.is_empty());
+}
+
+// the metrics are global, so this runs once rather than for every backend
+#[tokio::test]
+async fn measures_project_storage_on_disk() {
+    let app = TestApp::new(Backend::Memory).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+    app.upload(&project_id, &FIELDS, "a.apk", b"12345").await;
+    // a file the database knows nothing about, like one left by an overwritten upload
+    let legacy = app.uploads_path.join(&project_id).join("main");
+    fs::create_dir_all(&legacy).unwrap();
+    fs::write(legacy.join("1.0.0.apk"), b"123").unwrap();
+
+    let response = app
+        .send(Request::get("/metrics").body(Body::empty()).unwrap())
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let metrics = String::from_utf8(bytes.to_vec()).unwrap();
+    let label = format!("{{project_id=\"{project_id}\"}}");
+    assert!(
+        metrics.contains(&format!("project_storage_bytes{label} 8")),
+        "{metrics}"
+    );
+    assert!(
+        metrics.contains(&format!("project_artifacts{label} 1")),
+        "{metrics}"
+    );
+}
+
+backend_tests!(
+    keeps_every_version_by_default,
+    rejects_duplicate_identifiers,
+    replaces_duplicate_identifiers,
+    repairs_shared_files,
+);
@@ -0,0 +1,95 @@
+#[macro_use]
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::{id, Backend, TestApp, PUBLIC_URL};
+
+async fn checks_for_updates(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+    let android = |identifier, version_code| {
+        [
+            ("branch", "main"),
+            ("identifier", identifier),
+            ("package_name", "com.example.wallet"),
+            ("version_code", version_code),
+        ]
+    };
+    let (_, stable) = app
+        .upload(&project_id, &android("1.2.0", "12"), "wallet.apk", b"a")
+        .await;
+    assert_eq!(
+        stable["androidMetadata"]["packageName"],
+        "com.example.wallet"
+    );
+    let (_, newest) = app
+        .upload(&project_id, &android("1.10.0", "20"), "wallet.apk", b"b")
+        .await;
+    let ios = [
+        ("identifier", "9.9.9"),
+        ("bundle_identifier", "com.example.wallet"),
+        ("bundle_version", "99"),
+    ];
+    app.upload(&project_id, &ios, "wallet.ipa", b"c").await;
+
+    let check = "/updates/check?platform=android&bundleIdentifier=com.example.wallet";
+    let (status, body) = app.get(&format!("{check}&version=1.9.3&build=19")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["updateAvailable"], true);
+    assert_eq!(body["update"]["version"], "1.10.0");
+    assert_eq!(body["update"]["build"], "20");
+    assert_eq!(
+        body["update"]["installUrl"],
+        format!("{PUBLIC_URL}/artifacts/{}/download", id(&newest))
+    );
+
+    let (_, body) = app.get(&format!("{check}&version=1.10.0&build=19")).await;
+    assert_eq!(body["updateAvailable"], true);
+    let (_, body) = app.get(&format!("{check}&version=1.10.0&build=20")).await;
+    assert_eq!(body["updateAvailable"], false);
+    assert!(body["update"].is_null());
+    let (_, body) = app
+        .get("/updates/check?platform=android&bundleIdentifier=com.example.other&version=1.0")
+        .await;
+    assert_eq!(body["updateAvailable"], false);
+
+    let channels = format!("/projects/{project_id}/channels");
+    let stable_channel = json!({ "name": "stable" });
+    app.json(Method::POST, &channels, Some(&token), Some(stable_channel))
+        .await;
+    let (_, body) = app
+        .get(&format!("{check}&version=1.0.0&channel=stable"))
+        .await;
+    assert_eq!(body["updateAvailable"], false);
+
+    let promotion = json!({ "artifactId": id(&stable) });
+    app.json(
+        Method::POST,
+        &format!("{channels}/stable/promotions"),
+        Some(&token),
+        Some(promotion),
+    )
+    .await;
+    let (_, body) = app
+        .get(&format!("{check}&version=1.0.0&channel=stable"))
+        .await;
+    assert_eq!(body["updateAvailable"], true);
+    assert_eq!(body["update"]["artifactId"], id(&stable).as_str());
+    let (_, body) = app
+        .get(&format!("{check}&version=1.2.0&build=12&channel=stable"))
+        .await;
+    assert_eq!(body["updateAvailable"], false);
+
+    let (status, _) = app
+        .get(&format!("{check}&version=1.0.0&channel=nightly"))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, body) = app.get("/updates/check?platform=ios").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["details"].as_array().unwrap().len(), 2);
+}
+
+backend_tests!(checks_for_updates);
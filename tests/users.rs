@@ -0,0 +1,77 @@
+#[macro_use]
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::{Backend, TestApp};
+
+async fn registers_logs_in_and_returns_the_current_user(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    app.register("ada@example.com").await;
+
+    let login = json!({ "email": "ada@example.com", "password": "secret" });
+    let (status, body) = app
+        .json(Method::POST, "/users/login", None, Some(login))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let token = body["token"].as_str().unwrap();
+
+    let (status, body) = app.json(Method::GET, "/users/me", Some(token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["name"], "ada@example.com");
+    assert_eq!(body["favoriteProjects"], json!([]));
+}
+
+async fn rejects_duplicate_emails_and_wrong_passwords(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    app.register("ada@example.com").await;
+
+    let user = json!({ "email": "ada@example.com", "name": "Ada", "password": "other" });
+    let (status, body) = app.json(Method::POST, "/users", None, Some(user)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "user_already_registered");
+
+    let login = json!({ "email": "ada@example.com", "password": "wrong" });
+    let (status, body) = app
+        .json(Method::POST, "/users/login", None, Some(login))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_credentials");
+
+    let (status, body) = app.get("/users/me").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "unauthorized");
+}
+
+async fn pages_through_users(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    for email in ["c@example.com", "a@example.com", "b@example.com"] {
+        app.register(email).await;
+    }
+
+    let (status, body) = app.get("/users?limit=2&sort=-email").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["email"], "c@example.com");
+    assert_eq!(body["data"][1]["email"], "b@example.com");
+    assert_eq!(body["pagination"]["hasMore"], true);
+
+    let cursor = body["pagination"]["nextCursor"].as_str().unwrap();
+    let (status, body) = app
+        .get(&format!("/users?limit=2&sort=-email&cursor={cursor}"))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["email"], "a@example.com");
+    assert_eq!(body["pagination"]["hasMore"], false);
+
+    let (status, body) = app.get(&format!("/users?cursor={cursor}")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_cursor");
+}
+
+backend_tests!(
+    registers_logs_in_and_returns_the_current_user,
+    rejects_duplicate_emails_and_wrong_passwords,
+    pages_through_users,
+);
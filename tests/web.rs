@@ -0,0 +1,141 @@
+#[macro_use]
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+};
+use serde_json::json;
+
+use common::{id, Backend, TestApp, PUBLIC_URL};
+
+const BROWSER: &str = "Mozilla/5.0 (X11; Linux x86_64)";
+
+/// A session cookie as a browser keeps it: only sent to paths under its `Path` attribute.
+struct StoredCookie {
+    pair: String,
+    path: String,
+}
+
+impl StoredCookie {
+    fn from_set_cookie(set_cookie: &str) -> StoredCookie {
+        let mut attributes = set_cookie.split(';').map(str::trim);
+        let pair = attributes.next().unwrap().to_string();
+        let path = attributes
+            .find_map(|attribute| attribute.strip_prefix("Path="))
+            .unwrap_or("/")
+            .to_string();
+        StoredCookie { pair, path }
+    }
+
+    /// Attaches the cookie if the browser would send it to `uri` (RFC 6265 path-match).
+    fn attach(&self, request: Request<Body>) -> Request<Body> {
+        let path = request.uri().path();
+        let matches = path == self.path
+            || (path.starts_with(&self.path)
+                && (self.path.ends_with('/') || path[self.path.len()..].starts_with('/')));
+        let (mut parts, body) = request.into_parts();
+        if matches {
+            parts
+                .headers
+                .insert(header::COOKIE, self.pair.parse().unwrap());
+        }
+        Request::from_parts(parts, body)
+    }
+}
+
+async fn html(app: &TestApp, request: Request<Body>) -> String {
+    let response = app.send(request).await;
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+async fn browses_projects_and_builds(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+    app.create_project(&token, "Atlas").await;
+    let fields = [("branch", "main"), ("identifier", "1.2.0")];
+    let (_, artifact) = app.upload(&project_id, &fields, "wallet.apk", b"apk").await;
+    let artifact_id = id(&artifact);
+
+    let html = app.page("/web", BROWSER).await;
+    let atlas = html.find("Atlas").unwrap();
+    let wallet = html.find("Wallet").unwrap();
+    assert!(atlas < wallet);
+    assert!(html.contains(&format!("href=\"/web/projects/{project_id}\"")));
+    assert!(html.contains("Log in"));
+
+    let html = app
+        .page(&format!("/web/projects/{project_id}"), BROWSER)
+        .await;
+    assert!(html.contains("<h2>main</h2>"));
+    assert!(html.contains("1.2.0"));
+    assert!(html.contains(&format!("src=\"/artifacts/{artifact_id}/qrcode\"")));
+    assert!(html.contains(&format!(
+        "href=\"{PUBLIC_URL}/artifacts/{artifact_id}/download\""
+    )));
+
+    let (status, _) = app.get("/web/projects/64b7f0c2a1b2c3d4e5f60718").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+async fn logs_in_with_a_session_cookie(backend: Backend) {
+    let app = TestApp::new(backend).await;
+    let token = app.register("ada@example.com").await;
+    let project_id = app.create_project(&token, "Wallet").await;
+    let favorite = json!({ "projectId": project_id });
+    app.json(
+        Method::PATCH,
+        "/users/favorite-projects",
+        Some(&token),
+        Some(favorite),
+    )
+    .await;
+
+    let login = |password: &str| {
+        Request::post("/web/login")
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(format!(
+                "email=ada%40example.com&password={password}"
+            )))
+            .unwrap()
+    };
+
+    let response = app.send(login("wrong")).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(response.headers().get(header::SET_COOKIE).is_none());
+
+    let response = app.send(login("secret")).await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(response.headers()[header::LOCATION], "/web");
+    let set_cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+    assert!(set_cookie.contains("HttpOnly"));
+    assert!(set_cookie.contains("Secure"));
+    let cookie = StoredCookie::from_set_cookie(set_cookie);
+
+    let request = Request::get("/web").body(Body::empty()).unwrap();
+    let page = html(&app, cookie.attach(request)).await;
+    assert!(page.contains("<h2>Favorites</h2>"));
+    assert!(page.contains("Log out"));
+
+    // install pages opened from a QR code live outside /web and still see the session
+    let fields = [("branch", "main"), ("identifier", "1.0.0")];
+    let (_, artifact) = app.upload(&project_id, &fields, "a.apk", b"apk").await;
+    let request = Request::get(format!("/artifacts/{}/file", id(&artifact)))
+        .body(Body::empty())
+        .unwrap();
+    let page = html(&app, cookie.attach(request)).await;
+    assert!(page.contains("ada@example.com"));
+    assert!(page.contains("Log out"));
+
+    let request = Request::post("/web/logout").body(Body::empty()).unwrap();
+    let response = app.send(cookie.attach(request)).await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    let cleared = response.headers()[header::SET_COOKIE].to_str().unwrap();
+    assert!(cleared.contains("Max-Age=0"));
+    // clearing only works on the path the cookie was set for
+    assert_eq!(StoredCookie::from_set_cookie(cleared).path, cookie.path);
+}
+
+backend_tests!(browses_projects_and_builds, logs_in_with_a_session_cookie);